@@ -0,0 +1,28 @@
+//! Prints row structs for the tables of a MIB module.
+//!
+//! Usage: mibgen <mib-dir> <module>, e.g.
+//! `cargo run --example mibgen -- /usr/share/snmp/mibs IF-MIB > src/if_mib.rs`
+
+extern crate snmplib;
+
+use std::env;
+use std::process;
+
+use snmplib::mib::Mib;
+
+fn main() {
+    let args: Vec<String> = env::args().collect();
+    if args.len() != 3 {
+        eprintln!("usage: {} <mib-dir> <module>", args[0]);
+        process::exit(2);
+    }
+    let mut mib = Mib::new();
+    let generated = mib.load_dir(&args[1]).and_then(|_| mib.generate_tables(&args[2]));
+    match generated {
+        Ok(source) => print!("{}", source),
+        Err(err) => {
+            eprintln!("{}", err);
+            process::exit(1);
+        }
+    }
+}
@@ -0,0 +1,400 @@
+//! Community-based (SNMPv1 and SNMPv2c) agent.
+//!
+//! An `Agent` answers requests from a UDP socket by dispatching each
+//! variable binding to the `MibHandler` registered for the subtree it falls
+//! in. Handlers only deal with names and values; the agent takes care of
+//! decoding, community checks, lexicographic ordering across handlers,
+//! GetBulk repetitions, message size limits and error-status/error-index
+//! reporting.
+//!
+//! ```no_run
+//! use snmplib::{Oid, OwnedValue};
+//! use snmplib::agent::{Agent, HandlerResult, MibHandler};
+//!
+//! /// sysDescr.0 and nothing else.
+//! struct SysDescr;
+//!
+//! const SYS_DESCR: &[u32] = &[1, 3, 6, 1, 2, 1, 1, 1, 0];
+//!
+//! impl MibHandler for SysDescr {
+//!     fn get(&mut self, name: &[u32]) -> HandlerResult<OwnedValue> {
+//!         if name == SYS_DESCR {
+//!             Ok(OwnedValue::OctetString(b"appliance".to_vec()))
+//!         } else {
+//!             Ok(OwnedValue::NoSuchInstance)
+//!         }
+//!     }
+//!
+//!     fn get_next(&mut self, name: &[u32]) -> HandlerResult<Option<(Oid, OwnedValue)>> {
+//!         if name < SYS_DESCR {
+//!             Ok(Some((Oid::from(SYS_DESCR), OwnedValue::OctetString(b"appliance".to_vec()))))
+//!         } else {
+//!             Ok(None)
+//!         }
+//!     }
+//! }
+//!
+//! let mut agent = Agent::bind("0.0.0.0:161", b"public").unwrap();
+//! agent.register(&[1, 3, 6, 1, 2, 1, 1, 1], SysDescr);
+//! agent.run().unwrap();
+//! ```
+
+use std::io;
+use std::net::{SocketAddr, ToSocketAddrs, UdpSocket};
+
+use super::BUFFER_SIZE;
+use super::oid::Oid;
+use super::pdu;
+use super::snmp::*;
+use super::value::{OwnedValue, Value};
+
+/// The result of a handler operation. An error is an SNMPv2 error-status
+/// such as `ERRSTATUS_NOTWRITABLE`; the agent maps it for SNMPv1 requests.
+pub type HandlerResult<T> = Result<T, u32>;
+
+/// Serves the objects of one OID subtree.
+pub trait MibHandler: Send {
+    /// The value of instance `name`, or `NoSuchObject`/`NoSuchInstance`.
+    fn get(&mut self, name: &[u32]) -> HandlerResult<OwnedValue>;
+
+    /// The first instance in this handler's subtree that follows `name` in
+    /// lexicographic order, or `None` if there is none. `name` may precede
+    /// the subtree.
+    fn get_next(&mut self, name: &[u32]) -> HandlerResult<Option<(Oid, OwnedValue)>>;
+
+    /// Checks that `value` can be assigned to instance `name` without
+    /// assigning it: the first phase of a SetRequest, run for every binding
+    /// before any is committed. Errors such as wrongType, wrongValue or
+    /// inconsistentValue are reported as they are. Handlers of read-only
+    /// objects can rely on the default, which reports notWritable.
+    fn test_set(&mut self, name: &[u32], value: &OwnedValue) -> HandlerResult<()> {
+        let _ = (name, value);
+        Err(ERRSTATUS_NOTWRITABLE)
+    }
+
+    /// Assigns a value that passed `test_set`. If this fails, the bindings
+    /// of the request committed so far are undone and commitFailed is
+    /// reported whatever the error.
+    fn commit_set(&mut self, name: &[u32], value: &OwnedValue) -> HandlerResult<()> {
+        let _ = (name, value);
+        Err(ERRSTATUS_COMMITFAILED)
+    }
+
+    /// Reverts a successful `commit_set` of `name` to the value it had
+    /// before, because a later binding of the same request failed to
+    /// commit. If this fails, undoFailed is reported.
+    fn undo_set(&mut self, name: &[u32]) -> HandlerResult<()> {
+        let _ = name;
+        Err(ERRSTATUS_UNDOFAILED)
+    }
+}
+
+/// An SNMP agent serving the handlers registered with it.
+pub struct Agent {
+    socket: UdpSocket,
+    read_community: Vec<u8>,
+    write_community: Option<Vec<u8>>,
+    /// Non-overlapping subtrees in lexicographic order.
+    handlers: Vec<(Oid, Box<dyn MibHandler>)>,
+    max_message_size: usize,
+    recv_buf: [u8; BUFFER_SIZE],
+    send_buf: pdu::Buf,
+}
+
+/// A decoded request.
+struct Request {
+    version: i64,
+    community: Vec<u8>,
+    message_type: SnmpMessageType,
+    req_id: i32,
+    /// For GetBulkRequests, which carry these in place of the error fields.
+    non_repeaters: u32,
+    max_repetitions: u32,
+    varbinds: Vec<(Oid, OwnedValue)>,
+}
+
+impl Request {
+    fn from_bytes(bytes: &[u8]) -> Option<Request> {
+        let pdu = SnmpPdu::from_bytes(bytes).ok()?;
+        let varbinds = pdu.varbinds.clone()
+            .map(|(name, value)| Some((Oid::from(name.to_vec().ok()?), value.into_owned().ok()?)))
+            .collect::<Option<Vec<_>>>()?;
+        Some(Request {
+            version: pdu.version(),
+            community: pdu.community.to_vec(),
+            message_type: pdu.message_type,
+            req_id: pdu.req_id,
+            non_repeaters: pdu.error_status,
+            max_repetitions: pdu.error_index,
+            varbinds,
+        })
+    }
+}
+
+const MIN_MESSAGE_SIZE: usize = 484;
+
+/// Bytes the error-index and the three length fields enclosing the variable
+/// bindings may grow by once bindings are added to an empty Response.
+const RESPONSE_GROWTH: usize = 4 + 3 * 2;
+
+/// The bindings to encode for `varbinds`.
+fn as_bindings(varbinds: &[(Oid, OwnedValue)]) -> Vec<(&[u32], Value<'_>)> {
+    varbinds.iter().map(|(name, value)| (&name[..], value.as_value())).collect()
+}
+
+/// The encoded size of `varbinds`, or the 1-based index of the first one
+/// that cannot be encoded.
+fn encoded_len(varbinds: &[(Oid, OwnedValue)]) -> Result<usize, u32> {
+    varbinds.iter().enumerate().try_fold(0, |total, (i, (name, value))| {
+        pdu::varbind_len(name, &value.as_value()).map(|len| total + len).ok_or(i as u32 + 1)
+    })
+}
+
+/// The outcome of processing a request: the variable bindings to return, or
+/// an error-status with its 1-based index.
+type Outcome = Result<Vec<(Oid, OwnedValue)>, (u32, u32)>;
+
+impl Agent {
+    /// Binds the agent's socket. Requests must carry `read_community`, or
+    /// the write community for SetRequests.
+    pub fn bind<A: ToSocketAddrs>(addr: A, read_community: &[u8]) -> io::Result<Agent> {
+        Ok(Agent {
+            socket: UdpSocket::bind(addr)?,
+            read_community: read_community.to_vec(),
+            write_community: None,
+            handlers: Vec::new(),
+            max_message_size: BUFFER_SIZE,
+            recv_buf: [0; BUFFER_SIZE],
+            send_buf: pdu::Buf::default(),
+        })
+    }
+
+    /// Allows SetRequests carrying `community`, which may also read.
+    /// Without one, SetRequests are answered with noAccess.
+    pub fn set_write_community(&mut self, community: &[u8]) {
+        self.write_community = Some(community.to_vec());
+    }
+
+    /// Limits the size of Responses, which defaults to and cannot exceed
+    /// the crate's 4096-byte buffer. GetBulk responses are cut short to
+    /// fit; other requests that do not fit are answered with tooBig.
+    ///
+    /// # Panics if `size` is below 484, the size every SNMP entity must
+    /// accept (RFC 3417 3).
+    pub fn set_max_message_size(&mut self, size: usize) {
+        assert!(size >= MIN_MESSAGE_SIZE, "message size {} is below {}", size, MIN_MESSAGE_SIZE);
+        self.max_message_size = size.min(BUFFER_SIZE);
+    }
+
+    pub fn local_addr(&self) -> io::Result<SocketAddr> {
+        self.socket.local_addr()
+    }
+
+    /// Serves the subtree under `subtree` with `handler`.
+    ///
+    /// # Panics if `subtree` overlaps one that is already registered.
+    pub fn register<H: MibHandler + 'static>(&mut self, subtree: &[u32], handler: H) {
+        let overlaps = self.handlers.iter()
+            .any(|(other, _)| other.starts_with(subtree) || subtree.starts_with(other));
+        assert!(!overlaps, "subtree {} overlaps a registered one", Oid::from(subtree));
+        let pos = self.handlers.partition_point(|(other, _)| other[..] < *subtree);
+        self.handlers.insert(pos, (Oid::from(subtree), Box::new(handler)));
+    }
+
+    /// Answers requests until receiving fails.
+    pub fn run(&mut self) -> io::Result<()> {
+        loop {
+            self.serve_one()?;
+        }
+    }
+
+    /// Receives one datagram and answers it if it is a valid request. Only
+    /// a failure to receive is returned; a response that cannot be sent,
+    /// e.g. because the manager has gone away, is dropped.
+    pub fn serve_one(&mut self) -> io::Result<()> {
+        let (len, source) = self.socket.recv_from(&mut self.recv_buf)?;
+        let request = match Request::from_bytes(&self.recv_buf[..len]) {
+            Some(request) => request,
+            None => return Ok(()),
+        };
+        if self.respond(&request) {
+            let _ = self.socket.send_to(&self.send_buf[..], source);
+        }
+        Ok(())
+    }
+
+    /// Processes `request` and encodes the Response into `send_buf`.
+    /// Returns false if it is not to be answered.
+    fn respond(&mut self, request: &Request) -> bool {
+        let writer = self.write_community.as_ref() == Some(&request.community);
+        if !writer && request.community != self.read_community {
+            return false;
+        }
+        let room = self.varbind_room(request);
+        let outcome = match request.message_type {
+            SnmpMessageType::GetRequest => self.get(&request.varbinds),
+            SnmpMessageType::GetNextRequest => self.get_next(&request.varbinds),
+            SnmpMessageType::GetBulkRequest if request.version != VERSION_1 => self.get_bulk(request, room),
+            SnmpMessageType::SetRequest if writer => self.set(&request.varbinds),
+            SnmpMessageType::SetRequest => Err((ERRSTATUS_NOACCESS, 1)),
+            _ => return false,
+        };
+        let outcome = outcome.and_then(|varbinds| {
+            match encoded_len(&varbinds) {
+                Ok(len) if len > room => Err((ERRSTATUS_TOOBIG, 0)),
+                Ok(_) => Ok(varbinds),
+                Err(index) => Err((ERRSTATUS_GENERR, index)),
+            }
+        });
+
+        let (version, req_id) = (request.version, request.req_id);
+        match outcome {
+            Ok(varbinds) => {
+                let bindings = as_bindings(&varbinds);
+                pdu::build_community_message(version, &request.community, &mut self.send_buf, |buf| {
+                    pdu::push_response(buf, version, req_id, &bindings)
+                });
+            }
+            Err((status, index)) => {
+                // tooBig is reported without bindings from SNMPv2 on (RFC 3416
+                // 4.2.1); SNMPv1 echoes the request's (RFC 1157 4.1.2).
+                let echo = (status != ERRSTATUS_TOOBIG || version == VERSION_1)
+                    && encoded_len(&request.varbinds).is_ok_and(|len| len <= room);
+                let bindings = if echo { as_bindings(&request.varbinds) } else { Vec::new() };
+                pdu::build_community_message(version, &request.community, &mut self.send_buf, |buf| {
+                    pdu::push_error_response(buf, version, req_id, status, index, &bindings)
+                });
+            }
+        }
+        true
+    }
+
+    /// Bytes available for the variable bindings of the Response to
+    /// `request` within the message size limit.
+    fn varbind_room(&mut self, request: &Request) -> usize {
+        let (version, req_id) = (request.version, request.req_id);
+        pdu::build_community_message(version, &request.community, &mut self.send_buf, |buf| {
+            pdu::push_error_response(buf, version, req_id, ERRSTATUS_NOERROR, 0, &[])
+        });
+        self.max_message_size.saturating_sub(self.send_buf.len() + RESPONSE_GROWTH)
+    }
+
+    /// The handler whose subtree contains `name`.
+    fn handler(&mut self, name: &[u32]) -> Option<&mut Box<dyn MibHandler>> {
+        self.handlers.iter_mut().find(|(subtree, _)| name.starts_with(subtree)).map(|(_, handler)| handler)
+    }
+
+    fn get(&mut self, varbinds: &[(Oid, OwnedValue)]) -> Outcome {
+        let mut out = Vec::with_capacity(varbinds.len());
+        for (i, (name, _)) in varbinds.iter().enumerate() {
+            let value = match self.handler(name) {
+                Some(handler) => handler.get(name).map_err(|status| (status, i as u32 + 1))?,
+                None => OwnedValue::NoSuchObject,
+            };
+            out.push((name.clone(), value));
+        }
+        Ok(out)
+    }
+
+    /// The instance following `name` across all handlers, or endOfMibView.
+    fn next(&mut self, name: &[u32]) -> HandlerResult<(Oid, OwnedValue)> {
+        for (subtree, handler) in &mut self.handlers {
+            // Skip subtrees that lie entirely before `name`.
+            if subtree[..] < *name && !name.starts_with(subtree) {
+                continue;
+            }
+            if let Some((next, value)) = handler.get_next(name)? {
+                if next[..] <= *name || !next.starts_with(subtree) {
+                    return Err(ERRSTATUS_GENERR);
+                }
+                return Ok((next, value));
+            }
+        }
+        Ok((Oid::from(name), OwnedValue::EndOfMibView))
+    }
+
+    fn get_next(&mut self, varbinds: &[(Oid, OwnedValue)]) -> Outcome {
+        let mut out = Vec::with_capacity(varbinds.len());
+        for (i, (name, _)) in varbinds.iter().enumerate() {
+            out.push(self.next(name).map_err(|status| (status, i as u32 + 1))?);
+        }
+        Ok(out)
+    }
+
+    /// Answers a GetBulkRequest (RFC 3416 4.2.3): one get-next for each
+    /// non-repeater, then up to max-repetitions rounds over the repeaters.
+    /// Stops early once every repeater has reached endOfMibView or the next
+    /// binding would not fit in `room` bytes.
+    fn get_bulk(&mut self, request: &Request, room: usize) -> Outcome {
+        let varbinds = &request.varbinds;
+        let non_repeaters = (request.non_repeaters as usize).min(varbinds.len());
+        let mut out = Vec::new();
+        let mut used = 0;
+        let mut fits = |out: &mut Vec<(Oid, OwnedValue)>, binding: (Oid, OwnedValue), index: usize|
+                        -> Result<bool, (u32, u32)> {
+            let len = pdu::varbind_len(&binding.0, &binding.1.as_value())
+                .ok_or((ERRSTATUS_GENERR, index as u32 + 1))?;
+            if used + len > room {
+                return Ok(false);
+            }
+            used += len;
+            out.push(binding);
+            Ok(true)
+        };
+
+        for (i, (name, _)) in varbinds[..non_repeaters].iter().enumerate() {
+            let next = self.next(name).map_err(|status| (status, i as u32 + 1))?;
+            if !fits(&mut out, next, i)? {
+                return Ok(out);
+            }
+        }
+
+        let mut cursors: Vec<(Oid, bool)> = varbinds[non_repeaters..].iter()
+            .map(|(name, _)| (name.clone(), false))
+            .collect();
+        for _ in 0..request.max_repetitions {
+            if cursors.iter().all(|&(_, ended)| ended) {
+                break;
+            }
+            for (j, (cursor, ended)) in cursors.iter_mut().enumerate() {
+                let index = non_repeaters + j;
+                let (next, value) = if *ended {
+                    (cursor.clone(), OwnedValue::EndOfMibView)
+                } else {
+                    self.next(cursor).map_err(|status| (status, index as u32 + 1))?
+                };
+                *ended = value == OwnedValue::EndOfMibView;
+                *cursor = next.clone();
+                if !fits(&mut out, (next, value), index)? {
+                    return Ok(out);
+                }
+            }
+        }
+        Ok(out)
+    }
+
+    /// Applies a SetRequest as a whole or not at all (RFC 3416 4.2.5): every
+    /// binding is tested before any is committed, and a failed commit undoes
+    /// those before it.
+    fn set(&mut self, varbinds: &[(Oid, OwnedValue)]) -> Outcome {
+        for (i, (name, value)) in varbinds.iter().enumerate() {
+            let result = match self.handler(name) {
+                Some(handler) => handler.test_set(name, value),
+                None => Err(ERRSTATUS_NOTWRITABLE),
+            };
+            result.map_err(|status| (status, i as u32 + 1))?;
+        }
+        for (i, (name, value)) in varbinds.iter().enumerate() {
+            let committed = self.handler(name).is_some_and(|handler| handler.commit_set(name, value).is_ok());
+            if committed {
+                continue;
+            }
+            let mut undone = true;
+            for (name, _) in varbinds[..i].iter().rev() {
+                undone &= self.handler(name).is_some_and(|handler| handler.undo_set(name).is_ok());
+            }
+            return Err(if undone { (ERRSTATUS_COMMITFAILED, i as u32 + 1) } else { (ERRSTATUS_UNDOFAILED, 0) });
+        }
+        Ok(varbinds.to_vec())
+    }
+}
@@ -1,5 +1,4 @@
 use std::fmt;
-use std::mem;
 use std::ptr;
 
 use super::{ SnmpError, SnmpResult, USIZE_LEN, decode_i64, asn1, snmp };
@@ -37,10 +36,15 @@ impl<'a> fmt::Debug for AsnReader<'a> {
 
 impl<'a> AsnReader<'a> {
 
-    pub fn from_bytes(bytes: &[u8]) -> AsnReader {
+    pub fn from_bytes(bytes: &[u8]) -> AsnReader<'_> {
         AsnReader {inner: bytes}
     }
 
+    /// Bytes not yet consumed by the reader.
+    pub fn remaining(&self) -> &'a [u8] {
+        self.inner
+    }
+
     pub fn peek_byte(&mut self) -> SnmpResult<u8> {
         if self.inner.is_empty() {
             Err(SnmpError::AsnEof)
@@ -81,8 +85,8 @@ impl<'a> AsnReader<'a> {
                 bytes[(USIZE_LEN - length_len)..]
                     .copy_from_slice(&tail[..length_len]);
 
-                o = unsafe { mem::transmute::<[u8; USIZE_LEN], usize>(bytes).to_be()};
-                self.inner = &tail[length_len as usize..];
+                o = usize::from_be_bytes(bytes);
+                self.inner = &tail[length_len..];
                 Ok(o)
             }
         } else {
@@ -141,7 +145,7 @@ impl<'a> AsnReader<'a> {
 
     pub fn read_asn_boolean(&mut self) -> SnmpResult<bool> {
         let ident = self.read_byte()?;
-        if ident != asn1::TYPE_NULL {
+        if ident != asn1::TYPE_BOOLEAN {
             return Err(SnmpError::AsnWrongType);
         }
         let val_len = self.read_length()?;
@@ -219,8 +223,15 @@ impl<'a> AsnReader<'a> {
         self.read_i64_type(snmp::TYPE_TIMETICKS).map(|v| v as u32)
     }
 
+    /// Values above `i64::MAX` take nine bytes, the first of them zero.
     pub fn read_snmp_counter64(&mut self) -> SnmpResult<u64> {
-        self.read_i64_type(snmp::TYPE_COUNTER64).map(|v| v as u64)
+        let raw = self.read_raw(snmp::TYPE_COUNTER64)?;
+        if raw.len() == 9 && raw[0] == 0 {
+            let mut bytes = [0; 8];
+            bytes.copy_from_slice(&raw[1..]);
+            return Ok(u64::from_be_bytes(bytes));
+        }
+        decode_i64(raw).map(|v| v as u64)
     }
 
     pub fn read_snmp_opaque(&mut self) -> SnmpResult<&'a [u8]> {
@@ -310,6 +321,9 @@ impl<'a> Iterator for AsnReader<'a> {
                 snmp::TYPE_TIMETICKS        => self.read_snmp_timeticks().map(Timeticks),
                 snmp::TYPE_OPAQUE           => self.read_snmp_opaque().map(Opaque),
                 snmp::TYPE_COUNTER64        => self.read_snmp_counter64().map(Counter64),
+                snmp::SNMP_NOSUCHOBJECT     => self.read_raw(ident).map(|_| NoSuchObject),
+                snmp::SNMP_NOSUCHINSTANCE   => self.read_raw(ident).map(|_| NoSuchInstance),
+                snmp::SNMP_ENDOFMIBVIEW     => self.read_raw(ident).map(|_| EndOfMibView),
                 snmp::MSG_GET               => self.read_raw(ident).map(|v| SnmpGetRequest(AsnReader::from_bytes(v))),
                 snmp::MSG_GET_NEXT          => self.read_raw(ident).map(|v| SnmpGetNextRequest(AsnReader::from_bytes(v))),
                 snmp::MSG_GET_BULK          => self.read_raw(ident).map(|v| SnmpGetBulkRequest(AsnReader::from_bytes(v))),
@@ -0,0 +1,183 @@
+use std::io;
+use std::net::{Ipv4Addr, Ipv6Addr, SocketAddr};
+use std::time::Duration;
+
+use tokio::net::{lookup_host, ToSocketAddrs, UdpSocket};
+use tokio::time::{self, Instant};
+
+use super::BUFFER_SIZE;
+use super::pdu;
+use super::RetryPolicy;
+use super::session::{self, Core};
+use super::{SnmpError, SnmpResult};
+use super::snmp::*;
+use super::v3;
+use super::value::Value;
+
+/// Asynchronous SNMPv1/SNMPv2c/SNMPv3 client running on tokio.
+///
+/// Mirrors `SyncSession`: each request method resolves to the decoded
+/// response, which borrows the session until the next request.
+pub struct AsyncSession {
+    socket: UdpSocket,
+    core: Core,
+    recv_buf: Vec<u8>,
+}
+
+impl AsyncSession {
+    pub async fn new<SA>(destination: SA, community: &[u8], timeout: Option<Duration>, starting_req_id: i32) -> io::Result<Self>
+        where SA: ToSocketAddrs
+    {
+        Self::open(destination, VERSION_2, community, None, timeout, starting_req_id).await
+    }
+
+    /// Opens an SNMPv1 session. `getbulk` is not available in SNMPv1.
+    pub async fn new_v1<SA>(destination: SA, community: &[u8], timeout: Option<Duration>, starting_req_id: i32) -> io::Result<Self>
+        where SA: ToSocketAddrs
+    {
+        Self::open(destination, VERSION_1, community, None, timeout, starting_req_id).await
+    }
+
+    /// Opens an SNMPv3 session using the User-based Security Model.
+    pub async fn new_v3<SA>(destination: SA, security: v3::Security, timeout: Option<Duration>, starting_req_id: i32) -> io::Result<Self>
+        where SA: ToSocketAddrs
+    {
+        Self::open(destination, VERSION_3, &[], Some(security), timeout, starting_req_id).await
+    }
+
+    async fn open<SA>(destination: SA, version: i64, community: &[u8], security: Option<v3::Security>,
+                      timeout: Option<Duration>, starting_req_id: i32) -> io::Result<Self>
+        where SA: ToSocketAddrs
+    {
+        let destination = lookup_host(destination).await?.next().ok_or_else(session::no_addresses)?;
+        let socket = match destination {
+            SocketAddr::V4(_) => UdpSocket::bind((Ipv4Addr::new(0,0,0,0), 0)).await?,
+            SocketAddr::V6(_) => UdpSocket::bind((Ipv6Addr::new(0,0,0,0,0,0,0,0), 0)).await?,
+        };
+        socket.connect(destination).await?;
+        Ok(AsyncSession {
+            socket,
+            core: Core::new(version, community, security, timeout, starting_req_id),
+            recv_buf: vec![0; BUFFER_SIZE],
+        })
+    }
+
+    /// See `SyncSession::set_retry_policy`.
+    pub fn set_retry_policy(&mut self, retry: RetryPolicy) {
+        self.core.retry = retry;
+    }
+
+    pub fn retry_policy(&self) -> &RetryPolicy {
+        &self.core.retry
+    }
+
+    /// Sends the message built in `core` and waits for the reply to `msg_id`,
+    /// retransmitting as the retry policy allows and discarding replies to
+    /// other ids.
+    async fn send_and_recv<'a>(socket: &UdpSocket, core: &Core, msg_id: i32, out: &'a mut [u8]) -> SnmpResult<&'a [u8]> {
+        let retry = &core.retry;
+        for attempt in 0..retry.attempts() {
+            if socket.send(&core.send_pdu[..]).await.is_err() {
+                return Err(SnmpError::SendError);
+            }
+            let deadline = retry.attempt_timeout(attempt).map(|timeout| Instant::now() + timeout);
+            loop {
+                let received = match deadline {
+                    Some(deadline) => match time::timeout_at(deadline, socket.recv(out)).await {
+                        Ok(received) => received,
+                        Err(_) => break,
+                    },
+                    None => socket.recv(out).await,
+                };
+                match received {
+                    Ok(len) if message_id(&out[..len]) == Some(msg_id) => return Ok(&out[..len]),
+                    Ok(_) => continue,
+                    Err(_) => return Err(SnmpError::ReceiveError),
+                }
+            }
+        }
+        Err(SnmpError::Timeout)
+    }
+
+    /// Wraps the PDU written by `f` for this session's security, sends it and
+    /// decodes the response.
+    async fn request<F>(&mut self, req_id: i32, mut f: F) -> SnmpResult<SnmpPdu<'_>>
+        where F: FnMut(&mut pdu::Buf)
+    {
+        if self.core.is_v3() {
+            let exchanged = self.exchange_v3(req_id & i32::MAX, &mut f).await;
+            self.core.finish_v3(req_id, exchanged)
+        } else {
+            self.core.build_community(f);
+            let received = Self::send_and_recv(&self.socket, &self.core, req_id, &mut self.recv_buf[..]).await;
+            self.core.finish_community(req_id, received)
+        }
+    }
+
+    /// See `SyncSession::exchange_v3`.
+    async fn exchange_v3<F>(&mut self, msg_id: i32, mut f: F) -> SnmpResult<()>
+        where F: FnMut(&mut pdu::Buf)
+    {
+        let mut resynced = false;
+        loop {
+            if self.core.needs_discovery() {
+                self.core.build_discovery(msg_id);
+                let report = Self::send_and_recv(&self.socket, &self.core, msg_id, &mut self.recv_buf[..]).await?;
+                self.core.process_discovery(msg_id, report)?;
+            }
+            self.core.build_v3(msg_id, &mut f);
+            let resp = Self::send_and_recv(&self.socket, &self.core, msg_id, &mut self.recv_buf[..]).await?;
+            if self.core.process_v3(msg_id, resp, &mut resynced)? {
+                return Ok(());
+            }
+        }
+    }
+
+    /// The authoritative engine of an SNMPv3 session, once known.
+    pub fn engine(&self) -> Option<&v3::Engine> {
+        self.core.engine()
+    }
+
+    pub async fn get(&mut self, name: &[u32]) -> SnmpResult<SnmpPdu<'_>> {
+        let req_id = self.core.req_id();
+        self.request(req_id, |buf| pdu::push_get(buf, req_id, name)).await
+    }
+
+    pub async fn getnext(&mut self, name: &[u32]) -> SnmpResult<SnmpPdu<'_>> {
+        let req_id = self.core.req_id();
+        self.request(req_id, |buf| pdu::push_getnext(buf, req_id, name)).await
+    }
+
+    pub async fn getbulk(&mut self, names: &[&[u32]], non_repeaters: u32, max_repetitions: u32) -> SnmpResult<SnmpPdu<'_>> {
+        self.core.check_v2_pdu()?;
+        let req_id = self.core.req_id();
+        self.request(req_id, |buf| pdu::push_getbulk(buf, req_id, names, non_repeaters, max_repetitions)).await
+    }
+
+    pub async fn set(&mut self, values: &[(&[u32], Value<'_>)]) -> SnmpResult<SnmpPdu<'_>> {
+        let req_id = self.core.req_id();
+        self.request(req_id, |buf| pdu::push_set(buf, req_id, values)).await
+    }
+
+    /// See `SyncSession::send_trap_v1`.
+    pub async fn send_trap_v1(&mut self, enterprise: &[u32], agent_addr: [u8; 4], generic_trap: i64,
+                              specific_trap: i64, timestamp: u32, values: &[(&[u32], Value<'_>)]) -> SnmpResult<()> {
+        self.core.build_trap_v1(enterprise, agent_addr, generic_trap, specific_trap, timestamp, values)?;
+        self.socket.send(&self.core.send_pdu[..]).await.map_err(|_| SnmpError::SendError)?;
+        Ok(())
+    }
+
+    /// See `SyncSession::send_trap_v2c`.
+    pub async fn send_trap_v2c(&mut self, uptime: u32, trap_oid: &[u32], values: &[(&[u32], Value<'_>)]) -> SnmpResult<()> {
+        self.core.build_trap_v2c(uptime, trap_oid, values)?;
+        self.socket.send(&self.core.send_pdu[..]).await.map_err(|_| SnmpError::SendError)?;
+        Ok(())
+    }
+
+    /// See `SyncSession::send_inform`.
+    pub async fn send_inform(&mut self, uptime: u32, trap_oid: &[u32], values: &[(&[u32], Value<'_>)]) -> SnmpResult<SnmpPdu<'_>> {
+        self.core.check_v2_pdu()?;
+        let req_id = self.core.req_id();
+        self.request(req_id, |buf| pdu::push_inform(buf, req_id, uptime, trap_oid, values)).await
+    }
+}
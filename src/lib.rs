@@ -1,14 +1,66 @@
+//! SNMPv1, SNMPv2c and SNMPv3 client library, with a community-based agent
+//! and a notification receiver.
+//!
+//! Requests are made through a session such as `SyncSession`. Responses
+//! come back as an `SnmpPdu` that borrows the session's receive buffer;
+//! `SnmpPdu::into_owned` copies one out.
+//!
+//! ```no_run
+//! use std::time::Duration;
+//! use snmplib::{SyncSession, Value};
+//!
+//! let sys_descr = &[1, 3, 6, 1, 2, 1, 1, 1, 0];
+//! let sys_contact = &[1, 3, 6, 1, 2, 1, 1, 4, 0];
+//! let mut sess = SyncSession::new("192.0.2.1:161", b"private", Some(Duration::from_secs(2)), 0).unwrap();
+//!
+//! let resp = sess.get(sys_descr).unwrap();
+//! for (name, value) in resp.varbinds {
+//!     println!("{} => {:?}", name, value);
+//! }
+//!
+//! let resp = sess.set(&[(sys_contact, Value::from("noc@example.com"))]).unwrap();
+//! assert_eq!(resp.error_status, snmplib::snmp::ERRSTATUS_NOERROR);
+//! ```
+
+use std::error;
+use std::fmt;
 use std::mem;
 
 pub mod asn1;
-mod snmp;
+pub mod snmp;
 mod pdu;
 mod value;
 mod asnreader;
 mod objectidentifier;
+mod oid;
 pub mod varbinds;
 mod sync;
+pub mod v3;
+pub mod mux;
+pub mod agent;
+pub mod trap;
+mod retry;
+pub mod walk;
+pub mod mib;
+pub mod mib2;
+mod table;
+mod session;
 pub use sync::SyncSession;
+pub use retry::RetryPolicy;
+pub use asnreader::AsnReader;
+pub use objectidentifier::{ObjIdBuf, ObjectIdentifier};
+pub use pdu::PduBuilder;
+pub use oid::Oid;
+#[doc(hidden)]
+pub use oid::{__oid_len, __oid_parse};
+pub use snmp::{OwnedPdu, OwnedTrapV1, SnmpMessageType, SnmpPdu, TrapV1};
+pub use value::{FromValue, OwnedValue, Value};
+pub use varbinds::Varbinds;
+pub use table::Table;
+#[cfg(feature = "tokio")]
+mod asyncsession;
+#[cfg(feature = "tokio")]
+pub use asyncsession::AsyncSession;
 
 
 const BUFFER_SIZE: usize = 4096;
@@ -20,6 +72,7 @@ const USIZE_LEN: usize = 8;
 
 
 #[derive(Debug, PartialEq)]
+#[non_exhaustive]
 pub enum SnmpError {
     AsnParseError,
     AsnInvalidLen,
@@ -32,12 +85,65 @@ pub enum SnmpError {
     RequestIdMismatch,
     CommunityMismatch,
     ValueOutOfRange,
+    /// A walk was answered with an OID not after the one requested.
+    NonIncreasingOid,
+    /// A table retrieval was answered without moving any column forward,
+    /// leaving the table incomplete.
+    NoProgress,
+    /// A string could not be parsed as a dotted OID.
+    InvalidOid,
+    /// The agent answered with a non-zero error-status.
+    ErrorStatus { status: u32, index: u32 },
+
+    UnsupportedSecurityModel,
+    UnknownEngineId,
+    AuthFailure,
+    DecryptionError,
+    NotInTimeWindow,
+    UnknownUserName,
+    UnsupportedSecurityLevel,
 
     SendError,
     ReceiveError,
+    /// No response arrived before the last retry timed out.
+    Timeout,
 }
 
-type SnmpResult<T> = Result<T, SnmpError>;
+impl fmt::Display for SnmpError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        use SnmpError::*;
+        match *self {
+            AsnParseError                 => write!(f, "malformed ASN.1 encoding"),
+            AsnInvalidLen                 => write!(f, "invalid ASN.1 length"),
+            AsnWrongType                  => write!(f, "unexpected ASN.1 type"),
+            AsnUnsupportedType            => write!(f, "unsupported ASN.1 type"),
+            AsnEof                        => write!(f, "unexpected end of ASN.1 data"),
+            AsnIntOverflow                => write!(f, "ASN.1 integer overflow"),
+            UnsupportedVersion            => write!(f, "unsupported SNMP version"),
+            RequestIdMismatch             => write!(f, "response request-id does not match"),
+            CommunityMismatch             => write!(f, "response community does not match"),
+            ValueOutOfRange               => write!(f, "value out of range"),
+            NonIncreasingOid              => write!(f, "agent returned a non-increasing OID"),
+            NoProgress                    => write!(f, "agent response made no progress"),
+            InvalidOid                    => write!(f, "invalid OID"),
+            ErrorStatus { status, index } => write!(f, "agent returned error-status {} at index {}", status, index),
+            UnsupportedSecurityModel      => write!(f, "unsupported security model"),
+            UnknownEngineId               => write!(f, "unknown engine ID"),
+            AuthFailure                   => write!(f, "authentication failure"),
+            DecryptionError               => write!(f, "decryption error"),
+            NotInTimeWindow               => write!(f, "message not in time window"),
+            UnknownUserName               => write!(f, "unknown user name"),
+            UnsupportedSecurityLevel      => write!(f, "unsupported security level"),
+            SendError                     => write!(f, "failed to send request"),
+            ReceiveError                  => write!(f, "failed to receive response"),
+            Timeout                       => write!(f, "request timed out"),
+        }
+    }
+}
+
+impl error::Error for SnmpError {}
+
+pub type SnmpResult<T> = Result<T, SnmpError>;
 
 
 fn decode_i64(i: &[u8]) -> SnmpResult<i64> {
@@ -47,7 +153,7 @@ fn decode_i64(i: &[u8]) -> SnmpResult<i64> {
     let mut bytes = [0u8; 8];
     bytes[(mem::size_of::<i64>() - i.len())..].copy_from_slice(i);
 
-    let mut ret = unsafe { mem::transmute::<[u8; 8], i64>(bytes).to_be()};
+    let mut ret = i64::from_be_bytes(bytes);
     {
         //sign extend
         let shift_amount = (mem::size_of::<i64>() - i.len()) * 8;
@@ -0,0 +1,463 @@
+//! MIB modules: SMIv1/SMIv2 definitions, OID names and object metadata.
+//!
+//! A `Mib` is loaded from MIB files and resolves between symbolic and
+//! numeric OIDs. The top of the OID tree defined by SNMPv2-SMI is built in,
+//! so modules can be loaded without the SMI files themselves.
+//!
+//! ```
+//! use snmplib::mib::Mib;
+//!
+//! let mut mib = Mib::new();
+//! mib.load_str(r#"
+//!     DEMO-MIB DEFINITIONS ::= BEGIN
+//!     IMPORTS OBJECT-TYPE, Counter32, enterprises FROM SNMPv2-SMI;
+//!     demo OBJECT IDENTIFIER ::= { enterprises 99999 }
+//!     demoPackets OBJECT-TYPE
+//!         SYNTAX      Counter32
+//!         MAX-ACCESS  read-only
+//!         STATUS      current
+//!         DESCRIPTION "Packets seen."
+//!         ::= { demo 1 }
+//!     END
+//! "#).unwrap();
+//!
+//! let oid = mib.resolve("DEMO-MIB::demoPackets.0").unwrap();
+//! assert_eq!(oid.to_string(), "1.3.6.1.4.1.99999.1.0");
+//! assert_eq!(mib.display(&oid).to_string(), "DEMO-MIB::demoPackets.0");
+//! ```
+
+mod codegen;
+mod lexer;
+mod parser;
+mod render;
+
+use std::collections::HashMap;
+use std::error;
+use std::fmt;
+use std::fs;
+use std::io;
+use std::path::{Path, PathBuf};
+
+use super::SnmpResult;
+use super::oid::Oid;
+use self::parser::{Definition, ObjectInfo, OidComponent};
+
+#[derive(Debug)]
+pub enum MibError {
+    Io(io::Error),
+    Parse { line: usize, message: String },
+    /// An error in one of the files loaded by `Mib::load_dir`.
+    InFile(PathBuf, Box<MibError>),
+    /// A name that is not defined in the loaded modules.
+    UnknownName(String),
+}
+
+impl fmt::Display for MibError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match *self {
+            MibError::Io(ref err) => write!(f, "{}", err),
+            MibError::Parse { line, ref message } => write!(f, "line {}: {}", line, message),
+            MibError::InFile(ref path, ref err) => write!(f, "{}: {}", path.display(), err),
+            MibError::UnknownName(ref name) => write!(f, "unknown name {:?}", name),
+        }
+    }
+}
+
+impl error::Error for MibError {}
+
+impl From<io::Error> for MibError {
+    fn from(err: io::Error) -> MibError {
+        MibError::Io(err)
+    }
+}
+
+/// Which macro, if any, defined a node.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum NodeKind {
+    /// A plain `OBJECT IDENTIFIER` assignment.
+    Identifier,
+    ObjectType,
+    ModuleIdentity,
+    ObjectIdentity,
+    Notification,
+    /// An SNMPv1 TRAP-TYPE; its OID is enterprise.0.specific-trap.
+    Trap,
+    Group,
+    Compliance,
+}
+
+/// The SYNTAX of an object or textual convention.
+#[derive(Debug, Clone, PartialEq)]
+pub enum Syntax {
+    /// INTEGER, with its named numbers if it is an enumeration.
+    Integer(Vec<(String, i64)>),
+    OctetString,
+    ObjectIdentifier,
+    /// BITS with its named bit positions.
+    Bits(Vec<(String, u32)>),
+    /// A defined type such as `Counter32` or `DisplayString`, with named
+    /// numbers if the enumeration is refined here.
+    Named { name: String, enums: Vec<(String, i64)> },
+    /// `SEQUENCE OF Entry`, the SYNTAX of a table.
+    SequenceOf(String),
+    /// The columns of a row type.
+    Sequence(Vec<(String, Syntax)>),
+    /// Anything else, e.g. the CHOICEs inside SNMPv2-SMI.
+    Other,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct IndexItem {
+    pub name: String,
+    pub implied: bool,
+}
+
+/// A named node of the OID tree.
+#[derive(Debug, Clone)]
+pub struct Node {
+    pub name: String,
+    pub module: String,
+    pub oid: Oid,
+    pub kind: NodeKind,
+    pub syntax: Option<Syntax>,
+    pub access: Option<String>,
+    pub status: Option<String>,
+    pub units: Option<String>,
+    pub description: Option<String>,
+    /// INDEX of a table row.
+    pub index: Vec<IndexItem>,
+    /// The row a table row AUGMENTS, sharing its index.
+    pub augments: Option<String>,
+}
+
+/// A type assignment or TEXTUAL-CONVENTION.
+#[derive(Debug, Clone)]
+pub struct TypeDef {
+    pub name: String,
+    pub module: String,
+    pub syntax: Syntax,
+    pub display_hint: Option<String>,
+    pub textual_convention: bool,
+    pub description: Option<String>,
+}
+
+/// An object definition whose parent is not yet known.
+struct Pending {
+    module: String,
+    name: String,
+    kind: NodeKind,
+    value: Vec<OidComponent>,
+    info: ObjectInfo,
+}
+
+/// Nodes defined by SNMPv2-SMI (RFC 2578 2) and the ASN.1 roots.
+const BUILTIN_NODES: &[(&str, &[u32])] = &[
+    ("ccitt", &[0]),
+    ("zeroDotZero", &[0, 0]),
+    ("iso", &[1]),
+    ("org", &[1, 3]),
+    ("dod", &[1, 3, 6]),
+    ("internet", &[1, 3, 6, 1]),
+    ("directory", &[1, 3, 6, 1, 1]),
+    ("mgmt", &[1, 3, 6, 1, 2]),
+    ("mib-2", &[1, 3, 6, 1, 2, 1]),
+    ("transmission", &[1, 3, 6, 1, 2, 1, 10]),
+    ("experimental", &[1, 3, 6, 1, 3]),
+    ("private", &[1, 3, 6, 1, 4]),
+    ("enterprises", &[1, 3, 6, 1, 4, 1]),
+    ("security", &[1, 3, 6, 1, 5]),
+    ("snmpV2", &[1, 3, 6, 1, 6]),
+    ("snmpDomains", &[1, 3, 6, 1, 6, 1]),
+    ("snmpProxys", &[1, 3, 6, 1, 6, 2]),
+    ("snmpModules", &[1, 3, 6, 1, 6, 3]),
+    ("joint-iso-ccitt", &[2]),
+];
+
+/// Loaded MIB modules.
+pub struct Mib {
+    nodes: Vec<Node>,
+    by_oid: HashMap<Vec<u32>, usize>,
+    by_name: HashMap<String, Vec<usize>>,
+    by_module: HashMap<(String, String), usize>,
+    types: HashMap<(String, String), TypeDef>,
+    /// module -> (symbol -> module it is imported from)
+    imports: HashMap<String, HashMap<String, String>>,
+    pending: Vec<Pending>,
+}
+
+impl Default for Mib {
+    fn default() -> Mib {
+        Mib::new()
+    }
+}
+
+impl Mib {
+    /// A MIB knowing only the nodes built into SNMPv2-SMI.
+    pub fn new() -> Mib {
+        let mut mib = Mib {
+            nodes: Vec::new(),
+            by_oid: HashMap::new(),
+            by_name: HashMap::new(),
+            by_module: HashMap::new(),
+            types: HashMap::new(),
+            imports: HashMap::new(),
+            pending: Vec::new(),
+        };
+        for &(name, oid) in BUILTIN_NODES {
+            mib.insert(Node {
+                name: name.to_string(),
+                module: "SNMPv2-SMI".to_string(),
+                oid: Oid::from(oid),
+                kind: NodeKind::Identifier,
+                syntax: None,
+                access: None,
+                status: None,
+                units: None,
+                description: None,
+                index: Vec::new(),
+                augments: None,
+            });
+        }
+        mib
+    }
+
+    /// Loads the modules in `text`, returning their names.
+    ///
+    /// Objects whose parents are defined in modules not loaded yet are
+    /// resolved once those are loaded.
+    pub fn load_str(&mut self, text: &str) -> Result<Vec<String>, MibError> {
+        let toks = lexer::tokenize(text)?;
+        let modules = parser::parse(&toks)?;
+        let mut names = Vec::new();
+        for module in modules {
+            let imports = self.imports.entry(module.name.clone()).or_default();
+            imports.extend(module.imports);
+            for definition in module.definitions {
+                match definition {
+                    Definition::Object { name, kind, value, info } => {
+                        if !self.by_module.contains_key(&(module.name.clone(), name.clone())) {
+                            self.pending.push(Pending { module: module.name.clone(), name, kind, value, info });
+                        }
+                    }
+                    Definition::Type { name, syntax, display_hint, textual_convention, description } => {
+                        self.types.entry((module.name.clone(), name.clone())).or_insert(TypeDef {
+                            name,
+                            module: module.name.clone(),
+                            syntax,
+                            display_hint,
+                            textual_convention,
+                            description,
+                        });
+                    }
+                }
+            }
+            names.push(module.name);
+        }
+        self.resolve_pending();
+        Ok(names)
+    }
+
+    /// Loads the modules in a file. MIB files are often Latin-1, so
+    /// invalid UTF-8 is replaced rather than rejected.
+    pub fn load_file<P: AsRef<Path>>(&mut self, path: P) -> Result<Vec<String>, MibError> {
+        let path = path.as_ref();
+        let bytes = fs::read(path).map_err(|err| MibError::InFile(path.to_path_buf(), Box::new(err.into())))?;
+        self.load_str(&String::from_utf8_lossy(&bytes))
+            .map_err(|err| MibError::InFile(path.to_path_buf(), Box::new(err)))
+    }
+
+    /// Loads every MIB module file in a directory, in file name order.
+    /// Files that contain no module definition are ignored.
+    pub fn load_dir<P: AsRef<Path>>(&mut self, dir: P) -> Result<Vec<String>, MibError> {
+        let mut paths = Vec::new();
+        for entry in fs::read_dir(dir)? {
+            let path = entry?.path();
+            if path.is_file() {
+                paths.push(path);
+            }
+        }
+        paths.sort();
+        let mut names = Vec::new();
+        for path in paths {
+            let bytes = fs::read(&path)?;
+            let text = String::from_utf8_lossy(&bytes);
+            if !text.contains("DEFINITIONS") {
+                continue;
+            }
+            let loaded = self.load_str(&text).map_err(|err| MibError::InFile(path.clone(), Box::new(err)))?;
+            names.extend(loaded);
+        }
+        Ok(names)
+    }
+
+    fn insert(&mut self, node: Node) {
+        let index = self.nodes.len();
+        self.by_oid.entry(node.oid.to_vec()).or_insert(index);
+        self.by_name.entry(node.name.clone()).or_default().push(index);
+        self.by_module.insert((node.module.clone(), node.name.clone()), index);
+        self.nodes.push(node);
+    }
+
+    /// Places pending objects in the tree until no more parents resolve.
+    fn resolve_pending(&mut self) {
+        loop {
+            let mut progress = false;
+            let mut pending = std::mem::take(&mut self.pending);
+            pending.retain(|object| {
+                let oid = match self.resolve_value(&object.module, &object.value) {
+                    Some(oid) => oid,
+                    None => return true,
+                };
+                let info = object.info.clone();
+                self.insert(Node {
+                    name: object.name.clone(),
+                    module: object.module.clone(),
+                    oid,
+                    kind: object.kind,
+                    syntax: info.syntax,
+                    access: info.access,
+                    status: info.status,
+                    units: info.units,
+                    description: info.description,
+                    index: info.index,
+                    augments: info.augments,
+                });
+                progress = true;
+                false
+            });
+            self.pending = pending;
+            if !progress {
+                return;
+            }
+        }
+    }
+
+    fn resolve_value(&self, module: &str, value: &[OidComponent]) -> Option<Oid> {
+        let (first, rest) = value.split_first()?;
+        let mut subids = match *first {
+            OidComponent::Number(n) | OidComponent::NamedNumber(_, n) => vec![n],
+            OidComponent::Name(ref name) => self.symbol(module, name)?.oid.to_vec(),
+        };
+        for component in rest {
+            match *component {
+                OidComponent::Number(n) | OidComponent::NamedNumber(_, n) => subids.push(n),
+                OidComponent::Name(_) => return None,
+            }
+        }
+        Some(Oid::from(subids))
+    }
+
+    /// Looks up `name` as seen from `module`: its own definitions, then its
+    /// imports, then any module defining the name.
+    fn symbol(&self, module: &str, name: &str) -> Option<&Node> {
+        let key = |module: &str| (module.to_string(), name.to_string());
+        if let Some(&index) = self.by_module.get(&key(module)) {
+            return Some(&self.nodes[index]);
+        }
+        let from = self.imports.get(module).and_then(|imports| imports.get(name));
+        if let Some(&index) = from.and_then(|from| self.by_module.get(&key(from))) {
+            return Some(&self.nodes[index]);
+        }
+        self.by_name.get(name).and_then(|indices| indices.first()).map(|&index| &self.nodes[index])
+    }
+
+    /// Looks up a node by `name` or `MODULE::name`.
+    pub fn node(&self, name: &str) -> Option<&Node> {
+        match name.find("::") {
+            Some(pos) => {
+                let key = (name[..pos].to_string(), name[pos + 2..].to_string());
+                self.by_module.get(&key).map(|&index| &self.nodes[index])
+            }
+            None => self.by_name.get(name).and_then(|indices| indices.first()).map(|&index| &self.nodes[index]),
+        }
+    }
+
+    /// All resolved nodes, in load order.
+    pub fn nodes(&self) -> impl Iterator<Item = &Node> {
+        self.nodes.iter()
+    }
+
+    /// Objects whose OID could not be resolved yet, as (module, name).
+    pub fn unresolved(&self) -> impl Iterator<Item = (&str, &str)> {
+        self.pending.iter().map(|object| (object.module.as_str(), object.name.as_str()))
+    }
+
+    /// Looks up a type or textual convention as seen from `module`.
+    pub fn type_def(&self, module: &str, name: &str) -> Option<&TypeDef> {
+        let key = |module: &str| (module.to_string(), name.to_string());
+        if let Some(def) = self.types.get(&key(module)) {
+            return Some(def);
+        }
+        let from = self.imports.get(module).and_then(|imports| imports.get(name));
+        if let Some(def) = from.and_then(|from| self.types.get(&key(from))) {
+            return Some(def);
+        }
+        self.types.values().find(|def| def.name == name)
+    }
+
+    /// Resolves `MODULE::name.suffix`, `name.suffix` or a numeric OID, e.g.
+    /// "IF-MIB::ifInOctets.3" or "sysDescr.0", optionally with a leading dot.
+    pub fn resolve(&self, name: &str) -> Result<Oid, MibError> {
+        let unknown = || MibError::UnknownName(name.to_string());
+        let trimmed = name.strip_prefix('.').unwrap_or(name);
+        if trimmed.starts_with(|c: char| c.is_ascii_digit()) {
+            return trimmed.parse().map_err(|_| unknown());
+        }
+        let (symbol, suffix) = match trimmed.find('.') {
+            Some(pos) => (&trimmed[..pos], &trimmed[pos + 1..]),
+            None => (trimmed, ""),
+        };
+        let node = self.node(symbol).ok_or_else(unknown)?;
+        let mut subids = node.oid.to_vec();
+        if !suffix.is_empty() {
+            for subid in suffix.split('.') {
+                subids.push(subid.parse().map_err(|_| unknown())?);
+            }
+        }
+        Ok(Oid::from(subids))
+    }
+
+    /// The node with the longest OID that is a prefix of `oid`, and the
+    /// remaining sub-IDs (e.g. a table index).
+    pub fn lookup<'o>(&self, oid: &'o [u32]) -> Option<(&Node, &'o [u32])> {
+        (1..=oid.len()).rev().find_map(|len| {
+            self.by_oid.get(&oid[..len]).map(|&index| (&self.nodes[index], &oid[len..]))
+        })
+    }
+
+    /// Renders `oid` as `MODULE::name.suffix`, or numerically if no prefix
+    /// of it is known.
+    pub fn display<'m>(&'m self, oid: &[u32]) -> Symbolic<'m> {
+        Symbolic { mib: self, oid: Ok(oid.to_vec()) }
+    }
+}
+
+/// An OID displayed by name; see `Mib::display`.
+pub struct Symbolic<'m> {
+    mib: &'m Mib,
+    oid: SnmpResult<Vec<u32>>,
+}
+
+impl<'m> Symbolic<'m> {
+    pub(crate) fn new(mib: &'m Mib, oid: SnmpResult<Vec<u32>>) -> Symbolic<'m> {
+        Symbolic { mib, oid }
+    }
+}
+
+impl<'m> fmt::Display for Symbolic<'m> {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let oid = match self.oid {
+            Ok(ref oid) => oid,
+            Err(ref err) => return write!(f, "Invalid OID: {:?}", err),
+        };
+        let (node, suffix) = match self.mib.lookup(oid) {
+            Some(found) => found,
+            None => return write!(f, "{}", Oid::from(&oid[..])),
+        };
+        write!(f, "{}::{}", node.module, node.name)?;
+        for subid in suffix {
+            write!(f, ".{}", subid)?;
+        }
+        Ok(())
+    }
+}
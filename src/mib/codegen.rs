@@ -0,0 +1,190 @@
+//! Generating typed row structs for the tables of a MIB module.
+
+use std::fmt::Write;
+
+use super::{Mib, MibError, Node, NodeKind, Syntax};
+
+/// How many textual conventions deep a column's SYNTAX is followed.
+const MAX_TYPE_DEPTH: usize = 8;
+
+const KEYWORDS: &[&str] = &[
+    "as", "break", "const", "continue", "crate", "else", "enum", "extern", "false", "fn", "for", "if",
+    "impl", "in", "let", "loop", "match", "mod", "move", "mut", "pub", "ref", "return", "static",
+    "struct", "super", "trait", "true", "type", "unsafe", "use", "where", "while", "async", "await",
+    "dyn", "abstract", "become", "box", "do", "final", "macro", "override", "priv", "typeof",
+    "unsized", "virtual", "yield", "try",
+];
+
+/// `ifHCInOctets` -> `if_hc_in_octets`
+fn snake_case(name: &str) -> String {
+    let chars: Vec<char> = name.chars().collect();
+    let mut out = String::new();
+    for (i, &c) in chars.iter().enumerate() {
+        if c == '-' || c == '_' {
+            out.push('_');
+            continue;
+        }
+        if c.is_ascii_uppercase() && i > 0 {
+            let prev = chars[i - 1];
+            let next_lower = chars.get(i + 1).is_some_and(|c| c.is_ascii_lowercase());
+            if prev.is_ascii_lowercase() || prev.is_ascii_digit() || (prev.is_ascii_uppercase() && next_lower) {
+                out.push('_');
+            }
+        }
+        out.push(c.to_ascii_lowercase());
+    }
+    if KEYWORDS.contains(&out.as_str()) {
+        out.insert_str(0, "r#");
+    }
+    out
+}
+
+/// `ifEntry` -> `IfEntry`
+fn camel_case(name: &str) -> String {
+    let mut out = String::new();
+    let mut upper = true;
+    for c in name.chars() {
+        if c == '-' || c == '_' {
+            upper = true;
+        } else if upper {
+            out.push(c.to_ascii_uppercase());
+            upper = false;
+        } else {
+            out.push(c);
+        }
+    }
+    out
+}
+
+fn subids(oid: &[u32]) -> String {
+    let subids: Vec<String> = oid.iter().map(|subid| subid.to_string()).collect();
+    subids.join(", ")
+}
+
+impl Mib {
+    /// Generates Rust source with a row struct for every table of `module`.
+    ///
+    /// Each struct has an `index` and an `Option` field per accessible
+    /// column, typed by its SYNTAX, and a `fetch` function that retrieves
+    /// all rows with `SyncSession::table`. The output refers to the crate as
+    /// `snmplib`, so it can be written to `OUT_DIR` by a build script and
+    /// pulled in with `include!`:
+    ///
+    /// ```no_run
+    /// // build.rs
+    /// use snmplib::mib::Mib;
+    ///
+    /// let mut mib = Mib::new();
+    /// mib.load_dir("mibs").unwrap();
+    /// let out = std::env::var("OUT_DIR").unwrap();
+    /// std::fs::write(format!("{}/if_mib.rs", out), mib.generate_tables("IF-MIB").unwrap()).unwrap();
+    /// ```
+    pub fn generate_tables(&self, module: &str) -> Result<String, MibError> {
+        let mut tables: Vec<&Node> = self.nodes()
+            .filter(|node| node.module == module && matches!(node.syntax, Some(Syntax::SequenceOf(_))))
+            .collect();
+        if tables.is_empty() && !self.nodes().any(|node| node.module == module) {
+            return Err(MibError::UnknownName(module.to_string()));
+        }
+        tables.sort_by(|a, b| a.oid.cmp(&b.oid));
+
+        let mut out = format!("// Generated from {} by snmplib. Do not edit.\n", module);
+        for table in tables {
+            let entry = match self.nodes().find(|node| node.oid.parent().as_ref() == Some(&table.oid)) {
+                Some(entry) => entry,
+                None => continue,
+            };
+            self.generate_row(&mut out, table, entry);
+        }
+        Ok(out)
+    }
+
+    fn generate_row(&self, out: &mut String, table: &Node, entry: &Node) {
+        let mut columns: Vec<&Node> = self.nodes()
+            .filter(|node| node.kind == NodeKind::ObjectType && node.oid.parent().as_ref() == Some(&entry.oid))
+            .filter(|node| node.access.as_deref() != Some("not-accessible"))
+            .collect();
+        columns.sort_by(|a, b| a.oid.cmp(&b.oid));
+        let name = match table.syntax {
+            Some(Syntax::SequenceOf(ref row)) => camel_case(row),
+            _ => camel_case(&entry.name),
+        };
+
+        let _ = writeln!(out, "\n/// A row of {}::{}.", table.module, table.name);
+        let _ = writeln!(out, "#[derive(Debug, Clone, Default, PartialEq)]");
+        let _ = writeln!(out, "pub struct {} {{", name);
+        let _ = writeln!(out, "    /// The instance sub-IDs following the column OIDs.");
+        let _ = writeln!(out, "    pub index: snmplib::Oid,");
+        for column in &columns {
+            let _ = writeln!(out, "    /// {}::{}", column.module, column.name);
+            let _ = writeln!(out, "    pub {}: Option<{}>,", snake_case(&column.name), self.rust_type(column));
+        }
+        let _ = writeln!(out, "}}\n");
+
+        let _ = writeln!(out, "impl {} {{", name);
+        let _ = writeln!(out, "    /// {}::{}", entry.module, entry.name);
+        let _ = writeln!(out, "    pub const OID: &'static [u32] = &[{}];\n", subids(&entry.oid));
+        let _ = writeln!(out, "    /// The columns fetched, in field order.");
+        let _ = writeln!(out, "    pub const COLUMNS: &'static [&'static [u32]] = &[");
+        for column in &columns {
+            let _ = writeln!(out, "        &[{}],", subids(&column.oid));
+        }
+        let _ = writeln!(out, "    ];\n");
+        let _ = writeln!(out, "    /// Builds a row from cells in `COLUMNS` order; cells of another type are `None`.");
+        let _ = writeln!(out, "    pub fn from_cells(index: &[u32], cells: &[Option<snmplib::OwnedValue>]) -> {} {{", name);
+        let _ = writeln!(out, "        let cell = |i: usize| cells.get(i).and_then(|cell| cell.as_ref());");
+        let _ = writeln!(out, "        {} {{", name);
+        let _ = writeln!(out, "            index: snmplib::Oid::from(index),");
+        for (i, column) in columns.iter().enumerate() {
+            let _ = writeln!(out, "            {}: cell({}).and_then(snmplib::FromValue::from_value),",
+                             snake_case(&column.name), i);
+        }
+        let _ = writeln!(out, "        }}");
+        let _ = writeln!(out, "    }}\n");
+        let _ = writeln!(out, "    /// Fetches every row of {} with GETBULK.", table.name);
+        let _ = writeln!(out, "    pub fn fetch(sess: &mut snmplib::SyncSession, max_repetitions: u32)");
+        let _ = writeln!(out, "                 -> snmplib::SnmpResult<Vec<{}>> {{", name);
+        let _ = writeln!(out, "        let table = sess.table(Self::COLUMNS, max_repetitions)?;");
+        let _ = writeln!(out, "        Ok(table.rows().map(|(index, cells)| {}::from_cells(index, cells)).collect())", name);
+        let _ = writeln!(out, "    }}");
+        let _ = writeln!(out, "}}");
+    }
+
+    /// The Rust type of a column's values.
+    fn rust_type(&self, column: &Node) -> &'static str {
+        let mut module = column.module.as_str();
+        let mut syntax = match column.syntax {
+            Some(ref syntax) => syntax,
+            None => return "snmplib::OwnedValue",
+        };
+        for _ in 0..MAX_TYPE_DEPTH {
+            let name = match *syntax {
+                Syntax::Integer(_) => return "i64",
+                Syntax::OctetString | Syntax::Bits(_) => return "Vec<u8>",
+                Syntax::ObjectIdentifier => return "snmplib::Oid",
+                Syntax::Named { ref name, .. } => name,
+                _ => break,
+            };
+            match name.as_str() {
+                "Integer32" => return "i64",
+                "Counter32" | "Gauge32" | "Unsigned32" | "TimeTicks" | "Counter" | "Gauge" => return "u32",
+                "Counter64" => return "u64",
+                "IpAddress" | "NetworkAddress" => return "std::net::Ipv4Addr",
+                "Opaque" => return "Vec<u8>",
+                _ => (),
+            }
+            let def = match self.type_def(module, name) {
+                Some(def) => def,
+                None => break,
+            };
+            // Octet strings displayed as text
+            let text = def.display_hint.as_deref().is_some_and(|hint| hint.ends_with('a') || hint.ends_with('t'));
+            if text && def.syntax == Syntax::OctetString {
+                return "String";
+            }
+            module = &def.module;
+            syntax = &def.syntax;
+        }
+        "snmplib::OwnedValue"
+    }
+}
@@ -0,0 +1,142 @@
+//! Tokenizer for SMI module text.
+
+use super::MibError;
+
+#[derive(Debug, Clone, PartialEq)]
+pub(crate) enum Token {
+    /// Identifier or keyword, including hyphenated ones like `OBJECT-TYPE`.
+    Ident(String),
+    Number(i64),
+    /// Quoted string, e.g. a DESCRIPTION.
+    Str(String),
+    /// Binary or hexadecimal string such as `'ff'H`.
+    Binary(String),
+    Assign,
+    LBrace,
+    RBrace,
+    LParen,
+    RParen,
+    LBracket,
+    RBracket,
+    Comma,
+    Semi,
+    Pipe,
+    Range,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub(crate) struct Tok {
+    pub token: Token,
+    pub line: usize,
+}
+
+fn is_ident_char(b: u8) -> bool {
+    b.is_ascii_alphanumeric() || b == b'-' || b == b'_'
+}
+
+pub(crate) fn tokenize(text: &str) -> Result<Vec<Tok>, MibError> {
+    let bytes = text.as_bytes();
+    let mut toks = Vec::new();
+    let mut line = 1;
+    let mut i = 0;
+    let error = |line, message: &str| MibError::Parse { line, message: message.to_string() };
+
+    while i < bytes.len() {
+        let b = bytes[i];
+        let start_line = line;
+        let token = match b {
+            b'\n' => {
+                line += 1;
+                i += 1;
+                continue;
+            }
+            _ if b.is_ascii_whitespace() => {
+                i += 1;
+                continue;
+            }
+            // A comment runs to the end of the line or the next "--".
+            b'-' if bytes.get(i + 1) == Some(&b'-') => {
+                i += 2;
+                while i < bytes.len() && bytes[i] != b'\n' {
+                    if bytes[i] == b'-' && bytes.get(i + 1) == Some(&b'-') {
+                        i += 2;
+                        break;
+                    }
+                    i += 1;
+                }
+                continue;
+            }
+            b'"' => {
+                let start = i + 1;
+                i = start;
+                while i < bytes.len() && bytes[i] != b'"' {
+                    if bytes[i] == b'\n' {
+                        line += 1;
+                    }
+                    i += 1;
+                }
+                if i == bytes.len() {
+                    return Err(error(start_line, "unterminated string"));
+                }
+                i += 1;
+                Token::Str(text[start..i - 1].to_string())
+            }
+            b'\'' => {
+                let start = i + 1;
+                i = start;
+                while i < bytes.len() && bytes[i] != b'\'' {
+                    i += 1;
+                }
+                if i + 1 >= bytes.len() {
+                    return Err(error(start_line, "unterminated binary string"));
+                }
+                i += 2; // closing quote and the B/H suffix
+                Token::Binary(text[start..i - 2].to_string())
+            }
+            b':' if bytes[i..].starts_with(b"::=") => {
+                i += 3;
+                Token::Assign
+            }
+            b'.' if bytes.get(i + 1) == Some(&b'.') => {
+                i += 2;
+                Token::Range
+            }
+            b'{' => { i += 1; Token::LBrace }
+            b'}' => { i += 1; Token::RBrace }
+            b'(' => { i += 1; Token::LParen }
+            b')' => { i += 1; Token::RParen }
+            b'[' => { i += 1; Token::LBracket }
+            b']' => { i += 1; Token::RBracket }
+            b',' => { i += 1; Token::Comma }
+            b';' => { i += 1; Token::Semi }
+            b'|' => { i += 1; Token::Pipe }
+            b'-' if !bytes.get(i + 1).is_some_and(u8::is_ascii_digit) => {
+                return Err(error(line, "unexpected character '-'"));
+            }
+            b'-' | b'0'..=b'9' => {
+                let start = i;
+                i += 1;
+                while i < bytes.len() && bytes[i].is_ascii_digit() {
+                    i += 1;
+                }
+                let digits = &text[start..i];
+                // Values beyond i64 only occur in ranges, which are not kept.
+                Token::Number(digits.parse().unwrap_or(if b == b'-' { i64::MIN } else { i64::MAX }))
+            }
+            _ if b.is_ascii_alphabetic() => {
+                let start = i;
+                while i < bytes.len() && is_ident_char(bytes[i]) {
+                    // "--" inside a word starts a comment
+                    if bytes[i] == b'-' && bytes.get(i + 1) == Some(&b'-') {
+                        break;
+                    }
+                    i += 1;
+                }
+                Token::Ident(text[start..i].trim_end_matches('-').to_string())
+            }
+            _ => return Err(error(line, &format!("unexpected character {:?}", b as char))),
+        };
+        toks.push(Tok { token, line: start_line });
+    }
+    Ok(toks)
+}
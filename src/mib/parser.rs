@@ -0,0 +1,505 @@
+//! Parser turning SMI tokens into module definitions.
+//!
+//! Only what the resolver and renderers need is kept; other clauses and
+//! MACRO definitions are skipped.
+
+use std::convert::TryFrom;
+
+use super::lexer::{Tok, Token};
+use super::{IndexItem, MibError, NodeKind, Syntax};
+
+/// One component of an OBJECT IDENTIFIER value, e.g. `mib-2`, `2` or `iso(1)`.
+#[derive(Debug, Clone, PartialEq)]
+pub(crate) enum OidComponent {
+    Name(String),
+    Number(u32),
+    NamedNumber(String, u32),
+}
+
+#[derive(Debug, Clone, Default)]
+pub(crate) struct ObjectInfo {
+    pub syntax: Option<Syntax>,
+    pub access: Option<String>,
+    pub status: Option<String>,
+    pub units: Option<String>,
+    pub description: Option<String>,
+    pub index: Vec<IndexItem>,
+    pub augments: Option<String>,
+    pub display_hint: Option<String>,
+}
+
+#[derive(Debug, Clone)]
+pub(crate) enum Definition {
+    Object {
+        name: String,
+        kind: NodeKind,
+        value: Vec<OidComponent>,
+        info: ObjectInfo,
+    },
+    Type {
+        name: String,
+        syntax: Syntax,
+        display_hint: Option<String>,
+        textual_convention: bool,
+        description: Option<String>,
+    },
+}
+
+#[derive(Debug, Clone)]
+pub(crate) struct Module {
+    pub name: String,
+    /// (symbol, module it is imported from)
+    pub imports: Vec<(String, String)>,
+    pub definitions: Vec<Definition>,
+}
+
+/// Clause keywords of the SMI macros; an unrecognized clause is skipped up
+/// to the next of these.
+const CLAUSES: &[&str] = &[
+    "SYNTAX", "UNITS", "MAX-ACCESS", "ACCESS", "MIN-ACCESS", "STATUS", "DESCRIPTION", "REFERENCE",
+    "INDEX", "AUGMENTS", "DEFVAL", "DISPLAY-HINT", "OBJECTS", "NOTIFICATIONS", "LAST-UPDATED",
+    "ORGANIZATION", "CONTACT-INFO", "REVISION", "ENTERPRISE", "VARIABLES", "MODULE",
+    "MANDATORY-GROUPS", "GROUP", "OBJECT", "WRITE-SYNTAX", "PRODUCT-RELEASE", "SUPPORTS",
+    "INCLUDES", "VARIATION", "CREATION-REQUIRES",
+];
+
+fn macro_kind(name: &str) -> Option<NodeKind> {
+    Some(match name {
+        "OBJECT-TYPE"        => NodeKind::ObjectType,
+        "MODULE-IDENTITY"    => NodeKind::ModuleIdentity,
+        "OBJECT-IDENTITY"    => NodeKind::ObjectIdentity,
+        "NOTIFICATION-TYPE"  => NodeKind::Notification,
+        "TRAP-TYPE"          => NodeKind::Trap,
+        "OBJECT-GROUP"
+        | "NOTIFICATION-GROUP" => NodeKind::Group,
+        "MODULE-COMPLIANCE"
+        | "AGENT-CAPABILITIES" => NodeKind::Compliance,
+        _ => return None,
+    })
+}
+
+struct Parser<'t> {
+    toks: &'t [Tok],
+    pos: usize,
+}
+
+impl<'t> Parser<'t> {
+    fn peek(&self) -> Option<&'t Token> {
+        self.toks.get(self.pos).map(|tok| &tok.token)
+    }
+
+    fn peek_at(&self, offset: usize) -> Option<&'t Token> {
+        self.toks.get(self.pos + offset).map(|tok| &tok.token)
+    }
+
+    fn line(&self) -> usize {
+        self.toks.get(self.pos).or_else(|| self.toks.last()).map_or(0, |tok| tok.line)
+    }
+
+    fn error<T>(&self, message: &str) -> Result<T, MibError> {
+        Err(MibError::Parse { line: self.line(), message: message.to_string() })
+    }
+
+    fn next(&mut self) -> Result<&'t Token, MibError> {
+        match self.toks.get(self.pos) {
+            Some(tok) => {
+                self.pos += 1;
+                Ok(&tok.token)
+            }
+            None => self.error("unexpected end of file"),
+        }
+    }
+
+    fn is_ident(&self, word: &str) -> bool {
+        matches!(self.peek(), Some(Token::Ident(ident)) if ident == word)
+    }
+
+    fn expect(&mut self, token: Token) -> Result<(), MibError> {
+        if self.next()? != &token {
+            self.pos -= 1;
+            return self.error(&format!("expected {:?}", token));
+        }
+        Ok(())
+    }
+
+    fn expect_ident(&mut self, word: &str) -> Result<(), MibError> {
+        if !self.is_ident(word) {
+            return self.error(&format!("expected {}", word));
+        }
+        self.pos += 1;
+        Ok(())
+    }
+
+    fn ident(&mut self) -> Result<String, MibError> {
+        match self.next()? {
+            Token::Ident(ident) => Ok(ident.clone()),
+            _ => {
+                self.pos -= 1;
+                self.error("expected an identifier")
+            }
+        }
+    }
+
+    fn string(&mut self) -> Result<String, MibError> {
+        match self.next()? {
+            Token::Str(s) => Ok(s.clone()),
+            _ => {
+                self.pos -= 1;
+                self.error("expected a string")
+            }
+        }
+    }
+
+    fn number(&mut self) -> Result<i64, MibError> {
+        match self.next()? {
+            Token::Number(n) => Ok(*n),
+            _ => {
+                self.pos -= 1;
+                self.error("expected a number")
+            }
+        }
+    }
+
+    /// Skips one token, or a whole bracketed group starting at it.
+    fn skip_item(&mut self) -> Result<(), MibError> {
+        let mut depth = 0usize;
+        loop {
+            match self.next()? {
+                Token::LBrace | Token::LParen | Token::LBracket => depth += 1,
+                Token::RBrace | Token::RParen | Token::RBracket => depth = depth.saturating_sub(1),
+                _ => (),
+            }
+            if depth == 0 {
+                return Ok(());
+            }
+        }
+    }
+
+    fn module(&mut self) -> Result<Module, MibError> {
+        let name = self.ident()?;
+        // Optional module OID, e.g. "RFC1213-MIB { iso ... }"
+        if self.peek() == Some(&Token::LBrace) {
+            self.skip_item()?;
+        }
+        self.expect_ident("DEFINITIONS")?;
+        // Tagging defaults such as "IMPLICIT TAGS"
+        while !matches!(self.peek(), Some(Token::Assign) | None) {
+            self.pos += 1;
+        }
+        self.expect(Token::Assign)?;
+        self.expect_ident("BEGIN")?;
+
+        let mut module = Module { name, imports: Vec::new(), definitions: Vec::new() };
+        loop {
+            match self.peek() {
+                Some(Token::Ident(word)) if word == "END" => {
+                    self.pos += 1;
+                    return Ok(module);
+                }
+                Some(Token::Ident(word)) if word == "IMPORTS" => {
+                    self.pos += 1;
+                    module.imports = self.imports()?;
+                }
+                Some(Token::Ident(word)) if word == "EXPORTS" => {
+                    while self.next()? != &Token::Semi {}
+                }
+                Some(Token::Ident(_)) => {
+                    if let Some(definition) = self.definition()? {
+                        module.definitions.push(definition);
+                    }
+                }
+                Some(_) => return self.error("expected a definition"),
+                None => return self.error("missing END"),
+            }
+        }
+    }
+
+    fn imports(&mut self) -> Result<Vec<(String, String)>, MibError> {
+        let mut imports = Vec::new();
+        let mut symbols = Vec::new();
+        loop {
+            match self.next()? {
+                Token::Semi => return Ok(imports),
+                Token::Comma => (),
+                Token::Ident(word) if word == "FROM" => {
+                    let from = self.ident()?;
+                    // An optional module OID value
+                    if self.peek() == Some(&Token::LBrace) {
+                        self.skip_item()?;
+                    }
+                    imports.extend(symbols.drain(..).map(|symbol| (symbol, from.clone())));
+                }
+                Token::Ident(symbol) => symbols.push(symbol.clone()),
+                _ => {
+                    self.pos -= 1;
+                    return self.error("malformed IMPORTS");
+                }
+            }
+        }
+    }
+
+    fn definition(&mut self) -> Result<Option<Definition>, MibError> {
+        let name = self.ident()?;
+        match self.peek() {
+            Some(Token::Ident(word)) if word == "MACRO" => {
+                while !self.is_ident("END") {
+                    self.next()?;
+                }
+                self.pos += 1;
+                Ok(None)
+            }
+            Some(Token::Assign) => {
+                self.pos += 1;
+                self.type_assignment(name).map(Some)
+            }
+            Some(Token::Ident(word)) if word == "OBJECT" && self.peek_at(1) == Some(&Token::Ident("IDENTIFIER".into())) => {
+                self.pos += 2;
+                self.expect(Token::Assign)?;
+                let value = self.oid_value()?;
+                Ok(Some(Definition::Object { name, kind: NodeKind::Identifier, value, info: ObjectInfo::default() }))
+            }
+            Some(Token::Ident(word)) if macro_kind(word).is_some() => {
+                let kind = macro_kind(word).unwrap_or(NodeKind::Identifier);
+                self.pos += 1;
+                let (info, enterprise) = self.clauses()?;
+                self.expect(Token::Assign)?;
+                let value = if kind == NodeKind::Trap {
+                    // SNMPv1 trap n of an enterprise is enterprise.0.n (RFC 3584 3).
+                    let number = self.number()?;
+                    match enterprise {
+                        Some(enterprise) => vec![OidComponent::Name(enterprise), OidComponent::Number(0),
+                                                 OidComponent::Number(number as u32)],
+                        None => return self.error("TRAP-TYPE without ENTERPRISE"),
+                    }
+                } else {
+                    self.oid_value()?
+                };
+                Ok(Some(Definition::Object { name, kind, value, info }))
+            }
+            _ => {
+                // Some other value assignment, e.g. "x INTEGER ::= 5".
+                while self.peek() != Some(&Token::Assign) {
+                    self.skip_item()?;
+                }
+                self.pos += 1;
+                self.skip_item()?;
+                Ok(None)
+            }
+        }
+    }
+
+    fn type_assignment(&mut self, name: String) -> Result<Definition, MibError> {
+        if self.is_ident("TEXTUAL-CONVENTION") {
+            self.pos += 1;
+            let (info, _) = self.clauses()?;
+            let syntax = match info.syntax {
+                Some(syntax) => syntax,
+                None => return self.error("TEXTUAL-CONVENTION without SYNTAX"),
+            };
+            return Ok(Definition::Type {
+                name,
+                syntax,
+                display_hint: info.display_hint,
+                textual_convention: true,
+                description: info.description,
+            });
+        }
+        let syntax = self.syntax()?;
+        Ok(Definition::Type { name, syntax, display_hint: None, textual_convention: false, description: None })
+    }
+
+    /// Reads macro clauses up to `::=`, or the next definition after a
+    /// TEXTUAL-CONVENTION. Also returns a TRAP-TYPE's ENTERPRISE.
+    fn clauses(&mut self) -> Result<(ObjectInfo, Option<String>), MibError> {
+        let mut info = ObjectInfo::default();
+        let mut enterprise = None;
+        loop {
+            let clause = match self.peek() {
+                Some(Token::Assign) => return Ok((info, enterprise)),
+                Some(Token::Ident(word)) if CLAUSES.contains(&word.as_str()) => word.as_str(),
+                Some(Token::Ident(_)) => return Ok((info, enterprise)),
+                _ => return self.error("expected a clause"),
+            };
+            self.pos += 1;
+            match clause {
+                "SYNTAX" => info.syntax = Some(self.syntax()?),
+                "UNITS" => info.units = Some(self.string()?),
+                "MAX-ACCESS" | "ACCESS" => info.access = Some(self.ident()?),
+                "STATUS" => info.status = Some(self.ident()?),
+                "DESCRIPTION" => {
+                    // A MODULE-IDENTITY's REVISIONs have descriptions too.
+                    let description = self.string()?;
+                    info.description.get_or_insert(description);
+                }
+                "DISPLAY-HINT" => info.display_hint = Some(self.string()?),
+                "ENTERPRISE" => enterprise = Some(self.ident()?),
+                "INDEX" => info.index = self.index()?,
+                "AUGMENTS" => {
+                    self.expect(Token::LBrace)?;
+                    info.augments = Some(self.ident()?);
+                    self.expect(Token::RBrace)?;
+                }
+                _ => {
+                    // Skip this clause's arguments.
+                    while let Some(token) = self.peek() {
+                        match token {
+                            Token::Assign => break,
+                            Token::Ident(word) if CLAUSES.contains(&word.as_str()) => break,
+                            _ => self.skip_item()?,
+                        }
+                    }
+                }
+            }
+        }
+    }
+
+    fn index(&mut self) -> Result<Vec<IndexItem>, MibError> {
+        self.expect(Token::LBrace)?;
+        let mut index = Vec::new();
+        loop {
+            let implied = self.is_ident("IMPLIED");
+            if implied {
+                self.pos += 1;
+            }
+            index.push(IndexItem { name: self.ident()?, implied });
+            match self.next()? {
+                Token::Comma => (),
+                Token::RBrace => return Ok(index),
+                _ => {
+                    self.pos -= 1;
+                    return self.error("malformed INDEX");
+                }
+            }
+        }
+    }
+
+    fn oid_value(&mut self) -> Result<Vec<OidComponent>, MibError> {
+        self.expect(Token::LBrace)?;
+        let mut value = Vec::new();
+        loop {
+            match self.next()? {
+                Token::RBrace => return Ok(value),
+                Token::Number(n) if *n >= 0 && *n <= u32::MAX as i64 => value.push(OidComponent::Number(*n as u32)),
+                Token::Ident(name) => {
+                    if self.peek() == Some(&Token::LParen) {
+                        self.pos += 1;
+                        let n = match u32::try_from(self.number()?) {
+                            Ok(n) => n,
+                            Err(_) => {
+                                self.pos -= 1;
+                                return self.error("sub-identifier out of range");
+                            }
+                        };
+                        self.expect(Token::RParen)?;
+                        value.push(OidComponent::NamedNumber(name.clone(), n));
+                    } else {
+                        value.push(OidComponent::Name(name.clone()));
+                    }
+                }
+                _ => {
+                    self.pos -= 1;
+                    return self.error("malformed OBJECT IDENTIFIER value");
+                }
+            }
+        }
+    }
+
+    /// `{ name(n), ... }` of an enumerated INTEGER or BITS.
+    fn named_numbers(&mut self) -> Result<Vec<(String, i64)>, MibError> {
+        self.expect(Token::LBrace)?;
+        let mut named = Vec::new();
+        loop {
+            let name = self.ident()?;
+            self.expect(Token::LParen)?;
+            let n = self.number()?;
+            self.expect(Token::RParen)?;
+            named.push((name, n));
+            match self.next()? {
+                Token::Comma => (),
+                Token::RBrace => return Ok(named),
+                _ => {
+                    self.pos -= 1;
+                    return self.error("malformed enumeration");
+                }
+            }
+        }
+    }
+
+    /// Skips a subtype constraint such as `(SIZE (0..255))`, if present.
+    fn constraint(&mut self) -> Result<(), MibError> {
+        if self.peek() == Some(&Token::LParen) {
+            self.skip_item()?;
+        }
+        Ok(())
+    }
+
+    fn syntax(&mut self) -> Result<Syntax, MibError> {
+        // Tags as in "[APPLICATION 0] IMPLICIT" in SNMPv2-SMI
+        if self.peek() == Some(&Token::LBracket) {
+            self.skip_item()?;
+        }
+        if self.is_ident("IMPLICIT") || self.is_ident("EXPLICIT") {
+            self.pos += 1;
+        }
+        let word = self.ident()?;
+        let syntax = match word.as_str() {
+            "INTEGER" => {
+                let enums = if self.peek() == Some(&Token::LBrace) { self.named_numbers()? } else { Vec::new() };
+                Syntax::Integer(enums)
+            }
+            "OCTET" => {
+                self.expect_ident("STRING")?;
+                Syntax::OctetString
+            }
+            "OBJECT" => {
+                self.expect_ident("IDENTIFIER")?;
+                Syntax::ObjectIdentifier
+            }
+            "BITS" => {
+                let bits = self.named_numbers()?;
+                Syntax::Bits(bits.into_iter().map(|(name, n)| (name, n as u32)).collect())
+            }
+            "SEQUENCE" if self.is_ident("OF") => {
+                self.pos += 1;
+                Syntax::SequenceOf(self.ident()?)
+            }
+            "SEQUENCE" => {
+                self.expect(Token::LBrace)?;
+                let mut fields = Vec::new();
+                loop {
+                    let field = self.ident()?;
+                    fields.push((field, self.syntax()?));
+                    match self.next()? {
+                        Token::Comma => (),
+                        Token::RBrace => break,
+                        _ => {
+                            self.pos -= 1;
+                            return self.error("malformed SEQUENCE");
+                        }
+                    }
+                }
+                Syntax::Sequence(fields)
+            }
+            "CHOICE" => {
+                self.skip_item()?;
+                Syntax::Other
+            }
+            _ => {
+                let enums = if self.peek() == Some(&Token::LBrace) { self.named_numbers()? } else { Vec::new() };
+                Syntax::Named { name: word, enums }
+            }
+        };
+        self.constraint()?;
+        Ok(syntax)
+    }
+}
+
+/// Parses every module in `toks`.
+pub(crate) fn parse(toks: &[Tok]) -> Result<Vec<Module>, MibError> {
+    let mut parser = Parser { toks, pos: 0 };
+    let mut modules = Vec::new();
+    while parser.peek().is_some() {
+        modules.push(parser.module()?);
+    }
+    Ok(modules)
+}
@@ -0,0 +1,285 @@
+//! Rendering values by the SYNTAX of the objects they belong to.
+
+use std::fmt::Write;
+use std::net::Ipv6Addr;
+
+use super::{Mib, Syntax};
+use crate::value::Value;
+
+/// How many textual conventions deep a SYNTAX is followed.
+const MAX_TYPE_DEPTH: usize = 8;
+
+/// What the SYNTAX of an object says about rendering its values.
+#[derive(Default)]
+struct Format<'m> {
+    enums: &'m [(String, i64)],
+    bits: &'m [(String, u32)],
+    hint: Option<&'m str>,
+    /// Names of the types the SYNTAX refers to, outermost first.
+    types: Vec<&'m str>,
+}
+
+impl<'m> Format<'m> {
+    fn is_type(&self, name: &str) -> bool {
+        self.types.contains(&name)
+    }
+}
+
+impl Mib {
+    /// Renders `value` as the object at `name` defines it, e.g. `up(1)` for
+    /// ifOperStatus or `00:1a:2b:3c:4d:5e` for ifPhysAddress. Values of
+    /// unknown objects are rendered by their type alone.
+    pub fn format_value(&self, name: &[u32], value: &Value) -> String {
+        let mut format = Format::default();
+        if let Some((node, _)) = self.lookup(name) {
+            if let Some(ref syntax) = node.syntax {
+                self.describe(&node.module, syntax, &mut format, 0);
+            }
+        }
+        self.render(value, &format)
+    }
+
+    /// Renders a variable binding as `MODULE::name.index = value`.
+    pub fn format_varbind(&self, name: &[u32], value: &Value) -> String {
+        format!("{} = {}", self.display(name), self.format_value(name, value))
+    }
+
+    /// Follows `syntax` through the textual conventions it refers to. The
+    /// outermost enumeration and DISPLAY-HINT win.
+    fn describe<'m>(&'m self, module: &str, syntax: &'m Syntax, format: &mut Format<'m>, depth: usize) {
+        match *syntax {
+            Syntax::Integer(ref enums) if format.enums.is_empty() => format.enums = enums,
+            Syntax::Bits(ref bits) if format.bits.is_empty() => format.bits = bits,
+            Syntax::Named { ref name, ref enums } => {
+                if format.enums.is_empty() {
+                    format.enums = enums;
+                }
+                format.types.push(name);
+                let def = match self.type_def(module, name) {
+                    Some(def) if depth < MAX_TYPE_DEPTH => def,
+                    _ => return,
+                };
+                if format.hint.is_none() {
+                    format.hint = def.display_hint.as_deref();
+                }
+                self.describe(&def.module, &def.syntax, format, depth + 1);
+            }
+            _ => (),
+        }
+    }
+
+    fn render(&self, value: &Value, format: &Format) -> String {
+        match *value {
+            Value::Integer(n) => {
+                if let Some((label, _)) = format.enums.iter().find(|&&(_, v)| v == n) {
+                    return format!("{}({})", label, n);
+                }
+                format.hint.and_then(|hint| integer_hint(hint, n)).unwrap_or_else(|| n.to_string())
+            }
+            Value::OctetString(bytes) => {
+                if format.is_type("InetAddress") || format.is_type("InetAddressIPv4")
+                    || format.is_type("InetAddressIPv6") {
+                    if let Some(addr) = inet_address(bytes) {
+                        return addr;
+                    }
+                }
+                if !format.bits.is_empty() {
+                    return bits(bytes, format.bits);
+                }
+                format.hint.and_then(|hint| octet_hint(hint, bytes)).unwrap_or_else(|| octets(bytes))
+            }
+            Value::Boolean(v)                   => v.to_string(),
+            Value::Null                         => "NULL".to_string(),
+            Value::ObjectIdentifier(ref obj_id) => obj_id.display_with(self).to_string(),
+            Value::IpAddress(a)                 => format!("{}.{}.{}.{}", a[0], a[1], a[2], a[3]),
+            Value::Counter32(n)
+            | Value::Unsigned32(n)              => n.to_string(),
+            Value::Counter64(n)                 => n.to_string(),
+            Value::Timeticks(n)                 => timeticks(n),
+            Value::Opaque(bytes)                => hex(bytes),
+            Value::EndOfMibView                 => "No more variables left in this MIB View".to_string(),
+            Value::NoSuchObject                 => "No Such Object available on this agent at this OID".to_string(),
+            Value::NoSuchInstance               => "No Such Instance currently exists at this OID".to_string(),
+            ref other                           => format!("{:?}", other),
+        }
+    }
+}
+
+/// Hex bytes separated by spaces, e.g. `00 1A FF`.
+fn hex(bytes: &[u8]) -> String {
+    let mut out = String::new();
+    for (i, b) in bytes.iter().enumerate() {
+        if i > 0 {
+            out.push(' ');
+        }
+        let _ = write!(out, "{:02X}", b);
+    }
+    out
+}
+
+/// An OCTET STRING without a hint: text if it is printable, else hex.
+fn octets(bytes: &[u8]) -> String {
+    match std::str::from_utf8(bytes) {
+        Ok(s) if s.chars().all(|c| !c.is_control() || c == '\n' || c == '\r' || c == '\t') => s.to_string(),
+        _ => hex(bytes),
+    }
+}
+
+/// Timeticks as net-snmp shows them, e.g. `(12345) 0:02:03.45`.
+fn timeticks(ticks: u32) -> String {
+    let days = ticks / 8_640_000;
+    let hours = ticks / 360_000 % 24;
+    let minutes = ticks / 6_000 % 60;
+    let seconds = ticks / 100 % 60;
+    let centis = ticks % 100;
+    let days = match days {
+        0 => String::new(),
+        1 => "1 day, ".to_string(),
+        n => format!("{} days, ", n),
+    };
+    format!("({}) {}{}:{:02}:{:02}.{:02}", ticks, days, hours, minutes, seconds, centis)
+}
+
+/// InetAddress (RFC 4001) values of the plain IPv4 and IPv6 lengths.
+fn inet_address(bytes: &[u8]) -> Option<String> {
+    match bytes.len() {
+        4 => Some(format!("{}.{}.{}.{}", bytes[0], bytes[1], bytes[2], bytes[3])),
+        16 => {
+            let mut octets = [0u8; 16];
+            octets.copy_from_slice(bytes);
+            Some(Ipv6Addr::from(octets).to_string())
+        }
+        _ => None,
+    }
+}
+
+/// The named bits set in a BITS value, e.g. `up(0) testing(2)`. Unnamed
+/// bits are shown by number.
+fn bits(bytes: &[u8], names: &[(String, u32)]) -> String {
+    let mut set = Vec::new();
+    for (i, b) in bytes.iter().enumerate() {
+        for bit in 0..8 {
+            if b & (0x80 >> bit) != 0 {
+                let n = (i * 8 + bit) as u32;
+                match names.iter().find(|&&(_, v)| v == n) {
+                    Some((label, _)) => set.push(format!("{}({})", label, n)),
+                    None => set.push(n.to_string()),
+                }
+            }
+        }
+    }
+    set.join(" ")
+}
+
+/// Formats an integer by a DISPLAY-HINT of `d[-n]`, `x`, `o` or `b`
+/// (RFC 2579 3.1).
+fn integer_hint(hint: &str, n: i64) -> Option<String> {
+    let mut chars = hint.chars();
+    let format = chars.next()?;
+    let rest = chars.as_str();
+    match format {
+        'd' if rest.is_empty() => Some(n.to_string()),
+        'd' => {
+            let places: usize = rest.strip_prefix('-')?.parse().ok()?;
+            let digits = format!("{:0width$}", n.unsigned_abs(), width = places + 1);
+            let (int, frac) = digits.split_at(digits.len() - places);
+            let sign = if n < 0 { "-" } else { "" };
+            Some(format!("{}{}.{}", sign, int, frac))
+        }
+        'x' if rest.is_empty() => Some(format!("{:x}", n)),
+        'o' if rest.is_empty() => Some(format!("{:o}", n)),
+        'b' if rest.is_empty() => Some(format!("{:b}", n)),
+        _ => None,
+    }
+}
+
+/// One specification of an octet DISPLAY-HINT, e.g. `*1x:`.
+struct HintSpec {
+    repeat: bool,
+    len: usize,
+    format: char,
+    separator: Option<char>,
+    terminator: Option<char>,
+}
+
+fn parse_octet_hint(hint: &str) -> Option<Vec<HintSpec>> {
+    let mut specs = Vec::new();
+    let mut chars = hint.chars().peekable();
+    while chars.peek().is_some() {
+        let repeat = chars.peek() == Some(&'*');
+        if repeat {
+            chars.next();
+        }
+        let mut len = String::new();
+        while let Some(&c) = chars.peek().filter(|c| c.is_ascii_digit()) {
+            len.push(c);
+            chars.next();
+        }
+        let len = len.parse().ok().filter(|&len| len > 0)?;
+        let format = chars.next().filter(|c| "dxoat".contains(*c))?;
+        // numbers are formatted from a u128
+        if "dxo".contains(format) && len > 16 {
+            return None;
+        }
+        let delimiter = |chars: &mut std::iter::Peekable<std::str::Chars>| {
+            chars.next_if(|&c| !c.is_ascii_digit() && c != '*')
+        };
+        let separator = delimiter(&mut chars);
+        let terminator = if repeat { delimiter(&mut chars) } else { None };
+        specs.push(HintSpec { repeat, len, format, separator, terminator });
+    }
+    if specs.is_empty() {
+        return None;
+    }
+    Some(specs)
+}
+
+/// Formats an OCTET STRING by a DISPLAY-HINT such as `1x:` or `255a`
+/// (RFC 2579 3.1). The last specification repeats until the value ends.
+fn octet_hint(hint: &str, bytes: &[u8]) -> Option<String> {
+    let specs = parse_octet_hint(hint)?;
+    let mut out = String::new();
+    let mut pos = 0;
+    let mut index = 0;
+    while pos < bytes.len() {
+        let spec = &specs[index.min(specs.len() - 1)];
+        index += 1;
+        let count = if spec.repeat {
+            pos += 1;
+            bytes[pos - 1] as usize
+        } else {
+            1
+        };
+        for i in 0..count {
+            if pos >= bytes.len() {
+                break;
+            }
+            let end = (pos + spec.len).min(bytes.len());
+            let chunk = &bytes[pos..end];
+            pos = end;
+            match spec.format {
+                'a' | 't' => out.push_str(&String::from_utf8_lossy(chunk)),
+                format => {
+                    let n = chunk.iter().fold(0u128, |n, &b| n << 8 | b as u128);
+                    let _ = match format {
+                        'd' => write!(out, "{}", n),
+                        'x' => write!(out, "{:0width$x}", n, width = chunk.len() * 2),
+                        _ => write!(out, "{:o}", n),
+                    };
+                }
+            }
+            if pos >= bytes.len() {
+                break;
+            }
+            let delimiter = if i + 1 == count && spec.terminator.is_some() {
+                spec.terminator
+            } else {
+                spec.separator
+            };
+            if let Some(c) = delimiter {
+                out.push(c);
+            }
+        }
+    }
+    Some(out)
+}
@@ -0,0 +1,275 @@
+//! Typed access to the MIB-II objects almost every agent implements: the
+//! system group (RFC 3418), the interface tables (RFC 2863) and the IPv4
+//! address table (RFC 1213), without loading any MIB files.
+//!
+//! ```no_run
+//! use std::time::Duration;
+//! use snmplib::{SyncSession, mib2};
+//!
+//! let mut sess = SyncSession::new("192.0.2.1:161", b"public", Some(Duration::from_secs(2)), 0).unwrap();
+//! let system = mib2::system(&mut sess).unwrap();
+//! println!("{} up {} ticks", system.name, system.up_time);
+//! for interface in mib2::interfaces(&mut sess).unwrap() {
+//!     println!("{}: {:?} {:?} octets in", interface.descr, interface.oper_status, interface.in_octets64());
+//! }
+//! ```
+
+use std::collections::BTreeMap;
+use std::convert::TryFrom;
+use std::net::Ipv4Addr;
+
+use super::{SnmpError, SnmpResult};
+use super::oid::Oid;
+use super::snmp::{ERRSTATUS_NOERROR, ERRSTATUS_NOSUCHNAME};
+use super::sync::SyncSession;
+use super::value::{FromValue, OwnedValue};
+
+pub const SYS_DESCR: &[u32] = crate::oid!("1.3.6.1.2.1.1.1.0");
+pub const SYS_OBJECT_ID: &[u32] = crate::oid!("1.3.6.1.2.1.1.2.0");
+pub const SYS_UP_TIME: &[u32] = crate::oid!("1.3.6.1.2.1.1.3.0");
+pub const SYS_CONTACT: &[u32] = crate::oid!("1.3.6.1.2.1.1.4.0");
+pub const SYS_NAME: &[u32] = crate::oid!("1.3.6.1.2.1.1.5.0");
+pub const SYS_LOCATION: &[u32] = crate::oid!("1.3.6.1.2.1.1.6.0");
+
+pub const IF_INDEX: &[u32] = crate::oid!("1.3.6.1.2.1.2.2.1.1");
+pub const IF_DESCR: &[u32] = crate::oid!("1.3.6.1.2.1.2.2.1.2");
+pub const IF_TYPE: &[u32] = crate::oid!("1.3.6.1.2.1.2.2.1.3");
+pub const IF_MTU: &[u32] = crate::oid!("1.3.6.1.2.1.2.2.1.4");
+pub const IF_SPEED: &[u32] = crate::oid!("1.3.6.1.2.1.2.2.1.5");
+pub const IF_PHYS_ADDRESS: &[u32] = crate::oid!("1.3.6.1.2.1.2.2.1.6");
+pub const IF_ADMIN_STATUS: &[u32] = crate::oid!("1.3.6.1.2.1.2.2.1.7");
+pub const IF_OPER_STATUS: &[u32] = crate::oid!("1.3.6.1.2.1.2.2.1.8");
+pub const IF_LAST_CHANGE: &[u32] = crate::oid!("1.3.6.1.2.1.2.2.1.9");
+pub const IF_IN_OCTETS: &[u32] = crate::oid!("1.3.6.1.2.1.2.2.1.10");
+pub const IF_IN_UCAST_PKTS: &[u32] = crate::oid!("1.3.6.1.2.1.2.2.1.11");
+pub const IF_IN_DISCARDS: &[u32] = crate::oid!("1.3.6.1.2.1.2.2.1.13");
+pub const IF_IN_ERRORS: &[u32] = crate::oid!("1.3.6.1.2.1.2.2.1.14");
+pub const IF_OUT_OCTETS: &[u32] = crate::oid!("1.3.6.1.2.1.2.2.1.16");
+pub const IF_OUT_UCAST_PKTS: &[u32] = crate::oid!("1.3.6.1.2.1.2.2.1.17");
+pub const IF_OUT_DISCARDS: &[u32] = crate::oid!("1.3.6.1.2.1.2.2.1.19");
+pub const IF_OUT_ERRORS: &[u32] = crate::oid!("1.3.6.1.2.1.2.2.1.20");
+
+pub const IF_NAME: &[u32] = crate::oid!("1.3.6.1.2.1.31.1.1.1.1");
+pub const IF_HC_IN_OCTETS: &[u32] = crate::oid!("1.3.6.1.2.1.31.1.1.1.6");
+pub const IF_HC_OUT_OCTETS: &[u32] = crate::oid!("1.3.6.1.2.1.31.1.1.1.10");
+pub const IF_HIGH_SPEED: &[u32] = crate::oid!("1.3.6.1.2.1.31.1.1.1.15");
+pub const IF_ALIAS: &[u32] = crate::oid!("1.3.6.1.2.1.31.1.1.1.18");
+
+pub const IP_AD_ENT_ADDR: &[u32] = crate::oid!("1.3.6.1.2.1.4.20.1.1");
+pub const IP_AD_ENT_IF_INDEX: &[u32] = crate::oid!("1.3.6.1.2.1.4.20.1.2");
+pub const IP_AD_ENT_NET_MASK: &[u32] = crate::oid!("1.3.6.1.2.1.4.20.1.3");
+
+/// Variable bindings asked for per GetBulkRequest, which keeps responses
+/// for wide tables well within a UDP datagram.
+const MAX_BULK_VARBINDS: usize = 48;
+
+/// The system group. Objects the agent does not have are left empty.
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct System {
+    pub descr: String,
+    pub object_id: Oid,
+    /// Hundredths of a second since the agent was (re)initialized.
+    pub up_time: u32,
+    pub contact: String,
+    pub name: String,
+    pub location: String,
+}
+
+/// ifAdminStatus and ifOperStatus.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum IfStatus {
+    Up,
+    Down,
+    Testing,
+    Unknown,
+    Dormant,
+    NotPresent,
+    LowerLayerDown,
+    /// A value outside the enumeration.
+    Other(i64),
+}
+
+impl From<i64> for IfStatus {
+    fn from(n: i64) -> IfStatus {
+        match n {
+            1 => IfStatus::Up,
+            2 => IfStatus::Down,
+            3 => IfStatus::Testing,
+            4 => IfStatus::Unknown,
+            5 => IfStatus::Dormant,
+            6 => IfStatus::NotPresent,
+            7 => IfStatus::LowerLayerDown,
+            n => IfStatus::Other(n),
+        }
+    }
+}
+
+/// An ifTable row joined with its ifXTable extension. ifXTable columns are
+/// `None` on agents without IF-MIB.
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct Interface {
+    pub index: u32,
+    pub descr: String,
+    /// IANAifType, e.g. 6 for ethernetCsmacd.
+    pub if_type: Option<i64>,
+    pub mtu: Option<i64>,
+    /// Bits per second, saturating at 4294967295; see `high_speed`.
+    pub speed: Option<u32>,
+    pub phys_address: Vec<u8>,
+    pub admin_status: Option<IfStatus>,
+    pub oper_status: Option<IfStatus>,
+    pub last_change: Option<u32>,
+    pub in_octets: Option<u32>,
+    pub in_ucast_pkts: Option<u32>,
+    pub in_discards: Option<u32>,
+    pub in_errors: Option<u32>,
+    pub out_octets: Option<u32>,
+    pub out_ucast_pkts: Option<u32>,
+    pub out_discards: Option<u32>,
+    pub out_errors: Option<u32>,
+
+    pub name: Option<String>,
+    pub alias: Option<String>,
+    /// Megabits per second.
+    pub high_speed: Option<u32>,
+    pub hc_in_octets: Option<u64>,
+    pub hc_out_octets: Option<u64>,
+}
+
+impl Interface {
+    /// Received octets, from the 64-bit counter where the agent has one.
+    pub fn in_octets64(&self) -> Option<u64> {
+        self.hc_in_octets.or_else(|| self.in_octets.map(u64::from))
+    }
+
+    /// Sent octets, from the 64-bit counter where the agent has one.
+    pub fn out_octets64(&self) -> Option<u64> {
+        self.hc_out_octets.or_else(|| self.out_octets.map(u64::from))
+    }
+}
+
+/// An ipAddrTable row.
+#[derive(Debug, Clone, PartialEq)]
+pub struct IpAddrEntry {
+    pub addr: Ipv4Addr,
+    pub if_index: Option<u32>,
+    pub net_mask: Option<Ipv4Addr>,
+}
+
+/// Gets an object of the system group, or `None` if the agent lacks it.
+fn get_scalar<T: FromValue>(sess: &mut SyncSession, name: &[u32]) -> SnmpResult<Option<T>> {
+    let resp = sess.get(name)?;
+    match resp.error_status {
+        ERRSTATUS_NOERROR => (),
+        // SNMPv1 agents report a missing object as noSuchName.
+        ERRSTATUS_NOSUCHNAME => return Ok(None),
+        status => return Err(SnmpError::ErrorStatus { status, index: resp.error_index }),
+    }
+    match resp.varbinds.clone().next() {
+        Some((_, value)) => Ok(T::from_value(&value.into_owned()?)),
+        None => Ok(None),
+    }
+}
+
+/// Fetches the system group with one GetRequest per object.
+pub fn system(sess: &mut SyncSession) -> SnmpResult<System> {
+    Ok(System {
+        descr: get_scalar(sess, SYS_DESCR)?.unwrap_or_default(),
+        object_id: get_scalar(sess, SYS_OBJECT_ID)?.unwrap_or_default(),
+        up_time: get_scalar(sess, SYS_UP_TIME)?.unwrap_or_default(),
+        contact: get_scalar(sess, SYS_CONTACT)?.unwrap_or_default(),
+        name: get_scalar(sess, SYS_NAME)?.unwrap_or_default(),
+        location: get_scalar(sess, SYS_LOCATION)?.unwrap_or_default(),
+    })
+}
+
+type Rows = BTreeMap<Vec<u32>, Vec<Option<OwnedValue>>>;
+
+/// Retrieves table columns with GETBULK, or by walking each column in
+/// SNMPv1.
+fn columns(sess: &mut SyncSession, columns: &[&[u32]]) -> SnmpResult<Rows> {
+    let max_repetitions = (MAX_BULK_VARBINDS / columns.len()).max(1) as u32;
+    match sess.table(columns, max_repetitions) {
+        Ok(table) => Ok(table.rows().map(|(index, cells)| (index.to_vec(), cells.to_vec())).collect()),
+        Err(SnmpError::UnsupportedVersion) => {
+            let mut rows = Rows::new();
+            for (i, &column) in columns.iter().enumerate() {
+                for item in sess.walk(column) {
+                    let (name, value) = item?;
+                    let row = rows.entry(name[column.len()..].to_vec()).or_insert_with(|| vec![None; columns.len()]);
+                    row[i] = Some(value);
+                }
+            }
+            Ok(rows)
+        }
+        Err(err) => Err(err),
+    }
+}
+
+fn cell<T: FromValue>(cells: &[Option<OwnedValue>], column: usize) -> Option<T> {
+    cells.get(column).and_then(|cell| cell.as_ref()).and_then(T::from_value)
+}
+
+/// Fetches ifTable and ifXTable, in ifIndex order.
+pub fn interfaces(sess: &mut SyncSession) -> SnmpResult<Vec<Interface>> {
+    let if_table = columns(sess, &[
+        IF_DESCR, IF_TYPE, IF_MTU, IF_SPEED, IF_PHYS_ADDRESS, IF_ADMIN_STATUS, IF_OPER_STATUS,
+        IF_LAST_CHANGE, IF_IN_OCTETS, IF_IN_UCAST_PKTS, IF_IN_DISCARDS, IF_IN_ERRORS, IF_OUT_OCTETS,
+        IF_OUT_UCAST_PKTS, IF_OUT_DISCARDS, IF_OUT_ERRORS,
+    ])?;
+    let if_x_table = columns(sess, &[IF_NAME, IF_HC_IN_OCTETS, IF_HC_OUT_OCTETS, IF_HIGH_SPEED, IF_ALIAS])?;
+
+    let mut interfaces = Vec::new();
+    for (index, c) in &if_table {
+        let if_index = match *index.as_slice() {
+            [if_index] => if_index,
+            _ => continue,
+        };
+        let mut interface = Interface {
+            index: if_index,
+            descr: cell(c, 0).unwrap_or_default(),
+            if_type: cell(c, 1),
+            mtu: cell(c, 2),
+            speed: cell(c, 3),
+            phys_address: cell(c, 4).unwrap_or_default(),
+            admin_status: cell::<i64>(c, 5).map(IfStatus::from),
+            oper_status: cell::<i64>(c, 6).map(IfStatus::from),
+            last_change: cell(c, 7),
+            in_octets: cell(c, 8),
+            in_ucast_pkts: cell(c, 9),
+            in_discards: cell(c, 10),
+            in_errors: cell(c, 11),
+            out_octets: cell(c, 12),
+            out_ucast_pkts: cell(c, 13),
+            out_discards: cell(c, 14),
+            out_errors: cell(c, 15),
+            ..Interface::default()
+        };
+        if let Some(x) = if_x_table.get(index) {
+            interface.name = cell(x, 0);
+            interface.hc_in_octets = cell(x, 1);
+            interface.hc_out_octets = cell(x, 2);
+            interface.high_speed = cell(x, 3);
+            interface.alias = cell(x, 4);
+        }
+        interfaces.push(interface);
+    }
+    Ok(interfaces)
+}
+
+/// Fetches the IPv4 addresses from ipAddrTable, in address order.
+pub fn ip_addresses(sess: &mut SyncSession) -> SnmpResult<Vec<IpAddrEntry>> {
+    let table = columns(sess, &[IP_AD_ENT_ADDR, IP_AD_ENT_IF_INDEX, IP_AD_ENT_NET_MASK])?;
+    Ok(table.iter().filter_map(|(index, c)| {
+        // The index is the address itself.
+        let addr = match *index.as_slice() {
+            [a, b, c, d] if [a, b, c, d].iter().all(|&n| n <= 255) => Ipv4Addr::new(a as u8, b as u8, c as u8, d as u8),
+            _ => return None,
+        };
+        Some(IpAddrEntry {
+            addr,
+            if_index: cell::<i64>(c, 1).and_then(|n| u32::try_from(n).ok()),
+            net_mask: cell(c, 2),
+        })
+    }).collect())
+}
@@ -0,0 +1,222 @@
+//! Many agents over one UDP socket.
+//!
+//! A `Client` owns a single unconnected socket. Requests are dispatched to
+//! any number of agents without waiting; a background thread routes each
+//! incoming response to its waiter by (source address, request-id).
+//!
+//! ```no_run
+//! use std::time::Duration;
+//! use snmplib::mux::{Client, Target};
+//!
+//! let client = Client::new("0.0.0.0:0").unwrap();
+//! let sys_descr = &[1, 3, 6, 1, 2, 1, 1, 1, 0];
+//! let pending: Vec<_> = ["192.0.2.1:161", "192.0.2.2:161"].iter()
+//!     .map(|addr| Target::v2c(addr.parse().unwrap(), b"public"))
+//!     .map(|target| client.get(&target, sys_descr, Duration::from_secs(2)))
+//!     .collect();
+//! for request in pending {
+//!     if let Ok(resp) = request.and_then(|request| request.wait()) {
+//!         println!("{}: {:?}", resp.source(), resp.pdu().varbinds);
+//!     }
+//! }
+//! ```
+
+use std::collections::HashMap;
+use std::io;
+use std::net::{SocketAddr, ToSocketAddrs, UdpSocket};
+use std::sync::{Arc, Mutex, Weak};
+use std::sync::atomic::{AtomicI32, Ordering};
+use std::sync::mpsc::{self, Receiver, RecvTimeoutError, Sender};
+use std::thread;
+use std::time::{Duration, Instant};
+
+use super::BUFFER_SIZE;
+use super::pdu;
+use super::{SnmpError, SnmpResult};
+use super::snmp::*;
+use super::value::Value;
+
+/// How often the dispatcher thread checks whether its client is gone.
+const DISPATCH_POLL: Duration = Duration::from_millis(200);
+
+type Waiters = Mutex<HashMap<(SocketAddr, i32), Sender<Vec<u8>>>>;
+
+/// An agent addressed by a `Client`.
+#[derive(Debug, Clone)]
+pub struct Target {
+    addr: SocketAddr,
+    version: i64,
+    community: Vec<u8>,
+}
+
+impl Target {
+    pub fn v1(addr: SocketAddr, community: &[u8]) -> Target {
+        Target { addr, version: VERSION_1, community: community.to_vec() }
+    }
+
+    pub fn v2c(addr: SocketAddr, community: &[u8]) -> Target {
+        Target { addr, version: VERSION_2, community: community.to_vec() }
+    }
+
+    pub fn addr(&self) -> SocketAddr {
+        self.addr
+    }
+}
+
+/// Community-based client multiplexing requests to many agents over one
+/// socket.
+pub struct Client {
+    socket: UdpSocket,
+    waiters: Arc<Waiters>,
+    req_id: AtomicI32,
+}
+
+impl Client {
+    /// Binds the shared socket and starts the dispatcher thread, which exits
+    /// once the client is dropped.
+    pub fn new<A: ToSocketAddrs>(bind_addr: A) -> io::Result<Client> {
+        let socket = UdpSocket::bind(bind_addr)?;
+        let recv_socket = socket.try_clone()?;
+        recv_socket.set_read_timeout(Some(DISPATCH_POLL))?;
+        let waiters = Arc::new(Mutex::new(HashMap::new()));
+        let weak = Arc::downgrade(&waiters);
+        thread::Builder::new()
+            .name("snmp-mux".into())
+            .spawn(move || dispatch(recv_socket, weak))?;
+        Ok(Client {
+            socket,
+            waiters,
+            req_id: AtomicI32::new(0),
+        })
+    }
+
+    pub fn local_addr(&self) -> io::Result<SocketAddr> {
+        self.socket.local_addr()
+    }
+
+    /// Encodes the PDU written by `f` for `target` and sends it, registering
+    /// a waiter before the datagram leaves.
+    fn send<F>(&self, target: &Target, timeout: Duration, f: F) -> SnmpResult<Pending>
+        where F: FnOnce(&mut pdu::Buf, i32)
+    {
+        let req_id = self.req_id.fetch_add(1, Ordering::Relaxed);
+        let mut buf = pdu::Buf::default();
+        let mut f = Some(f);
+        pdu::build_community_message(target.version, &target.community, &mut buf, |buf| {
+            if let Some(f) = f.take() {
+                f(buf, req_id);
+            }
+        });
+
+        let (tx, rx) = mpsc::channel();
+        let key = (target.addr, req_id);
+        self.waiters.lock().unwrap().insert(key, tx);
+        if self.socket.send_to(&buf[..], target.addr).is_err() {
+            self.waiters.lock().unwrap().remove(&key);
+            return Err(SnmpError::SendError);
+        }
+        Ok(Pending {
+            target: target.clone(),
+            req_id,
+            deadline: Instant::now() + timeout,
+            rx,
+            waiters: Arc::clone(&self.waiters),
+        })
+    }
+
+    pub fn get(&self, target: &Target, name: &[u32], timeout: Duration) -> SnmpResult<Pending> {
+        self.send(target, timeout, |buf, req_id| pdu::push_get(buf, req_id, name))
+    }
+
+    pub fn getnext(&self, target: &Target, name: &[u32], timeout: Duration) -> SnmpResult<Pending> {
+        self.send(target, timeout, |buf, req_id| pdu::push_getnext(buf, req_id, name))
+    }
+
+    pub fn getbulk(&self, target: &Target, names: &[&[u32]], non_repeaters: u32, max_repetitions: u32,
+                   timeout: Duration) -> SnmpResult<Pending> {
+        if target.version == VERSION_1 {
+            return Err(SnmpError::UnsupportedVersion);
+        }
+        self.send(target, timeout, |buf, req_id| {
+            pdu::push_getbulk(buf, req_id, names, non_repeaters, max_repetitions)
+        })
+    }
+
+    pub fn set(&self, target: &Target, values: &[(&[u32], Value)], timeout: Duration) -> SnmpResult<Pending> {
+        self.send(target, timeout, |buf, req_id| pdu::push_set(buf, req_id, values))
+    }
+}
+
+fn dispatch(socket: UdpSocket, waiters: Weak<Waiters>) {
+    let mut buf = [0u8; BUFFER_SIZE];
+    loop {
+        let received = socket.recv_from(&mut buf);
+        let waiters = match waiters.upgrade() {
+            Some(waiters) => waiters,
+            None => return,
+        };
+        if let Ok((len, source)) = received {
+            let req_id = match SnmpPdu::from_bytes(&buf[..len]) {
+                Ok(pdu) => pdu.req_id,
+                Err(_) => continue,
+            };
+            // Responses nobody waits for (late or unsolicited) are dropped.
+            if let Some(tx) = waiters.lock().unwrap().remove(&(source, req_id)) {
+                let _ = tx.send(buf[..len].to_vec());
+            }
+        }
+    }
+}
+
+/// A request in flight.
+pub struct Pending {
+    target: Target,
+    req_id: i32,
+    deadline: Instant,
+    rx: Receiver<Vec<u8>>,
+    waiters: Arc<Waiters>,
+}
+
+impl Pending {
+    pub fn target(&self) -> &Target {
+        &self.target
+    }
+
+    /// Blocks until the response arrives or the request's timeout elapses.
+    pub fn wait(self) -> SnmpResult<Response> {
+        let remaining = self.deadline.saturating_duration_since(Instant::now());
+        let bytes = match self.rx.recv_timeout(remaining) {
+            Ok(bytes) => bytes,
+            Err(RecvTimeoutError::Timeout) => return Err(SnmpError::Timeout),
+            Err(RecvTimeoutError::Disconnected) => return Err(SnmpError::ReceiveError),
+        };
+        SnmpPdu::from_community_response(&bytes, self.target.version, &self.target.community)?
+            .check_response(self.req_id)?;
+        Ok(Response {
+            source: self.target.addr,
+            bytes,
+        })
+    }
+}
+
+impl Drop for Pending {
+    fn drop(&mut self) {
+        self.waiters.lock().unwrap().remove(&(self.target.addr, self.req_id));
+    }
+}
+
+/// A validated response received by a `Client`.
+pub struct Response {
+    source: SocketAddr,
+    bytes: Vec<u8>,
+}
+
+impl Response {
+    pub fn source(&self) -> SocketAddr {
+        self.source
+    }
+
+    pub fn pdu(&self) -> SnmpPdu<'_> {
+        SnmpPdu::from_bytes(&self.bytes).expect("response was decoded on receipt")
+    }
+}
@@ -1,17 +1,48 @@
 
 use std::fmt;
-use std::mem;
 use super::{SnmpResult, SnmpError};
+use super::mib::{Mib, Symbolic};
 
-/// Wrapper around raw bytes representing an ASN.1 OBJECT IDENTIFIER.
-#[derive(PartialEq)]
+/// An ASN.1 OBJECT IDENTIFIER borrowed either as raw BER content bytes, as
+/// decoded from a message, or as a list of sub-IDs.
+///
+/// ```
+/// use snmplib::{ObjIdBuf, ObjectIdentifier};
+///
+/// let decoded = ObjectIdentifier::from_bytes(&[0x2b, 0x06, 0x01, 0x02, 0x01]);
+/// assert_eq!(decoded, ObjectIdentifier::from_name(&[1, 3, 6, 1, 2, 1]));
+///
+/// let mut buf: ObjIdBuf = [0; 128];
+/// assert_eq!(decoded.read_name(&mut buf).unwrap(), &[1, 3, 6, 1, 2, 1]);
+/// assert_eq!(decoded.to_string(), "1.3.6.1.2.1");
+/// ```
+#[derive(Clone, Copy)]
 pub struct ObjectIdentifier<'a> {
-    inner: &'a [u8],
+    inner: Repr<'a>,
+}
+
+#[derive(Clone, Copy)]
+enum Repr<'a> {
+    Ber(&'a [u8]),
+    Name(&'a [u32]),
 }
 
 impl<'a> fmt::Debug for ObjectIdentifier<'a> {
     fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
-        f.debug_list().entries(self.inner).finish()
+        match self.inner {
+            Repr::Ber(bytes) => f.debug_list().entries(bytes).finish(),
+            Repr::Name(name) => f.debug_list().entries(name).finish(),
+        }
+    }
+}
+
+impl<'a, 'b> PartialEq<ObjectIdentifier<'b>> for ObjectIdentifier<'a> {
+    fn eq(&self, other: &ObjectIdentifier<'b>) -> bool {
+        match (self.inner, other.inner) {
+            (Repr::Ber(a), Repr::Ber(b)) => a == b,
+            (_, Repr::Name(name)) => self == name,
+            (Repr::Name(name), _) => other == name,
+        }
     }
 }
 
@@ -19,9 +50,8 @@ pub type ObjIdBuf = [u32; 128];
 
 impl<'a> fmt::Display for ObjectIdentifier<'a> {
     fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
-        let mut buf: ObjIdBuf = unsafe { mem::uninitialized() };
         let mut first = true;
-        match self.read_name(&mut buf) {
+        match self.to_vec() {
             Ok(name) => {
                 for subid in name {
                     if first {
@@ -40,11 +70,9 @@ impl<'a> fmt::Display for ObjectIdentifier<'a> {
 
 impl<'a> PartialEq<[u32]> for ObjectIdentifier<'a> {
     fn eq(&self, other: &[u32]) -> bool {
-        let mut buf: ObjIdBuf = unsafe { mem::uninitialized() };
-        if let Ok(name) = self.read_name(&mut buf) {
-            name == other
-        } else {
-            false
+        match self.inner {
+            Repr::Name(name) => name == other,
+            Repr::Ber(_) => self.to_vec().is_ok_and(|name| name == other),
         }
     }
 }
@@ -56,49 +84,104 @@ impl<'a, 'b> PartialEq<&'b [u32]> for ObjectIdentifier<'a> {
 }
 
 impl<'a> ObjectIdentifier<'a> {
-    pub fn from_bytes(bytes: &[u8]) -> ObjectIdentifier {
+    pub fn from_bytes(bytes: &[u8]) -> ObjectIdentifier<'_> {
         ObjectIdentifier {
-            inner: bytes,
+            inner: Repr::Ber(bytes),
+        }
+    }
+
+    /// Borrows a list of sub-IDs, e.g. to put one in a `Value` for encoding.
+    pub fn from_name(name: &[u32]) -> ObjectIdentifier<'_> {
+        ObjectIdentifier {
+            inner: Repr::Name(name),
+        }
+    }
+
+    /// The sub-IDs, if this was created from them rather than from BER.
+    pub fn name(&self) -> Option<&'a [u32]> {
+        match self.inner {
+            Repr::Name(name) => Some(name),
+            Repr::Ber(_) => None,
         }
     }
 
     /// Reads out the OBJECT IDENTIFIER sub-IDs as a slice of u32s.
     /// Caller must provide storage for 128 sub-IDs.
     pub fn read_name<'b>(&self, out: &'b mut ObjIdBuf) -> SnmpResult<&'b [u32]> {
-        let input = self.inner;
-        let output = &mut out[..];
-        if input.len() < 2 {
-            return Err(SnmpError::AsnInvalidLen);
-        }
-        let subid1 = (input[0] / 40) as u32;
-        let subid2 = (input[0] % 40) as u32;
-        output[0] = subid1;
-        output[1] = subid2;
-        let mut pos = 2;
-        let mut cur_oid: u32 = 0;
-        let mut is_done = false;
-        for b in &input[1..] {
-            if pos == output.len() {
-                return Err(SnmpError::AsnEof);
-            }
-            is_done = b & 0b10000000 == 0;
-            let val = b & 0b01111111;
-            cur_oid = cur_oid.checked_shl(7).ok_or(SnmpError::AsnIntOverflow)?;
-            cur_oid |= val as u32;
-            if is_done {
-                output[pos] = cur_oid;
-                pos += 1;
-                cur_oid = 0;
+        let input = match self.inner {
+            Repr::Ber(bytes) => bytes,
+            Repr::Name(name) => {
+                if name.len() > out.len() {
+                    return Err(SnmpError::AsnEof);
+                }
+                out[..name.len()].copy_from_slice(name);
+                return Ok(&out[..name.len()]);
             }
-        }
-        if !is_done {
-            Err(SnmpError::AsnParseError)
-        } else {
-            Ok(&output[..pos])
+        };
+        let mut pos = 0;
+        decode(input, |subid| {
+            *out.get_mut(pos).ok_or(SnmpError::AsnEof)? = subid;
+            pos += 1;
+            Ok(())
+        })?;
+        Ok(&out[..pos])
+    }
+
+    /// Reads out the sub-IDs into a new vector, without `read_name`'s limit
+    /// of 128 sub-IDs.
+    pub fn to_vec(self) -> SnmpResult<Vec<u32>> {
+        let input = match self.inner {
+            Repr::Ber(bytes) => bytes,
+            Repr::Name(name) => return Ok(name.to_vec()),
+        };
+        let mut output = Vec::with_capacity(input.len() + 1);
+        decode(input, |subid| {
+            output.push(subid);
+            Ok(())
+        })?;
+        Ok(output)
+    }
+
+    /// The BER content bytes, if this was decoded from them. An identifier
+    /// created from sub-IDs has none; `name` returns those instead.
+    pub fn raw(&self) -> Option<&'a [u8]> {
+        match self.inner {
+            Repr::Ber(bytes) => Some(bytes),
+            Repr::Name(_) => None,
         }
     }
 
-    pub fn raw(&self) -> &'a [u8] {
-        self.inner
+    /// Displays the OID by name, e.g. `IF-MIB::ifInOctets.3`.
+    pub fn display_with(self, mib: &Mib) -> Symbolic<'_> {
+        Symbolic::new(mib, self.to_vec())
     }
-}
\ No newline at end of file
+}
+
+/// Decodes the content bytes of an OBJECT IDENTIFIER, passing each sub-ID
+/// to `push`.
+fn decode<F>(input: &[u8], mut push: F) -> SnmpResult<()>
+    where F: FnMut(u32) -> SnmpResult<()>
+{
+    if input.len() < 2 {
+        return Err(SnmpError::AsnInvalidLen);
+    }
+    push((input[0] / 40) as u32)?;
+    push((input[0] % 40) as u32)?;
+    let mut cur_oid: u32 = 0;
+    let mut is_done = false;
+    for b in &input[1..] {
+        if cur_oid > u32::MAX >> 7 {
+            return Err(SnmpError::AsnIntOverflow);
+        }
+        is_done = b & 0b10000000 == 0;
+        cur_oid = (cur_oid << 7) | (b & 0b01111111) as u32;
+        if is_done {
+            push(cur_oid)?;
+            cur_oid = 0;
+        }
+    }
+    if !is_done {
+        return Err(SnmpError::AsnParseError);
+    }
+    Ok(())
+}
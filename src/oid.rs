@@ -0,0 +1,255 @@
+use std::convert::TryFrom;
+use std::fmt;
+use std::ops::Deref;
+use std::str::FromStr;
+
+use super::mib::{Mib, Symbolic};
+use super::objectidentifier::ObjectIdentifier;
+use super::{SnmpError, SnmpResult};
+
+/// An owned OBJECT IDENTIFIER of any length.
+///
+/// Ordering is lexicographic by sub-ID, which is the order agents walk the
+/// MIB in. An `Oid` dereferences to its sub-IDs, so it can be passed
+/// wherever a `&[u32]` name is taken.
+///
+/// ```
+/// use snmplib::Oid;
+///
+/// let if_descr: Oid = "1.3.6.1.2.1.2.2.1.2".parse().unwrap();
+/// let eth0 = if_descr.child(2);
+/// assert_eq!(eth0.to_string(), "1.3.6.1.2.1.2.2.1.2.2");
+/// assert!(eth0.starts_with(&if_descr));
+/// assert_eq!(eth0.strip_prefix(&if_descr), Some(&[2][..]));
+/// assert_eq!(eth0.parent(), Some(if_descr.clone()));
+/// assert!(if_descr < eth0 && eth0 < ".1.3.6.1.2.1.2.2.1.3".parse().unwrap());
+/// ```
+#[derive(Clone, Default, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub struct Oid {
+    subids: Vec<u32>,
+}
+
+impl Oid {
+    pub fn new(subids: Vec<u32>) -> Oid {
+        Oid { subids }
+    }
+
+    pub fn as_slice(&self) -> &[u32] {
+        &self.subids
+    }
+
+    pub fn into_vec(self) -> Vec<u32> {
+        self.subids
+    }
+
+    /// Whether `prefix` is this OID or one of its ancestors.
+    pub fn starts_with(&self, prefix: &[u32]) -> bool {
+        self.subids.starts_with(prefix)
+    }
+
+    /// The sub-IDs following `prefix`, e.g. the index of a table cell given
+    /// its column.
+    pub fn strip_prefix(&self, prefix: &[u32]) -> Option<&[u32]> {
+        self.subids.strip_prefix(prefix)
+    }
+
+    pub fn child(&self, subid: u32) -> Oid {
+        let mut subids = Vec::with_capacity(self.subids.len() + 1);
+        subids.extend_from_slice(&self.subids);
+        subids.push(subid);
+        Oid { subids }
+    }
+
+    /// This OID without its last sub-ID, or `None` if it is empty.
+    pub fn parent(&self) -> Option<Oid> {
+        self.subids.split_last().map(|(_, parent)| Oid::from(parent))
+    }
+
+    /// Borrows this as an `ObjectIdentifier`, e.g. to put it in a `Value`.
+    pub fn as_object_identifier(&self) -> ObjectIdentifier<'_> {
+        ObjectIdentifier::from_name(&self.subids)
+    }
+
+    /// Displays this OID by name, e.g. `IF-MIB::ifInOctets.3`.
+    pub fn display_with<'m>(&self, mib: &'m Mib) -> Symbolic<'m> {
+        mib.display(&self.subids)
+    }
+}
+
+impl Deref for Oid {
+    type Target = [u32];
+
+    fn deref(&self) -> &[u32] {
+        &self.subids
+    }
+}
+
+impl AsRef<[u32]> for Oid {
+    fn as_ref(&self) -> &[u32] {
+        &self.subids
+    }
+}
+
+impl From<Vec<u32>> for Oid {
+    fn from(subids: Vec<u32>) -> Oid {
+        Oid { subids }
+    }
+}
+
+impl<'a> From<&'a [u32]> for Oid {
+    fn from(subids: &'a [u32]) -> Oid {
+        Oid { subids: subids.to_vec() }
+    }
+}
+
+impl PartialEq<[u32]> for Oid {
+    fn eq(&self, other: &[u32]) -> bool {
+        self.subids[..] == *other
+    }
+}
+
+impl<'a> PartialEq<&'a [u32]> for Oid {
+    fn eq(&self, other: &&'a [u32]) -> bool {
+        self.subids[..] == **other
+    }
+}
+
+impl<'a> TryFrom<ObjectIdentifier<'a>> for Oid {
+    type Error = SnmpError;
+
+    fn try_from(obj_id: ObjectIdentifier<'a>) -> SnmpResult<Oid> {
+        obj_id.to_vec().map(Oid::from)
+    }
+}
+
+/// Parses dotted notation, with or without a leading dot.
+impl FromStr for Oid {
+    type Err = SnmpError;
+
+    fn from_str(s: &str) -> SnmpResult<Oid> {
+        let s = s.strip_prefix('.').unwrap_or(s);
+        if s.is_empty() {
+            return Err(SnmpError::InvalidOid);
+        }
+        s.split('.')
+            .map(|subid| {
+                if subid.is_empty() || !subid.bytes().all(|b| b.is_ascii_digit()) {
+                    return Err(SnmpError::InvalidOid);
+                }
+                subid.parse().map_err(|_| SnmpError::InvalidOid)
+            })
+            .collect::<SnmpResult<Vec<u32>>>()
+            .map(Oid::from)
+    }
+}
+
+impl fmt::Display for Oid {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        for (i, subid) in self.subids.iter().enumerate() {
+            if i > 0 {
+                f.write_str(".")?;
+            }
+            write!(f, "{}", subid)?;
+        }
+        Ok(())
+    }
+}
+
+impl fmt::Debug for Oid {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "Oid({})", self)
+    }
+}
+
+/// A validated `&'static [u32]` OID from a dotted string literal.
+///
+/// The string is parsed at compile time; a malformed OID, or one that can't
+/// be BER-encoded, fails the build.
+///
+/// ```
+/// use snmplib::oid;
+///
+/// const IF_IN_OCTETS: &[u32] = oid!("1.3.6.1.2.1.2.2.1.10");
+/// assert_eq!(IF_IN_OCTETS, &[1, 3, 6, 1, 2, 1, 2, 2, 1, 10]);
+/// assert_eq!(oid!(".1.3.6.1"), &[1, 3, 6, 1]);
+/// ```
+///
+/// ```compile_fail
+/// let _ = snmplib::oid!("1.3.6..1");
+/// ```
+///
+/// ```compile_fail
+/// let _ = snmplib::oid!("1.3.6.1.4294967296");
+/// ```
+#[macro_export]
+macro_rules! oid {
+    ($s:literal) => {{
+        const LEN: usize = $crate::__oid_len($s);
+        const SUBIDS: [u32; LEN] = $crate::__oid_parse::<LEN>($s);
+        &SUBIDS as &'static [u32]
+    }};
+}
+
+const fn skip_leading_dot(bytes: &[u8]) -> usize {
+    if !bytes.is_empty() && bytes[0] == b'.' { 1 } else { 0 }
+}
+
+/// Number of sub-IDs in a dotted OID; panics, failing const evaluation, on
+/// malformed input.
+#[doc(hidden)]
+pub const fn __oid_len(s: &str) -> usize {
+    let bytes = s.as_bytes();
+    let mut i = skip_leading_dot(bytes);
+    let mut count = 1;
+    let mut after_dot = true;
+    if i == bytes.len() {
+        panic!("empty OID");
+    }
+    while i < bytes.len() {
+        match bytes[i] {
+            b'.' => {
+                if after_dot {
+                    panic!("empty sub-ID in OID");
+                }
+                count += 1;
+                after_dot = true;
+            }
+            b'0'..=b'9' => after_dot = false,
+            _ => panic!("invalid character in OID"),
+        }
+        i += 1;
+    }
+    if after_dot {
+        panic!("OID ends with a dot");
+    }
+    count
+}
+
+/// Parses a dotted OID already checked by `__oid_len`.
+#[doc(hidden)]
+pub const fn __oid_parse<const N: usize>(s: &str) -> [u32; N] {
+    let bytes = s.as_bytes();
+    let mut out = [0u32; N];
+    let mut i = skip_leading_dot(bytes);
+    let mut n = 0;
+    while i < bytes.len() {
+        if bytes[i] == b'.' {
+            n += 1;
+        } else {
+            let digit = (bytes[i] - b'0') as u32;
+            out[n] = match out[n].checked_mul(10) {
+                Some(subid) => match subid.checked_add(digit) {
+                    Some(subid) => subid,
+                    None => panic!("sub-ID out of range"),
+                },
+                None => panic!("sub-ID out of range"),
+            };
+        }
+        i += 1;
+    }
+    // The first two sub-IDs share one BER byte.
+    if N < 2 || out[0] > 2 || out[1] >= 40 {
+        panic!("OID cannot be BER-encoded");
+    }
+    out
+}
@@ -1,7 +1,9 @@
 #![allow(dead_code)]
 
-use super::{BUFFER_SIZE, USIZE_LEN, asn1, snmp};
-use std::{fmt, mem, ops, ptr};
+use super::{BUFFER_SIZE, asn1, snmp, trap, v3};
+use super::snmp::{SnmpMessageType, SnmpPdu, TrapV1};
+use std::{fmt, mem, ops};
+use super::objectidentifier::ObjectIdentifier;
 use super::value::*;
 
 
@@ -22,7 +24,7 @@ impl Default for Buf {
     fn default() -> Buf {
         Buf {
             len: 0,
-            buf: unsafe { mem::uninitialized() },
+            buf: [0; BUFFER_SIZE],
         }
     }
 }
@@ -51,6 +53,10 @@ impl Buf {
         self.len += 1;
     }
 
+    fn bytes_mut(&mut self) -> &mut [u8] {
+        &mut self.buf[BUFFER_SIZE - self.len..]
+    }
+
     fn reset(&mut self) {
         self.len = 0;
     }
@@ -94,8 +100,8 @@ impl Buf {
             let length_len = mem::size_of::<usize>() - num_leading_nulls;
             let leading_byte = length_len as u8 | 0b1000_0000;
             self.scribble_bytes(|o| {
-                assert!(o.len() >= length_len + 1);
-                let bytes = unsafe { mem::transmute::<usize, [u8; USIZE_LEN]>(len.to_be()) };
+                assert!(o.len() > length_len);
+                let bytes = len.to_be_bytes();
                 let write_offset = o.len() - length_len - 1;
                 o[write_offset] = leading_byte;
                 o[write_offset + 1..].copy_from_slice(&bytes[num_leading_nulls..]);
@@ -147,44 +153,39 @@ impl Buf {
     }
 
     fn push_counter64(&mut self, n: u64) {
-        let len = self.push_i64(n as i64);
+        let len = if n > i64::MAX as u64 {
+            // a leading zero keeps the top bit from reading as a sign
+            self.push_chunk(&n.to_be_bytes());
+            self.push_byte(0);
+            9
+        } else {
+            self.push_i64(n as i64)
+        };
         self.push_length(len);
         self.push_byte(snmp::TYPE_COUNTER64);
     }
 
-    fn push_i64(&mut self, mut n: i64) -> usize {
+    fn push_i64(&mut self, n: i64) -> usize {
         let (null, num_null_bytes) = if !n.is_negative() {
             (0x00u8, (n.leading_zeros() / 8) as usize)
         } else {
             (0xffu8, ((!n).leading_zeros() / 8) as usize)
         };
-        n = n.to_be();
-        let count = unsafe {
-            let /*mut*/ wbuf = self.available();
-            let mut src_ptr = &n as *const i64 as *const u8;
-            let mut dst_ptr = wbuf.as_mut_ptr()
-                .offset((wbuf.len() - mem::size_of::<i64>()) as isize);
-            let mut count = mem::size_of::<i64>() - num_null_bytes;
-            if count == 0 {
-                count = 1;
-            }
-            // preserve sign
-            if (*(src_ptr.offset((mem::size_of::<i64>() - count) as isize)) ^ null) > 127u8 {
-                count += 1;
-            }
-            assert!(wbuf.len() >= count);
-            let offset = (mem::size_of::<i64>() - count) as isize;
-            src_ptr = src_ptr.offset(offset);
-            dst_ptr = dst_ptr.offset(offset);
-            ptr::copy_nonoverlapping(src_ptr, dst_ptr, count);
-            count
-        };
-        self.len += count;
+        let bytes = n.to_be_bytes();
+        let mut count = mem::size_of::<i64>() - num_null_bytes;
+        if count == 0 {
+            count = 1;
+        }
+        // preserve sign
+        if (bytes[mem::size_of::<i64>() - count] ^ null) > 127u8 {
+            count += 1;
+        }
+        self.push_chunk(&bytes[mem::size_of::<i64>() - count..]);
         count
     }
 
     fn push_boolean(&mut self, boolean: bool) {
-        if boolean == true {
+        if boolean {
             self.push_byte(0x1);
         }  else {
             self.push_byte(0x0);
@@ -250,142 +251,513 @@ impl Buf {
         self.push_byte(asn1::TYPE_OBJECTIDENTIFIER);
     }
 
+    /// An OBJECT IDENTIFIER as decoded, or from its sub-IDs.
+    fn push_objid(&mut self, objid: &ObjectIdentifier) {
+        match objid.raw() {
+            Some(raw) => self.push_object_identifier_raw(raw),
+            None      => self.push_object_identifier(objid.name().unwrap_or(&[])),
+        }
+    }
+
+    fn push_tlv(&mut self, ident: u8, content: &[u8]) {
+        self.push_chunk(content);
+        self.push_length(content.len());
+        self.push_byte(ident);
+    }
+
     fn push_octet_string(&mut self, bytes: &[u8]) {
         self.push_chunk(bytes);
         self.push_length(bytes.len());
         self.push_byte(asn1::TYPE_OCTETSTRING);
     }
-}
 
-pub fn build_get(community: &[u8], req_id: i32, name: &[u32], buf: &mut Buf) {
-    buf.reset();
-    buf.push_sequence(|buf| {
-        buf.push_constructed(snmp::MSG_GET, |buf| {
-            buf.push_sequence(|buf| {
-                buf.push_sequence(|buf| {
-                    buf.push_null(); // value
-                    buf.push_object_identifier(name); // name
-                })
+    /// Constructed values are written back as they were decoded.
+    fn push_value(&mut self, val: &Value) {
+        use Value::*;
+        match *val {
+            Boolean(b)                  => self.push_boolean(b),
+            Null                        => self.push_null(),
+            Integer(i)                  => self.push_integer(i),
+            OctetString(ostr)           => self.push_octet_string(ostr),
+            ObjectIdentifier(ref objid) => self.push_objid(objid),
+            IpAddress(ref ip)           => self.push_ipaddress(ip),
+            Counter32(i)                => self.push_counter32(i),
+            Unsigned32(i)               => self.push_unsigned32(i),
+            Timeticks(tt)               => self.push_timeticks(tt),
+            Opaque(bytes)               => self.push_opaque(bytes),
+            Counter64(i)                => self.push_counter64(i),
+            EndOfMibView                => self.push_endofmibview(),
+            NoSuchObject                => self.push_nosuchobject(),
+            NoSuchInstance              => self.push_nosuchinstance(),
+            _ => {
+                let (ident, content) = constructed_content(val).expect("constructed value");
+                self.push_tlv(ident, content)
+            }
+        }
+    }
+
+    fn push_varbinds(&mut self, values: &[(&[u32], Value)]) {
+        self.push_sequence(|buf| buf.push_bindings(values));
+    }
+
+    /// The variable bindings of a VarBindList, without the enclosing
+    /// SEQUENCE.
+    fn push_bindings(&mut self, values: &[(&[u32], Value)]) {
+        for (name, val) in values.iter().rev() {
+            self.push_sequence(|buf| {
+                buf.push_value(val);
+                buf.push_object_identifier(name); // name
             });
-            buf.push_integer(0); // error index
-            buf.push_integer(0); // error status
-            buf.push_integer(req_id as i64);
-        });
-        buf.push_octet_string(community);
-        buf.push_integer(snmp::VERSION_2 as i64);
-    });
+        }
+    }
 }
 
-pub fn build_getnext(community: &[u8], req_id: i32, name: &[u32], buf: &mut Buf) {
+/// Identifier and content octets of a constructed value.
+fn constructed_content<'a>(val: &Value<'a>) -> Option<(u8, &'a [u8])> {
+    use Value::*;
+    Some(match *val {
+        Sequence(ref rdr)           => (asn1::TYPE_SEQUENCE, rdr.remaining()),
+        Set(ref rdr)                => (asn1::TYPE_SET, rdr.remaining()),
+        Constructed(ident, ref rdr) => (ident, rdr.remaining()),
+        SnmpGetRequest(ref rdr)     => (snmp::MSG_GET, rdr.remaining()),
+        SnmpGetNextRequest(ref rdr) => (snmp::MSG_GET_NEXT, rdr.remaining()),
+        SnmpGetBulkRequest(ref rdr) => (snmp::MSG_GET_BULK, rdr.remaining()),
+        SnmpResponse(ref rdr)       => (snmp::MSG_RESPONSE, rdr.remaining()),
+        SnmpSetRequest(ref rdr)     => (snmp::MSG_SET, rdr.remaining()),
+        SnmpInformRequest(ref rdr)  => (snmp::MSG_INFORM, rdr.remaining()),
+        SnmpTrap(ref rdr)           => (snmp::MSG_TRAP, rdr.remaining()),
+        SnmpReport(ref rdr)         => (snmp::MSG_REPORT, rdr.remaining()),
+        _                           => return None,
+    })
+}
+
+/// Encoded size of a length field.
+fn length_len(len: usize) -> usize {
+    if len < 128 {
+        1
+    } else {
+        1 + mem::size_of::<usize>() - (len.leading_zeros() / 8) as usize
+    }
+}
+
+fn tlv_len(content_len: usize) -> usize {
+    1 + length_len(content_len) + content_len
+}
+
+/// Content size of an INTEGER as `Buf::push_i64` writes it.
+fn integer_len(n: i64) -> usize {
+    let significant = if n.is_negative() { !n } else { n };
+    let bytes = mem::size_of::<i64>() - (significant.leading_zeros() / 8) as usize;
+    let bytes = bytes.max(1);
+    // A sign byte is added when the top bit of the first byte disagrees.
+    if (significant >> (bytes * 8 - 1)) & 1 != 0 {
+        bytes + 1
+    } else {
+        bytes
+    }
+}
+
+/// Content size of an OBJECT IDENTIFIER, or `None` if it cannot be encoded.
+fn object_identifier_len(name: &[u32]) -> Option<usize> {
+    match *name {
+        [first, second, ref tail @ ..] if first < 3 && second < 40 => {
+            Some(1 + tail.iter().map(|&subid| (32 - subid.leading_zeros()).max(1).div_ceil(7) as usize).sum::<usize>())
+        }
+        _ => None,
+    }
+}
+
+/// Encoded size of a value, or `None` if it cannot be encoded.
+pub fn value_len(val: &Value) -> Option<usize> {
+    use Value::*;
+    let content = match *val {
+        Boolean(_)                  => 1,
+        Null | EndOfMibView
+        | NoSuchObject
+        | NoSuchInstance            => 0,
+        Integer(n)                  => integer_len(n),
+        OctetString(bytes)
+        | Opaque(bytes)             => bytes.len(),
+        ObjectIdentifier(ref objid) => match objid.raw() {
+            Some(raw)               => raw.len(),
+            None                    => object_identifier_len(objid.name()?)?,
+        },
+        IpAddress(_)                => 4,
+        Counter32(n)
+        | Unsigned32(n)
+        | Timeticks(n)              => integer_len(n.into()),
+        Counter64(n) if n > i64::MAX as u64 => 9,
+        Counter64(n)                => integer_len(n as i64),
+        _                           => constructed_content(val)?.1.len(),
+    };
+    Some(tlv_len(content))
+}
+
+/// Encoded size of a variable binding, or `None` if it cannot be encoded.
+pub fn varbind_len(name: &[u32], val: &Value) -> Option<usize> {
+    Some(tlv_len(tlv_len(object_identifier_len(name)?) + value_len(val)?))
+}
+
+pub fn build_response(version: i64, community: &[u8], req_id: i32, values: &[(&[u32], Value)], buf: &mut Buf) {
+    build_community_message(version, community, buf, |buf| push_response(buf, version, req_id, values));
+}
+
+/// Wraps the PDU written by `f` in a community-based (SNMPv1 or SNMPv2c)
+/// message.
+pub fn build_community_message<F>(version: i64, community: &[u8], buf: &mut Buf, mut f: F)
+    where F: FnMut(&mut Buf)
+{
     buf.reset();
     buf.push_sequence(|buf| {
-        buf.push_constructed(snmp::MSG_GET_NEXT, |buf| {
-            buf.push_sequence(|buf| {
-                buf.push_sequence(|buf| {
-                    buf.push_null(); // value
-                    buf.push_object_identifier(name); // name
-                })
-            });
-            buf.push_integer(0); // error index
-            buf.push_integer(0); // error status
-            buf.push_integer(req_id as i64);
-        });
+        f(buf);
         buf.push_octet_string(community);
-        buf.push_integer(snmp::VERSION_2 as i64);
+        buf.push_integer(version);
     });
 }
 
-pub fn build_getbulk(community: &[u8], req_id: i32, names: &[&[u32]],
-                        non_repeaters: u32, max_repetitions: u32, buf: &mut Buf) {
+/// Wraps the PDU written by `f` in an SNMPv3 message, encrypting and signing
+/// it according to the user's security level.
+///
+/// # Panics if `security` has no engine.
+pub fn build_v3_message<F>(security: &mut v3::Security, msg_id: i32, buf: &mut Buf, mut f: F)
+    where F: FnMut(&mut Buf)
+{
+    let mut scoped_pdu = |buf: &mut Buf, security: &v3::Security| {
+        let engine = security.engine.as_ref().expect("SNMPv3 engine not known");
+        buf.push_sequence(|buf| {
+            f(buf);
+            buf.push_octet_string(&security.context_name);
+            buf.push_octet_string(&engine.id); // contextEngineID
+        });
+    };
+
+    // Read once: the time sent must be the one the encryption used.
+    let engine_time = security.engine.as_ref().expect("SNMPv3 engine not known").current_time();
+    let mut encrypted = None;
+    if security.privacy.is_some() {
+        buf.reset();
+        scoped_pdu(buf, security);
+        encrypted = Some(security.encrypt(engine_time, &buf[..]));
+    }
+
+    let security = &*security;
+    let engine = security.engine.as_ref().expect("SNMPv3 engine not known");
+    let mac_len = security.auth.as_ref().map_or(0, |auth| auth.protocol.mac_len());
+    let mut auth_params_end = 0;
     buf.reset();
     buf.push_sequence(|buf| {
-        buf.push_constructed(snmp::MSG_GET_BULK, |buf| {
+        match encrypted {
+            Some((ref ciphertext, _)) => buf.push_octet_string(ciphertext), // encryptedPDU
+            None => scoped_pdu(buf, security),
+        }
+        // msgSecurityParameters
+        buf.push_constructed(asn1::TYPE_OCTETSTRING, |buf| {
             buf.push_sequence(|buf| {
-                for name in names.iter().rev() {
-                    buf.push_sequence(|buf| {
-                        buf.push_null(); // value
-                        buf.push_object_identifier(name); // name
-                    });
+                match encrypted {
+                    Some((_, ref salt)) => buf.push_octet_string(salt), // msgPrivacyParameters
+                    None => buf.push_octet_string(&[]),
                 }
+                auth_params_end = buf.len;
+                buf.push_octet_string(&[0; 48][..mac_len]); // msgAuthenticationParameters
+                buf.push_octet_string(&security.username);
+                buf.push_integer(engine_time.into());
+                buf.push_integer(engine.boots.into());
+                buf.push_octet_string(&engine.id);
             });
-            buf.push_integer(max_repetitions as i64);
-            buf.push_integer(non_repeaters as i64);
-            buf.push_integer(req_id as i64);
         });
-        buf.push_octet_string(community);
-        buf.push_integer(snmp::VERSION_2 as i64);
+        // msgGlobalData
+        buf.push_sequence(|buf| {
+            buf.push_integer(v3::SECURITY_MODEL_USM);
+            buf.push_octet_string(&[security.flags()]);
+            buf.push_integer(BUFFER_SIZE as i64); // msgMaxSize
+            buf.push_integer(msg_id.into());
+        });
+        buf.push_integer(snmp::VERSION_3);
     });
+    if let Some(ref auth) = security.auth {
+        let mac = auth.protocol.mac(&auth.localized_key, &buf[..]);
+        let offset = buf.len - auth_params_end - mac_len;
+        buf.bytes_mut()[offset..offset + mac_len].copy_from_slice(&mac);
+    }
 }
 
-pub fn build_set(community: &[u8], req_id: i32, values: &[(&[u32], Value)], buf: &mut Buf) {
-    buf.reset();
-    buf.push_sequence(|buf| {
-        buf.push_constructed(snmp::MSG_SET, |buf| {
-            buf.push_sequence(|buf| {
-                for &(ref name, ref val) in values.iter().rev() {
-                    buf.push_sequence(|buf| {
-                        use Value::*;
-                        match *val {
-                            Boolean(b)                  => buf.push_boolean(b),
-                            Null                        => buf.push_null(),
-                            Integer(i)                  => buf.push_integer(i),
-                            OctetString(ostr)           => buf.push_octet_string(ostr),
-                            ObjectIdentifier(ref objid) => buf.push_object_identifier_raw(objid.raw()),
-                            IpAddress(ref ip)           => buf.push_ipaddress(ip),
-                            Counter32(i)                => buf.push_counter32(i),
-                            Unsigned32(i)               => buf.push_unsigned32(i),
-                            Timeticks(tt)               => buf.push_timeticks(tt),
-                            Opaque(bytes)               => buf.push_opaque(bytes),
-                            Counter64(i)                => buf.push_counter64(i),
-                            _ => unimplemented!(),
-                        }
-                        buf.push_object_identifier(name); // name
-                    });
-                }
-            });
-            buf.push_integer(0);
-            buf.push_integer(0);
-            buf.push_integer(req_id as i64);
+/// Builds the unauthenticated probe used to discover an SNMPv3 engine.
+pub fn build_discovery(msg_id: i32, buf: &mut Buf) {
+    let mut probe = v3::Security::new(b"");
+    probe.set_engine(v3::Engine::new(b"", 0, 0));
+    build_v3_message(&mut probe, msg_id, buf, |buf| push_discovery(buf, msg_id));
+}
+
+/// Writes a PDU of type `ident` whose variable bindings are written by `f`.
+/// A GetBulkRequest carries non-repeaters and max-repetitions in place of
+/// `error_status` and `error_index`.
+fn push_pdu<F>(buf: &mut Buf, ident: u8, req_id: i32, error_status: u32, error_index: u32, mut f: F)
+    where F: FnMut(&mut Buf)
+{
+    buf.push_constructed(ident, |buf| {
+        buf.push_sequence(&mut f);
+        buf.push_integer(error_index.into());
+        buf.push_integer(error_status.into());
+        buf.push_integer(req_id.into());
+    });
+}
+
+/// Variable bindings with NULL values, as requested by the Get PDUs.
+fn push_null_bindings(buf: &mut Buf, names: &[&[u32]]) {
+    for name in names.iter().rev() {
+        buf.push_sequence(|buf| {
+            buf.push_null(); // value
+            buf.push_object_identifier(name); // name
         });
-        buf.push_octet_string(community);
-        buf.push_integer(snmp::VERSION_2 as i64);
+    }
+}
+
+/// GetRequest with an empty variable-binding list, as sent for SNMPv3
+/// engine discovery (RFC 3414 4).
+pub fn push_discovery(buf: &mut Buf, req_id: i32) {
+    push_pdu(buf, snmp::MSG_GET, req_id, 0, 0, |_| {});
+}
+
+pub fn push_get(buf: &mut Buf, req_id: i32, name: &[u32]) {
+    push_pdu(buf, snmp::MSG_GET, req_id, 0, 0, |buf| push_null_bindings(buf, &[name]));
+}
+
+pub fn push_getnext(buf: &mut Buf, req_id: i32, name: &[u32]) {
+    push_pdu(buf, snmp::MSG_GET_NEXT, req_id, 0, 0, |buf| push_null_bindings(buf, &[name]));
+}
+
+pub fn push_getbulk(buf: &mut Buf, req_id: i32, names: &[&[u32]],
+                    non_repeaters: u32, max_repetitions: u32) {
+    push_pdu(buf, snmp::MSG_GET_BULK, req_id, non_repeaters, max_repetitions, |buf| {
+        push_null_bindings(buf, names)
     });
 }
 
-pub fn build_response(community: &[u8], req_id: i32, values: &[(&[u32], Value)], buf: &mut Buf) {
-    buf.reset();
-    buf.push_sequence(|buf| {
-        buf.push_constructed(snmp::MSG_RESPONSE, |buf| {
-            buf.push_sequence(|buf| {
-                for &(ref name, ref val) in values.iter().rev() {
+pub fn push_set(buf: &mut Buf, req_id: i32, values: &[(&[u32], Value)]) {
+    push_pdu(buf, snmp::MSG_SET, req_id, 0, 0, |buf| buf.push_bindings(values));
+}
+
+/// In SNMPv1 responses exception values are reported as noSuchName on the
+/// first affected variable binding (RFC 3584 4.2.1).
+pub fn push_response(buf: &mut Buf, version: i64, req_id: i32, values: &[(&[u32], Value)]) {
+    let exception = if version == snmp::VERSION_1 {
+        values.iter().position(|(_, val)| val.is_exception())
+    } else {
+        None
+    };
+    match exception {
+        Some(index) => {
+            push_pdu(buf, snmp::MSG_RESPONSE, req_id, snmp::ERRSTATUS_NOSUCHNAME, index as u32 + 1, |buf| {
+                for (name, val) in values.iter().rev() {
                     buf.push_sequence(|buf| {
-                        use Value::*;
-                        match *val {
-                            Boolean(b)                  => buf.push_boolean(b),
-                            Null                        => buf.push_null(),
-                            Integer(i)                  => buf.push_integer(i),
-                            OctetString(ostr)           => buf.push_octet_string(ostr),
-                            ObjectIdentifier(ref objid) => buf.push_object_identifier_raw(objid.raw()),
-                            IpAddress(ref ip)           => buf.push_ipaddress(ip),
-                            Counter32(i)                => buf.push_counter32(i),
-                            Unsigned32(i)               => buf.push_unsigned32(i),
-                            Timeticks(tt)               => buf.push_timeticks(tt),
-                            Opaque(bytes)               => buf.push_opaque(bytes),
-                            Counter64(i)                => buf.push_counter64(i),
-                            EndOfMibView                => buf.push_endofmibview(),
-                            NoSuchObject                => buf.push_nosuchobject(),
-                            NoSuchInstance              => buf.push_nosuchinstance(),
-                            _ => unimplemented!(),
+                        if val.is_exception() {
+                            buf.push_null();
+                        } else {
+                            buf.push_value(val);
                         }
                         buf.push_object_identifier(name); // name
                     });
                 }
             });
-            buf.push_integer(0);
-            buf.push_integer(0);
-            buf.push_integer(req_id as i64);
+        }
+        None => push_pdu(buf, snmp::MSG_RESPONSE, req_id, 0, 0, |buf| buf.push_bindings(values)),
+    }
+}
+
+/// A Response reporting `error_status` at `error_index` (1-based). SNMPv2
+/// statuses are mapped onto SNMPv1 ones for v1 requests (RFC 3584 4.4).
+pub fn push_error_response(buf: &mut Buf, version: i64, req_id: i32, error_status: u32, error_index: u32,
+                           values: &[(&[u32], Value)]) {
+    let error_status = if version == snmp::VERSION_1 {
+        snmp::v1_error_status(error_status)
+    } else {
+        error_status
+    };
+    push_pdu(buf, snmp::MSG_RESPONSE, req_id, error_status, error_index, |buf| buf.push_bindings(values));
+}
+
+/// SNMPv2-Trap-PDU, with sysUpTime.0 and snmpTrapOID.0 prepended to
+/// `values` (RFC 3416 4.2.6).
+pub fn push_trap(buf: &mut Buf, req_id: i32, uptime: u32, trap_oid: &[u32], values: &[(&[u32], Value)]) {
+    push_notification(buf, snmp::MSG_TRAP, req_id, uptime, trap_oid, values);
+}
+
+/// InformRequest-PDU, with sysUpTime.0 and snmpTrapOID.0 prepended to
+/// `values` (RFC 3416 4.2.7).
+pub fn push_inform(buf: &mut Buf, req_id: i32, uptime: u32, trap_oid: &[u32], values: &[(&[u32], Value)]) {
+    push_notification(buf, snmp::MSG_INFORM, req_id, uptime, trap_oid, values);
+}
+
+fn push_notification(buf: &mut Buf, ident: u8, req_id: i32, uptime: u32, trap_oid: &[u32],
+                     values: &[(&[u32], Value)]) {
+    push_pdu(buf, ident, req_id, 0, 0, |buf| {
+        buf.push_bindings(values);
+        buf.push_sequence(|buf| {
+            buf.push_object_identifier(trap_oid);
+            buf.push_object_identifier(trap::SNMP_TRAP_OID);
+        });
+        buf.push_sequence(|buf| {
+            buf.push_timeticks(uptime);
+            buf.push_object_identifier(trap::SYS_UP_TIME);
         });
-        buf.push_octet_string(community);
-        buf.push_integer(snmp::VERSION_2 as i64);
     });
 }
+
+/// SNMPv1 Trap-PDU (RFC 1157 4.1.6).
+pub fn push_trap_v1(buf: &mut Buf, enterprise: &[u32], agent_addr: &[u8; 4], generic_trap: i64,
+                    specific_trap: i64, timestamp: u32, values: &[(&[u32], Value)]) {
+    let trap = TrapV1 {
+        enterprise: ObjectIdentifier::from_name(enterprise),
+        agent_addr: *agent_addr,
+        generic_trap,
+        specific_trap,
+        timestamp,
+    };
+    push_trap_v1_pdu(buf, &trap, |buf| buf.push_bindings(values));
+}
+
+fn push_trap_v1_pdu<F>(buf: &mut Buf, trap: &TrapV1, mut f: F)
+    where F: FnMut(&mut Buf)
+{
+    buf.push_constructed(snmp::MSG_TRAP_V1, |buf| {
+        buf.push_sequence(&mut f);
+        buf.push_timeticks(trap.timestamp);
+        buf.push_integer(trap.specific_trap);
+        buf.push_integer(trap.generic_trap);
+        buf.push_ipaddress(&trap.agent_addr);
+        buf.push_objid(&trap.enterprise);
+    });
+}
+
+/// Encodes any PDU, with every field under the caller's control: non-zero
+/// error-status and error-index, arbitrary variable bindings and values of
+/// any type.
+///
+/// `from_pdu` copies a decoded PDU, whose well-formed encoding is then
+/// reproduced byte for byte, e.g. by a proxy forwarding it.
+///
+/// ```
+/// use snmplib::{PduBuilder, SnmpMessageType, SnmpPdu, Value};
+/// use snmplib::snmp::{ERRSTATUS_GENERR, VERSION_2};
+///
+/// let sys_descr = &[1, 3, 6, 1, 2, 1, 1, 1, 0];
+/// let message = PduBuilder::new(SnmpMessageType::Response, 42)
+///     .with_error(ERRSTATUS_GENERR, 1)
+///     .with_varbind(sys_descr, Value::Null)
+///     .encode_message(VERSION_2, b"public");
+///
+/// let pdu = SnmpPdu::from_bytes(&message).unwrap();
+/// assert_eq!((pdu.error_status, pdu.error_index), (ERRSTATUS_GENERR, 1));
+/// assert_eq!(PduBuilder::from_pdu(&pdu).encode_message(VERSION_2, b"public"), message);
+/// ```
+pub struct PduBuilder<'a> {
+    message_type: SnmpMessageType,
+    req_id: i32,
+    error_status: u32,
+    error_index: u32,
+    trap_v1: Option<TrapV1<'a>>,
+    varbinds: Vec<(ObjectIdentifier<'a>, Value<'a>)>,
+}
+
+impl<'a> PduBuilder<'a> {
+    /// A PDU without variable bindings and with zero error fields. An
+    /// SNMPv1 Trap-PDU also needs `with_trap_v1`.
+    pub fn new(message_type: SnmpMessageType, req_id: i32) -> PduBuilder<'a> {
+        PduBuilder {
+            message_type,
+            req_id,
+            error_status: 0,
+            error_index: 0,
+            trap_v1: None,
+            varbinds: Vec::new(),
+        }
+    }
+
+    /// A copy of a decoded PDU.
+    pub fn from_pdu(pdu: &SnmpPdu<'a>) -> PduBuilder<'a> {
+        PduBuilder {
+            message_type: pdu.message_type,
+            req_id: pdu.req_id,
+            error_status: pdu.error_status,
+            error_index: pdu.error_index,
+            trap_v1: pdu.trap_v1.as_ref().map(|trap| TrapV1 {
+                enterprise: trap.enterprise,
+                agent_addr: trap.agent_addr,
+                generic_trap: trap.generic_trap,
+                specific_trap: trap.specific_trap,
+                timestamp: trap.timestamp,
+            }),
+            varbinds: pdu.varbinds.clone().collect(),
+        }
+    }
+
+    /// Sets error-status and error-index (1-based).
+    pub fn with_error(mut self, error_status: u32, error_index: u32) -> PduBuilder<'a> {
+        self.error_status = error_status;
+        self.error_index = error_index;
+        self
+    }
+
+    /// Sets non-repeaters and max-repetitions, which a GetBulkRequest
+    /// carries in place of the error fields.
+    pub fn with_bulk(self, non_repeaters: u32, max_repetitions: u32) -> PduBuilder<'a> {
+        self.with_error(non_repeaters, max_repetitions)
+    }
+
+    /// Sets the header fields of an SNMPv1 Trap-PDU, which replace the
+    /// request-id and error fields.
+    pub fn with_trap_v1(mut self, trap: TrapV1<'a>) -> PduBuilder<'a> {
+        self.trap_v1 = Some(trap);
+        self
+    }
+
+    /// Appends a variable binding.
+    pub fn with_varbind(self, name: &'a [u32], value: Value<'a>) -> PduBuilder<'a> {
+        self.with_object_varbind(ObjectIdentifier::from_name(name), value)
+    }
+
+    /// Appends a variable binding named by a decoded OBJECT IDENTIFIER.
+    pub fn with_object_varbind(mut self, name: ObjectIdentifier<'a>, value: Value<'a>) -> PduBuilder<'a> {
+        self.varbinds.push((name, value));
+        self
+    }
+
+    /// The encoded PDU alone, e.g. for an SNMPv3 ScopedPDU.
+    ///
+    /// # Panics if the encoding exceeds the maximum message size, if a
+    /// variable binding name has fewer than two sub-IDs, or if an SNMPv1
+    /// Trap-PDU lacks `with_trap_v1`.
+    pub fn encode(&self) -> Vec<u8> {
+        let mut buf = Buf::default();
+        self.push(&mut buf);
+        buf.to_vec()
+    }
+
+    /// The PDU in a community-based message of `version`.
+    ///
+    /// # Panics like `encode`.
+    pub fn encode_message(&self, version: i64, community: &[u8]) -> Vec<u8> {
+        let mut buf = Buf::default();
+        build_community_message(version, community, &mut buf, |buf| self.push(buf));
+        buf.to_vec()
+    }
+
+    pub(crate) fn push(&self, buf: &mut Buf) {
+        let bindings = |buf: &mut Buf| {
+            for (name, val) in self.varbinds.iter().rev() {
+                buf.push_sequence(|buf| {
+                    buf.push_value(val);
+                    buf.push_objid(name);
+                });
+            }
+        };
+        match self.message_type {
+            SnmpMessageType::TrapV1 => {
+                let trap = self.trap_v1.as_ref().expect("SNMPv1 Trap-PDU without trap header");
+                push_trap_v1_pdu(buf, trap, bindings)
+            }
+            message_type => push_pdu(buf, message_type.ident(), self.req_id, self.error_status,
+                                     self.error_index, bindings),
+        }
+    }
+}
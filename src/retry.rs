@@ -0,0 +1,116 @@
+use std::cell::Cell;
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+
+/// How a session retransmits requests that go unanswered.
+///
+/// Attempt `n` (counting from 0) waits `timeout * backoff^n`, randomly
+/// shortened or lengthened by up to `jitter` (a fraction of the wait) so
+/// that many sessions polling the same lossy link don't retransmit in
+/// lockstep. A session gives up with `SnmpError::Timeout` after
+/// `retries + 1` attempts.
+///
+/// ```
+/// use std::time::Duration;
+/// use snmplib::RetryPolicy;
+///
+/// let policy = RetryPolicy::new(Duration::from_millis(500))
+///     .with_retries(3)
+///     .with_backoff(2.0)
+///     .with_jitter(0.1);
+/// assert_eq!(policy.attempts(), 4);
+/// ```
+#[derive(Debug, Clone, PartialEq)]
+pub struct RetryPolicy {
+    retries: u32,
+    timeout: Option<Duration>,
+    backoff: f64,
+    jitter: f64,
+}
+
+impl RetryPolicy {
+    /// A single attempt waiting `timeout` for the response.
+    pub fn new(timeout: Duration) -> RetryPolicy {
+        RetryPolicy { timeout: Some(timeout), ..RetryPolicy::default() }
+    }
+
+    pub fn with_retries(mut self, retries: u32) -> RetryPolicy {
+        self.retries = retries;
+        self
+    }
+
+    /// # Panics if `backoff` is less than 1.
+    pub fn with_backoff(mut self, backoff: f64) -> RetryPolicy {
+        assert!(backoff >= 1.0, "backoff multiplier must be at least 1");
+        self.backoff = backoff;
+        self
+    }
+
+    /// # Panics unless `jitter` is within `0.0..1.0`.
+    pub fn with_jitter(mut self, jitter: f64) -> RetryPolicy {
+        assert!((0.0..1.0).contains(&jitter), "jitter must be within 0.0..1.0");
+        self.jitter = jitter;
+        self
+    }
+
+    pub fn retries(&self) -> u32 {
+        self.retries
+    }
+
+    pub fn timeout(&self) -> Option<Duration> {
+        self.timeout
+    }
+
+    pub fn attempts(&self) -> u32 {
+        self.retries.saturating_add(1)
+    }
+
+    /// How long to wait for a response to attempt `attempt`; `None` waits
+    /// forever.
+    pub fn attempt_timeout(&self, attempt: u32) -> Option<Duration> {
+        let timeout = self.timeout?;
+        let mut secs = timeout.as_secs_f64() * self.backoff.powi(attempt.min(i32::MAX as u32) as i32);
+        if self.jitter > 0.0 {
+            secs *= 1.0 + self.jitter * (2.0 * random_unit() - 1.0);
+        }
+        Some(Duration::from_secs_f64(secs.min(u32::MAX as f64)))
+    }
+}
+
+/// No retries, blocking until a response arrives.
+impl Default for RetryPolicy {
+    fn default() -> RetryPolicy {
+        RetryPolicy {
+            retries: 0,
+            timeout: None,
+            backoff: 1.0,
+            jitter: 0.0,
+        }
+    }
+}
+
+impl From<Option<Duration>> for RetryPolicy {
+    fn from(timeout: Option<Duration>) -> RetryPolicy {
+        RetryPolicy { timeout, ..RetryPolicy::default() }
+    }
+}
+
+/// Uniform in `0.0..1.0`; jitter only needs to decorrelate hosts, not be
+/// unpredictable.
+fn random_unit() -> f64 {
+    thread_local! {
+        static STATE: Cell<u64> = const { Cell::new(0) };
+    }
+    STATE.with(|state| {
+        let mut x = state.get();
+        if x == 0 {
+            let nanos = SystemTime::now().duration_since(UNIX_EPOCH).map_or(0, |d| d.as_nanos() as u64);
+            x = (nanos ^ (state as *const _ as u64)) | 1;
+        }
+        // xorshift64
+        x ^= x << 13;
+        x ^= x >> 7;
+        x ^= x << 17;
+        state.set(x);
+        (x >> 11) as f64 / (1u64 << 53) as f64
+    })
+}
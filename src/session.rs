@@ -0,0 +1,151 @@
+use std::io;
+use std::num::Wrapping;
+use std::time::Duration;
+
+use super::pdu;
+use super::RetryPolicy;
+use super::{SnmpError, SnmpResult};
+use super::snmp::*;
+use super::v3;
+use super::value::Value;
+
+/// The part of a session that does no I/O: building requests and checking
+/// responses. `SyncSession` and `AsyncSession` wrap it with a socket and
+/// only differ in how they send and receive.
+pub struct Core {
+    version: i64,
+    community: Vec<u8>,
+    security: Option<v3::Security>,
+    pub retry: RetryPolicy,
+    req_id: Wrapping<i32>,
+    pub send_pdu: pdu::Buf,
+    plaintext: Vec<u8>,
+}
+
+/// The error for a destination that resolved to no addresses.
+pub fn no_addresses() -> io::Error {
+    io::Error::new(io::ErrorKind::InvalidInput, "destination resolved to no socket addresses")
+}
+
+impl Core {
+    pub fn new(version: i64, community: &[u8], security: Option<v3::Security>,
+               timeout: Option<Duration>, starting_req_id: i32) -> Self {
+        Core {
+            version,
+            community: community.to_vec(),
+            security,
+            retry: timeout.into(),
+            req_id: Wrapping(starting_req_id),
+            send_pdu: pdu::Buf::default(),
+            plaintext: Vec::new(),
+        }
+    }
+
+    /// The request-id the next request is sent with.
+    pub fn req_id(&self) -> i32 {
+        self.req_id.0
+    }
+
+    pub fn is_v3(&self) -> bool {
+        self.security.is_some()
+    }
+
+    pub fn engine(&self) -> Option<&v3::Engine> {
+        self.security.as_ref().and_then(|security| security.engine())
+    }
+
+    /// GetBulkRequest and InformRequest don't exist in SNMPv1.
+    pub fn check_v2_pdu(&self) -> SnmpResult<()> {
+        if self.version == VERSION_1 {
+            return Err(SnmpError::UnsupportedVersion);
+        }
+        Ok(())
+    }
+
+    /// Wraps the PDU written by `f` in a community-based message.
+    pub fn build_community<F>(&mut self, f: F)
+        where F: FnMut(&mut pdu::Buf)
+    {
+        pdu::build_community_message(self.version, &self.community, &mut self.send_pdu, f);
+    }
+
+    /// Decodes the reply to a community-based request sent as `req_id`.
+    pub fn finish_community<'a>(&mut self, req_id: i32, received: SnmpResult<&'a [u8]>) -> SnmpResult<SnmpPdu<'a>> {
+        self.req_id += Wrapping(1);
+        let resp = SnmpPdu::from_community_response(received?, self.version, &self.community)?;
+        resp.check_response(req_id)?;
+        Ok(resp)
+    }
+
+    /// Whether the authoritative engine must be discovered (RFC 3414 4)
+    /// before an SNMPv3 request can be sent.
+    pub fn needs_discovery(&self) -> bool {
+        self.security.as_ref().expect("SNMPv3 session").engine.is_none()
+    }
+
+    pub fn build_discovery(&mut self, msg_id: i32) {
+        pdu::build_discovery(msg_id, &mut self.send_pdu);
+    }
+
+    /// Learns the authoritative engine from the report to a discovery probe.
+    pub fn process_discovery(&mut self, msg_id: i32, bytes: &[u8]) -> SnmpResult<()> {
+        self.security.as_mut().expect("SNMPv3 session").process_discovery(msg_id, bytes)
+    }
+
+    /// Wraps the PDU written by `f` in an SNMPv3 message.
+    pub fn build_v3<F>(&mut self, msg_id: i32, f: F)
+        where F: FnMut(&mut pdu::Buf)
+    {
+        let security = self.security.as_mut().expect("SNMPv3 session");
+        pdu::build_v3_message(security, msg_id, &mut self.send_pdu, f);
+    }
+
+    /// Processes the reply to an SNMPv3 request, leaving its plaintext
+    /// ScopedPDU for `finish_v3`. Returns `Ok(false)` when the reply was an
+    /// unknown-engine or not-in-time-window report and the request should be
+    /// resent; that happens at most once per request.
+    pub fn process_v3(&mut self, msg_id: i32, bytes: &[u8], resynced: &mut bool) -> SnmpResult<bool> {
+        let security = self.security.as_mut().expect("SNMPv3 session");
+        match security.process_response(msg_id, bytes, &mut self.plaintext) {
+            Err(err @ SnmpError::NotInTimeWindow) | Err(err @ SnmpError::UnknownEngineId) => {
+                if *resynced {
+                    return Err(err);
+                }
+                *resynced = true;
+                Ok(false)
+            }
+            other => other.map(|()| true),
+        }
+    }
+
+    /// Decodes the reply to an SNMPv3 request sent as `req_id`, once its
+    /// exchange has finished.
+    pub fn finish_v3(&mut self, req_id: i32, exchanged: SnmpResult<()>) -> SnmpResult<SnmpPdu<'_>> {
+        self.req_id += Wrapping(1);
+        exchanged?;
+        let resp = SnmpPdu::from_scoped_pdu(&self.plaintext)?;
+        resp.check_response(req_id)?;
+        Ok(resp)
+    }
+
+    pub fn build_trap_v1(&mut self, enterprise: &[u32], agent_addr: [u8; 4], generic_trap: i64, specific_trap: i64,
+                         timestamp: u32, values: &[(&[u32], Value)]) -> SnmpResult<()> {
+        if self.version != VERSION_1 || self.security.is_some() {
+            return Err(SnmpError::UnsupportedVersion);
+        }
+        self.build_community(|buf| {
+            pdu::push_trap_v1(buf, enterprise, &agent_addr, generic_trap, specific_trap, timestamp, values)
+        });
+        Ok(())
+    }
+
+    pub fn build_trap_v2c(&mut self, uptime: u32, trap_oid: &[u32], values: &[(&[u32], Value)]) -> SnmpResult<()> {
+        if self.version != VERSION_2 || self.security.is_some() {
+            return Err(SnmpError::UnsupportedVersion);
+        }
+        let req_id = self.req_id.0;
+        self.build_community(|buf| pdu::push_trap(buf, req_id, uptime, trap_oid, values));
+        self.req_id += Wrapping(1);
+        Ok(())
+    }
+}
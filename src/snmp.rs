@@ -1,16 +1,28 @@
-#![allow(dead_code)] //, identity_op, eq_op)]
+#![allow(dead_code, clippy::identity_op)]
+
+//! SNMP message types and protocol constants.
 
 use super::asn1;
+use std::sync::Arc;
+
 use super::varbinds::*;
 use super::asnreader::AsnReader;
+use super::objectidentifier::ObjectIdentifier;
+use super::oid::Oid;
+use super::value::{OwnedValue, Value};
+use super::trap;
+use super::v3;
 use super::{ SnmpResult, SnmpError} ;
 
+pub const VERSION_1:    i64 = 0;
 pub const VERSION_2:    i64 = 1;
+pub const VERSION_3:    i64 = 3;
 
 pub const MSG_GET:      u8 = asn1::CLASS_CONTEXTSPECIFIC | asn1::CONSTRUCTED | 0;
 pub const MSG_GET_NEXT: u8 = asn1::CLASS_CONTEXTSPECIFIC | asn1::CONSTRUCTED | 1;
 pub const MSG_RESPONSE: u8 = asn1::CLASS_CONTEXTSPECIFIC | asn1::CONSTRUCTED | 2;
 pub const MSG_SET:      u8 = asn1::CLASS_CONTEXTSPECIFIC | asn1::CONSTRUCTED | 3;
+pub const MSG_TRAP_V1:  u8 = asn1::CLASS_CONTEXTSPECIFIC | asn1::CONSTRUCTED | 4;
 pub const MSG_GET_BULK: u8 = asn1::CLASS_CONTEXTSPECIFIC | asn1::CONSTRUCTED | 5;
 pub const MSG_INFORM:   u8 = asn1::CLASS_CONTEXTSPECIFIC | asn1::CONSTRUCTED | 6;
 pub const MSG_TRAP:     u8 = asn1::CLASS_CONTEXTSPECIFIC | asn1::CONSTRUCTED | 7;
@@ -24,9 +36,9 @@ pub const TYPE_TIMETICKS:  u8 = asn1::CLASS_APPLICATION | 3;
 pub const TYPE_OPAQUE:     u8 = asn1::CLASS_APPLICATION | 4;
 pub const TYPE_COUNTER64:  u8 = asn1::CLASS_APPLICATION | 6;
 
-pub const SNMP_NOSUCHOBJECT:   u8 = (asn1::CLASS_CONTEXTSPECIFIC | asn1::PRIMITIVE | 0x0); /* 80=128 */
-pub const SNMP_NOSUCHINSTANCE: u8 = (asn1::CLASS_CONTEXTSPECIFIC | asn1::PRIMITIVE | 0x1); /* 81=129 */
-pub const SNMP_ENDOFMIBVIEW:   u8 = (asn1::CLASS_CONTEXTSPECIFIC | asn1::PRIMITIVE | 0x2); /* 82=130 */
+pub const SNMP_NOSUCHOBJECT:   u8 = asn1::CLASS_CONTEXTSPECIFIC | asn1::PRIMITIVE | 0x0; /* 80=128 */
+pub const SNMP_NOSUCHINSTANCE: u8 = asn1::CLASS_CONTEXTSPECIFIC | asn1::PRIMITIVE | 0x1; /* 81=129 */
+pub const SNMP_ENDOFMIBVIEW:   u8 = asn1::CLASS_CONTEXTSPECIFIC | asn1::PRIMITIVE | 0x2; /* 82=130 */
 
 pub const ERRSTATUS_NOERROR:             u32 =  0;
 pub const ERRSTATUS_TOOBIG:              u32 =  1;
@@ -48,9 +60,39 @@ pub const ERRSTATUS_AUTHORIZATIONERROR:  u32 = 16;
 pub const ERRSTATUS_NOTWRITABLE:         u32 = 17;
 pub const ERRSTATUS_INCONSISTENTNAME:    u32 = 18;
 
+pub const GENERIC_TRAP_COLDSTART:             i64 = 0;
+pub const GENERIC_TRAP_WARMSTART:             i64 = 1;
+pub const GENERIC_TRAP_LINKDOWN:              i64 = 2;
+pub const GENERIC_TRAP_LINKUP:                i64 = 3;
+pub const GENERIC_TRAP_AUTHENTICATIONFAILURE: i64 = 4;
+pub const GENERIC_TRAP_EGPNEIGHBORLOSS:       i64 = 5;
+pub const GENERIC_TRAP_ENTERPRISESPECIFIC:    i64 = 6;
+
+/// Maps an SNMPv2 error-status onto the subset defined by SNMPv1
+/// (RFC 3584 4.4).
+pub fn v1_error_status(status: u32) -> u32 {
+    match status {
+        ERRSTATUS_WRONGVALUE
+        | ERRSTATUS_WRONGENCODING
+        | ERRSTATUS_WRONGTYPE
+        | ERRSTATUS_WRONGLENGTH
+        | ERRSTATUS_INCONSISTENTVALUE   => ERRSTATUS_BADVALUE,
+        ERRSTATUS_NOACCESS
+        | ERRSTATUS_NOTWRITABLE
+        | ERRSTATUS_NOCREATION
+        | ERRSTATUS_INCONSISTENTNAME
+        | ERRSTATUS_AUTHORIZATIONERROR  => ERRSTATUS_NOSUCHNAME,
+        ERRSTATUS_RESOURCEUNAVAILABLE
+        | ERRSTATUS_COMMITFAILED
+        | ERRSTATUS_UNDOFAILED          => ERRSTATUS_GENERR,
+        status                          => status,
+    }
+}
+
 
 
-#[derive(Debug, PartialEq)]
+#[derive(Debug, Clone, Copy, PartialEq)]
+#[non_exhaustive]
 pub enum SnmpMessageType {
     GetRequest,
     GetNextRequest,
@@ -59,6 +101,7 @@ pub enum SnmpMessageType {
     SetRequest,
     InformRequest,
     Trap,
+    TrapV1,
     Report,
 }
 
@@ -74,13 +117,56 @@ impl SnmpMessageType {
                 MSG_SET      => SetRequest,
                 MSG_INFORM   => InformRequest,
                 MSG_TRAP     => Trap,
+                MSG_TRAP_V1  => TrapV1,
                 MSG_REPORT   => Report,
                 _ => return Err(SnmpError::AsnWrongType),
             }
         )
     }
+
+    /// The BER identifier of this PDU type.
+    pub fn ident(self) -> u8 {
+        use SnmpMessageType::*;
+        match self {
+            GetRequest     => MSG_GET,
+            GetNextRequest => MSG_GET_NEXT,
+            GetBulkRequest => MSG_GET_BULK,
+            Response       => MSG_RESPONSE,
+            SetRequest     => MSG_SET,
+            InformRequest  => MSG_INFORM,
+            Trap           => MSG_TRAP,
+            TrapV1         => MSG_TRAP_V1,
+            Report         => MSG_REPORT,
+        }
+    }
+}
+
+/// Header fields of an SNMPv1 Trap-PDU.
+#[derive(Debug)]
+pub struct TrapV1<'a> {
+    pub enterprise: ObjectIdentifier<'a>,
+    pub agent_addr: [u8; 4],
+    pub generic_trap: i64,
+    pub specific_trap: i64,
+    pub timestamp: u32,
 }
 
+/// A decoded SNMP message.
+///
+/// ```
+/// use snmplib::{SnmpMessageType, SnmpPdu};
+///
+/// // A v2c Response from "public" for request 1 binding 1.3.6.1 to 42.
+/// let bytes = [0x30, 0x22, 0x02, 0x01, 0x01, 0x04, 0x06, b'p', b'u', b'b', b'l', b'i', b'c',
+///              0xa2, 0x15, 0x02, 0x01, 0x01, 0x02, 0x01, 0x00, 0x02, 0x01, 0x00,
+///              0x30, 0x0a, 0x30, 0x08, 0x06, 0x03, 0x2b, 0x06, 0x01, 0x02, 0x01, 0x2a];
+/// let pdu = SnmpPdu::from_bytes(&bytes).unwrap();
+/// assert_eq!(pdu.message_type, SnmpMessageType::Response);
+/// assert_eq!(pdu.req_id, 1);
+/// let (name, value) = pdu.varbinds.clone().next().unwrap();
+/// assert_eq!(name.to_string(), "1.3.6.1");
+/// assert_eq!(format!("{:?}", value), "INTEGER: 42");
+/// ```
 #[derive(Debug)]
 pub struct SnmpPdu<'a> {
     version: i64,
@@ -89,35 +175,87 @@ pub struct SnmpPdu<'a> {
     pub req_id: i32,
     pub error_status: u32,
     pub error_index: u32,
+    /// Set for SNMPv1 traps, which carry no request-id or error fields.
+    pub trap_v1: Option<TrapV1<'a>>,
     pub varbinds: Varbinds<'a>,
 }
 
+/// The request-id of a community-based message or the msgID of an SNMPv3
+/// message, used to match responses to outstanding requests.
+pub(crate) fn message_id(bytes: &[u8]) -> Option<i32> {
+    match v3::Message::from_bytes(bytes) {
+        Ok(msg) => Some(msg.msg_id),
+        Err(_) => SnmpPdu::from_bytes(bytes).ok().map(|pdu| pdu.req_id),
+    }
+}
+
 impl<'a> SnmpPdu<'a> {
     pub fn from_bytes(bytes: &'a [u8]) -> SnmpResult<SnmpPdu<'a>> {
         let seq = AsnReader::from_bytes(bytes).read_raw(asn1::TYPE_SEQUENCE)?;
         let mut rdr = AsnReader::from_bytes(seq);
         let version = rdr.read_asn_integer()?;
-        if version != VERSION_2 {
+        if version != VERSION_1 && version != VERSION_2 {
             return Err(SnmpError::UnsupportedVersion);
         }
         let community = rdr.read_asn_octetstring()?;
+        Self::from_reader(version, community, rdr)
+    }
+
+    /// Decodes a community-based message, checking that it matches the
+    /// version and community of the request.
+    pub fn from_community_response(bytes: &'a [u8], version: i64, community: &[u8]) -> SnmpResult<SnmpPdu<'a>> {
+        let resp = Self::from_bytes(bytes)?;
+        if resp.version != version {
+            return Err(SnmpError::UnsupportedVersion);
+        }
+        if resp.community != community {
+            return Err(SnmpError::CommunityMismatch);
+        }
+        Ok(resp)
+    }
+
+    /// Checks that this is the Response to request `req_id`.
+    pub fn check_response(&self, req_id: i32) -> SnmpResult<()> {
+        if self.message_type != SnmpMessageType::Response {
+            return Err(SnmpError::AsnWrongType);
+        }
+        if self.req_id != req_id {
+            return Err(SnmpError::RequestIdMismatch);
+        }
+        Ok(())
+    }
+
+    /// Decodes a plaintext SNMPv3 ScopedPDU.
+    pub fn from_scoped_pdu(bytes: &'a [u8]) -> SnmpResult<SnmpPdu<'a>> {
+        let seq = AsnReader::from_bytes(bytes).read_raw(asn1::TYPE_SEQUENCE)?;
+        let mut rdr = AsnReader::from_bytes(seq);
+        let _context_engine_id = rdr.read_asn_octetstring()?;
+        let _context_name = rdr.read_asn_octetstring()?;
+        Self::from_reader(VERSION_3, &[], rdr)
+    }
+
+    fn from_reader(version: i64, community: &'a [u8], mut rdr: AsnReader<'a>) -> SnmpResult<SnmpPdu<'a>> {
         let ident = rdr.peek_byte()?;
         let message_type = SnmpMessageType::from_ident(ident)?;
 
         let mut response_pdu = AsnReader::from_bytes(rdr.read_raw(ident)?);
 
+        if message_type == SnmpMessageType::TrapV1 {
+            return Self::trap_v1_from_reader(version, community, response_pdu);
+        }
+
         let req_id = response_pdu.read_asn_integer()?;
-        if req_id < i32::min_value() as i64 || req_id > i32::max_value() as i64 {
+        if req_id < i32::MIN as i64 || req_id > i32::MAX as i64 {
             return Err(SnmpError::ValueOutOfRange);
         }
 
         let error_status = response_pdu.read_asn_integer()?;
-        if error_status < 0 || error_status > i32::max_value() as i64 {
+        if error_status < 0 || error_status > i32::MAX as i64 {
             return Err(SnmpError::ValueOutOfRange);
         }
 
         let error_index = response_pdu.read_asn_integer()?;
-        if error_index < 0 || error_index > i32::max_value() as i64 {
+        if error_index < 0 || error_index > i32::MAX as i64 {
             return Err(SnmpError::ValueOutOfRange);
         }
 
@@ -126,14 +264,150 @@ impl<'a> SnmpPdu<'a> {
 
         Ok(
             SnmpPdu {
-                version: version,
-                community: community,
-                message_type: message_type,
+                version,
+                community,
+                message_type,
                 req_id: req_id as i32,
                 error_status: error_status as u32,
                 error_index: error_index as u32,
-                varbinds: varbinds,
+                trap_v1: None,
+                varbinds,
+            }
+        )
+    }
+
+    fn trap_v1_from_reader(version: i64, community: &'a [u8], mut rdr: AsnReader<'a>) -> SnmpResult<SnmpPdu<'a>> {
+        let enterprise = rdr.read_asn_objectidentifier()?;
+        let agent_addr = rdr.read_snmp_ipaddress()?;
+        let generic_trap = rdr.read_asn_integer()?;
+        let specific_trap = rdr.read_asn_integer()?;
+        let timestamp = rdr.read_snmp_timeticks()?;
+        let varbinds = Varbinds::from_bytes(rdr.read_raw(asn1::TYPE_SEQUENCE)?);
+
+        Ok(
+            SnmpPdu {
+                version,
+                community,
+                message_type: SnmpMessageType::TrapV1,
+                req_id: 0,
+                error_status: 0,
+                error_index: 0,
+                trap_v1: Some(TrapV1 {
+                    enterprise,
+                    agent_addr,
+                    generic_trap,
+                    specific_trap,
+                    timestamp,
+                }),
+                varbinds,
             }
         )
     }
-}
\ No newline at end of file
+
+    /// Message version: `VERSION_1`, `VERSION_2` or `VERSION_3`.
+    pub fn version(&self) -> i64 {
+        self.version
+    }
+
+    /// Copies the PDU out of the buffer it was decoded from.
+    pub fn into_owned(self) -> SnmpResult<OwnedPdu> {
+        let trap_v1 = match self.trap_v1 {
+            Some(trap) => Some(OwnedTrapV1 {
+                enterprise: Oid::from(trap.enterprise.to_vec()?),
+                agent_addr: trap.agent_addr,
+                generic_trap: trap.generic_trap,
+                specific_trap: trap.specific_trap,
+                timestamp: trap.timestamp,
+            }),
+            None => None,
+        };
+        let varbinds = self.varbinds
+            .map(|(name, value)| Ok((Oid::from(name.to_vec()?), value.into_owned()?)))
+            .collect::<SnmpResult<Vec<_>>>()?;
+        Ok(OwnedPdu {
+            version: self.version,
+            community: self.community.to_vec(),
+            message_type: self.message_type,
+            req_id: self.req_id,
+            error_status: self.error_status,
+            error_index: self.error_index,
+            trap_v1,
+            varbinds: varbinds.into(),
+        })
+    }
+}
+
+/// Owned counterpart of `TrapV1`.
+#[derive(Debug, Clone, PartialEq)]
+pub struct OwnedTrapV1 {
+    pub enterprise: Oid,
+    pub agent_addr: [u8; 4],
+    pub generic_trap: i64,
+    pub specific_trap: i64,
+    pub timestamp: u32,
+}
+
+/// Owned counterpart of `SnmpPdu`, which can outlive the session that
+/// received it and be sent between threads. Clones share the variable
+/// bindings.
+#[derive(Debug, Clone, PartialEq)]
+pub struct OwnedPdu {
+    version: i64,
+    pub community: Vec<u8>,
+    pub message_type: SnmpMessageType,
+    pub req_id: i32,
+    pub error_status: u32,
+    pub error_index: u32,
+    pub trap_v1: Option<OwnedTrapV1>,
+    pub varbinds: Arc<[(Oid, OwnedValue)]>,
+}
+
+impl OwnedPdu {
+    /// Message version: `VERSION_1`, `VERSION_2` or `VERSION_3`.
+    pub fn version(&self) -> i64 {
+        self.version
+    }
+
+    /// The variable bindings in the borrowed form taken by the request
+    /// methods, e.g. to `set` what was read from another agent.
+    pub fn as_bindings(&self) -> Vec<(&[u32], Value<'_>)> {
+        self.varbinds.iter().map(|(name, value)| (&name[..], value.as_value())).collect()
+    }
+
+    /// The SNMPv2c SNMPv2-Trap-PDU equivalent to this SNMPv1 trap (RFC 3584
+    /// 3.1), or `None` if this is not a translatable SNMPv1 trap. See
+    /// `trap::v1_to_v2`.
+    pub fn to_trap_v2(&self) -> Option<OwnedPdu> {
+        let varbinds = trap::v1_to_v2(self.trap_v1.as_ref()?, &self.varbinds)?;
+        Some(OwnedPdu {
+            version: VERSION_2,
+            community: self.community.clone(),
+            message_type: SnmpMessageType::Trap,
+            req_id: 0,
+            error_status: 0,
+            error_index: 0,
+            trap_v1: None,
+            varbinds: varbinds.into(),
+        })
+    }
+
+    /// The SNMPv1 Trap-PDU equivalent to this SNMPv2 trap or inform (RFC
+    /// 3584 3.2), or `None` if this is not a well-formed SNMPv2
+    /// notification. See `trap::v2_to_v1`.
+    pub fn to_trap_v1(&self) -> Option<OwnedPdu> {
+        if self.message_type != SnmpMessageType::Trap && self.message_type != SnmpMessageType::InformRequest {
+            return None;
+        }
+        let (trap, varbinds) = trap::v2_to_v1(&self.varbinds)?;
+        Some(OwnedPdu {
+            version: VERSION_1,
+            community: self.community.clone(),
+            message_type: SnmpMessageType::TrapV1,
+            req_id: 0,
+            error_status: 0,
+            error_index: 0,
+            trap_v1: Some(trap),
+            varbinds: varbinds.into(),
+        })
+    }
+}
@@ -1,20 +1,22 @@
 use std::net::{Ipv4Addr, Ipv6Addr, SocketAddr, ToSocketAddrs, UdpSocket};
-use std::num::Wrapping;
-use std::time::Duration;
+use std::time::{Duration, Instant};
 use std::io;
 
 use super::BUFFER_SIZE;
 use super::pdu;
+use super::RetryPolicy;
+use super::session::{self, Core};
 use super::{SnmpError, SnmpResult};
 use super::snmp::*;
+use super::v3;
 use super::value::Value;
+use super::walk::{BulkWalk, Walk};
+use super::table::{self, Table};
 
-/// Synchronous SNMPv2 client.
+/// Synchronous SNMPv1/SNMPv2c/SNMPv3 client.
 pub struct SyncSession {
     socket: UdpSocket,
-    community: Vec<u8>,
-    req_id: Wrapping<i32>,
-    send_pdu: pdu::Buf,
+    core: Core,
     recv_buf: [u8; BUFFER_SIZE],
 }
 
@@ -22,119 +24,187 @@ impl SyncSession {
     pub fn new<SA>(destination: SA, community: &[u8], timeout: Option<Duration>, starting_req_id: i32) -> io::Result<Self>
         where SA: ToSocketAddrs
     {
-        let socket = match destination.to_socket_addrs()?.next() {
-            Some(SocketAddr::V4(_)) => UdpSocket::bind((Ipv4Addr::new(0,0,0,0), 0))?,
-            Some(SocketAddr::V6(_)) => UdpSocket::bind((Ipv6Addr::new(0,0,0,0,0,0,0,0), 0))?,
-            None => panic!("empty list of socket addrs"),
+        Self::open(destination, VERSION_2, community, None, timeout, starting_req_id)
+    }
+
+    /// Opens an SNMPv1 session. `getbulk` is not available in SNMPv1.
+    pub fn new_v1<SA>(destination: SA, community: &[u8], timeout: Option<Duration>, starting_req_id: i32) -> io::Result<Self>
+        where SA: ToSocketAddrs
+    {
+        Self::open(destination, VERSION_1, community, None, timeout, starting_req_id)
+    }
+
+    /// Opens an SNMPv3 session using the User-based Security Model.
+    pub fn new_v3<SA>(destination: SA, security: v3::Security, timeout: Option<Duration>, starting_req_id: i32) -> io::Result<Self>
+        where SA: ToSocketAddrs
+    {
+        Self::open(destination, VERSION_3, &[], Some(security), timeout, starting_req_id)
+    }
+
+    fn open<SA>(destination: SA, version: i64, community: &[u8], security: Option<v3::Security>,
+                timeout: Option<Duration>, starting_req_id: i32) -> io::Result<Self>
+        where SA: ToSocketAddrs
+    {
+        let destination = destination.to_socket_addrs()?.next().ok_or_else(session::no_addresses)?;
+        let socket = match destination {
+            SocketAddr::V4(_) => UdpSocket::bind((Ipv4Addr::new(0,0,0,0), 0))?,
+            SocketAddr::V6(_) => UdpSocket::bind((Ipv6Addr::new(0,0,0,0,0,0,0,0), 0))?,
         };
-        socket.set_read_timeout(timeout)?;
         socket.connect(destination)?;
         Ok(SyncSession {
-            socket: socket,
-            community: community.to_vec(),
-            req_id: Wrapping(starting_req_id),
-            send_pdu: pdu::Buf::default(),
+            socket,
+            core: Core::new(version, community, security, timeout, starting_req_id),
             recv_buf: [0; 4096],
         })
     }
 
-    fn send_and_recv(socket: &UdpSocket, pdu: &pdu::Buf, out: &mut [u8]) -> SnmpResult<usize> {
-        if let Ok(_pdu_len) = socket.send(&pdu[..]) {
-            match socket.recv(out) {
-                Ok(len) => Ok(len),
-                Err(_) => Err(SnmpError::ReceiveError)
+    /// Sets how unanswered requests are retransmitted. By default a session
+    /// makes one attempt bounded by the timeout it was opened with.
+    pub fn set_retry_policy(&mut self, retry: RetryPolicy) {
+        self.core.retry = retry;
+    }
+
+    pub fn retry_policy(&self) -> &RetryPolicy {
+        &self.core.retry
+    }
+
+    /// Sends the message built in `core` and waits for the reply to `msg_id`,
+    /// retransmitting as the retry policy allows. Replies to other ids, such
+    /// as late answers to earlier requests, are discarded.
+    fn send_and_recv<'a>(socket: &UdpSocket, core: &Core, msg_id: i32, out: &'a mut [u8]) -> SnmpResult<&'a [u8]> {
+        let retry = &core.retry;
+        for attempt in 0..retry.attempts() {
+            if socket.send(&core.send_pdu[..]).is_err() {
+                return Err(SnmpError::SendError);
+            }
+            let deadline = retry.attempt_timeout(attempt).map(|timeout| Instant::now() + timeout);
+            loop {
+                let remaining = match deadline {
+                    Some(deadline) => match deadline.checked_duration_since(Instant::now()) {
+                        Some(remaining) if remaining > Duration::from_millis(0) => Some(remaining),
+                        _ => break,
+                    },
+                    None => None,
+                };
+                if socket.set_read_timeout(remaining).is_err() {
+                    return Err(SnmpError::ReceiveError);
+                }
+                match socket.recv(out) {
+                    Ok(len) if message_id(&out[..len]) == Some(msg_id) => return Ok(&out[..len]),
+                    Ok(_) => continue,
+                    Err(ref e) if e.kind() == io::ErrorKind::WouldBlock
+                               || e.kind() == io::ErrorKind::TimedOut => break,
+                    Err(_) => return Err(SnmpError::ReceiveError),
+                }
             }
-        } else {
-            Err(SnmpError::SendError)
         }
+        Err(SnmpError::Timeout)
     }
 
-    pub fn get(&mut self, name: &[u32]) -> SnmpResult<SnmpPdu> {
-        let req_id = self.req_id.0;
-        pdu::build_get(self.community.as_slice(), req_id, name, &mut self.send_pdu);
-        let recv_len = Self::send_and_recv(&self.socket, &self.send_pdu, &mut self.recv_buf[..])?;
-        self.req_id += Wrapping(1);
-        let pdu_bytes = &self.recv_buf[..recv_len];
-        let resp = SnmpPdu::from_bytes(pdu_bytes)?;
-        if resp.message_type != SnmpMessageType::Response {
-            return Err(SnmpError::AsnWrongType);
-        }
-        if resp.req_id != req_id {
-            return Err(SnmpError::RequestIdMismatch);
-        }
-        if resp.community != &self.community[..] {
-            return Err(SnmpError::CommunityMismatch);
-        }
-        Ok(resp)
-    }
-
-    pub fn getnext(&mut self, name: &[u32]) -> SnmpResult<SnmpPdu> {
-        let req_id = self.req_id.0;
-        pdu::build_getnext(self.community.as_slice(), req_id, name, &mut self.send_pdu);
-        let recv_len = Self::send_and_recv(&self.socket, &self.send_pdu, &mut self.recv_buf[..])?;
-        self.req_id += Wrapping(1);
-        let pdu_bytes = &self.recv_buf[..recv_len];
-        let resp = SnmpPdu::from_bytes(pdu_bytes)?;
-        if resp.message_type != SnmpMessageType::Response {
-            return Err(SnmpError::AsnWrongType);
-        }
-        if resp.req_id != req_id {
-            return Err(SnmpError::RequestIdMismatch);
-        }
-        if resp.community != &self.community[..] {
-            return Err(SnmpError::CommunityMismatch);
-        }
-        Ok(resp)
-    }
-
-    pub fn getbulk(&mut self, names: &[&[u32]], non_repeaters: u32, max_repetitions: u32) -> SnmpResult<SnmpPdu> {
-        let req_id = self.req_id.0;
-        pdu::build_getbulk(self.community.as_slice(), req_id, names, non_repeaters, max_repetitions, &mut self.send_pdu);
-        let recv_len = Self::send_and_recv(&self.socket, &self.send_pdu, &mut self.recv_buf[..])?;
-        self.req_id += Wrapping(1);
-        let pdu_bytes = &self.recv_buf[..recv_len];
-        let resp = SnmpPdu::from_bytes(pdu_bytes)?;
-        if resp.message_type != SnmpMessageType::Response {
-            return Err(SnmpError::AsnWrongType);
-        }
-        if resp.req_id != req_id {
-            return Err(SnmpError::RequestIdMismatch);
-        }
-        if resp.community != &self.community[..] {
-            return Err(SnmpError::CommunityMismatch);
-        }
-        Ok(resp)
-    }
-
-    /// # Panics if any of the values are not one of these supported types:
-    ///   - `Boolean`
-    ///   - `Null`
-    ///   - `Integer`
-    ///   - `OctetString`
-    ///   - `ObjectIdentifier`
-    ///   - `IpAddress`
-    ///   - `Counter32`
-    ///   - `Unsigned32`
-    ///   - `Timeticks`
-    ///   - `Opaque`
-    ///   - `Counter64`
-    pub fn set(&mut self, values: &[(&[u32], Value)]) -> SnmpResult<SnmpPdu> {
-        let req_id = self.req_id.0;
-        pdu::build_set(self.community.as_slice(), req_id, values, &mut self.send_pdu);
-        let recv_len = Self::send_and_recv(&self.socket, &self.send_pdu, &mut self.recv_buf[..])?;
-        self.req_id += Wrapping(1);
-        let pdu_bytes = &self.recv_buf[..recv_len];
-        let resp = SnmpPdu::from_bytes(pdu_bytes)?;
-        if resp.message_type != SnmpMessageType::Response {
-            return Err(SnmpError::AsnWrongType);
-        }
-        if resp.req_id != req_id {
-            return Err(SnmpError::RequestIdMismatch);
+    /// Wraps the PDU written by `f` for this session's security, sends it and
+    /// decodes the response.
+    fn request<F>(&mut self, req_id: i32, mut f: F) -> SnmpResult<SnmpPdu<'_>>
+        where F: FnMut(&mut pdu::Buf)
+    {
+        if self.core.is_v3() {
+            let exchanged = self.exchange_v3(req_id & i32::MAX, &mut f);
+            self.core.finish_v3(req_id, exchanged)
+        } else {
+            self.core.build_community(f);
+            let received = Self::send_and_recv(&self.socket, &self.core, req_id, &mut self.recv_buf[..]);
+            self.core.finish_community(req_id, received)
         }
-        if resp.community != &self.community[..] {
-            return Err(SnmpError::CommunityMismatch);
+    }
+
+    /// Sends an SNMPv3 request, discovering the engine first if needed, and
+    /// resending it once if the reply is an unknown-engine or
+    /// not-in-time-window report.
+    fn exchange_v3<F>(&mut self, msg_id: i32, mut f: F) -> SnmpResult<()>
+        where F: FnMut(&mut pdu::Buf)
+    {
+        let mut resynced = false;
+        loop {
+            if self.core.needs_discovery() {
+                self.core.build_discovery(msg_id);
+                let report = Self::send_and_recv(&self.socket, &self.core, msg_id, &mut self.recv_buf[..])?;
+                self.core.process_discovery(msg_id, report)?;
+            }
+            self.core.build_v3(msg_id, &mut f);
+            let resp = Self::send_and_recv(&self.socket, &self.core, msg_id, &mut self.recv_buf[..])?;
+            if self.core.process_v3(msg_id, resp, &mut resynced)? {
+                return Ok(());
+            }
         }
-        Ok(resp)
     }
-}
 
+    /// The authoritative engine of an SNMPv3 session, once known.
+    pub fn engine(&self) -> Option<&v3::Engine> {
+        self.core.engine()
+    }
+
+    pub fn get(&mut self, name: &[u32]) -> SnmpResult<SnmpPdu<'_>> {
+        let req_id = self.core.req_id();
+        self.request(req_id, |buf| pdu::push_get(buf, req_id, name))
+    }
+
+    pub fn getnext(&mut self, name: &[u32]) -> SnmpResult<SnmpPdu<'_>> {
+        let req_id = self.core.req_id();
+        self.request(req_id, |buf| pdu::push_getnext(buf, req_id, name))
+    }
+
+    pub fn getbulk(&mut self, names: &[&[u32]], non_repeaters: u32, max_repetitions: u32) -> SnmpResult<SnmpPdu<'_>> {
+        self.core.check_v2_pdu()?;
+        let req_id = self.core.req_id();
+        self.request(req_id, |buf| pdu::push_getbulk(buf, req_id, names, non_repeaters, max_repetitions))
+    }
+
+    pub fn set(&mut self, values: &[(&[u32], Value)]) -> SnmpResult<SnmpPdu<'_>> {
+        let req_id = self.core.req_id();
+        self.request(req_id, |buf| pdu::push_set(buf, req_id, values))
+    }
+
+    /// Sends an SNMPv1 Trap-PDU. Requires an SNMPv1 session; nothing is
+    /// received in return.
+    pub fn send_trap_v1(&mut self, enterprise: &[u32], agent_addr: [u8; 4], generic_trap: i64, specific_trap: i64,
+                        timestamp: u32, values: &[(&[u32], Value)]) -> SnmpResult<()> {
+        self.core.build_trap_v1(enterprise, agent_addr, generic_trap, specific_trap, timestamp, values)?;
+        self.socket.send(&self.core.send_pdu[..]).map_err(|_| SnmpError::SendError)?;
+        Ok(())
+    }
+
+    /// Sends an SNMPv2-Trap-PDU for the notification `trap_oid`, with
+    /// sysUpTime.0 set to `uptime` (in hundredths of a second). Requires an
+    /// SNMPv2c session; nothing is received in return.
+    pub fn send_trap_v2c(&mut self, uptime: u32, trap_oid: &[u32], values: &[(&[u32], Value)]) -> SnmpResult<()> {
+        self.core.build_trap_v2c(uptime, trap_oid, values)?;
+        self.socket.send(&self.core.send_pdu[..]).map_err(|_| SnmpError::SendError)?;
+        Ok(())
+    }
+
+    /// Sends an InformRequest like `send_trap_v2c` does a trap, retrying
+    /// until the receiver acknowledges it. Requires an SNMPv2c or SNMPv3
+    /// session.
+    pub fn send_inform(&mut self, uptime: u32, trap_oid: &[u32], values: &[(&[u32], Value)]) -> SnmpResult<SnmpPdu<'_>> {
+        self.core.check_v2_pdu()?;
+        let req_id = self.core.req_id();
+        self.request(req_id, |buf| pdu::push_inform(buf, req_id, uptime, trap_oid, values))
+    }
+
+    /// Walks the subtree under `root` one GetNextRequest at a time.
+    pub fn walk(&mut self, root: &[u32]) -> Walk<'_> {
+        Walk::new(self, root)
+    }
+
+    /// Walks the subtree under `root` with GetBulkRequests of up to
+    /// `max_repetitions` items. Not available in SNMPv1.
+    pub fn bulkwalk(&mut self, root: &[u32], max_repetitions: u32) -> BulkWalk<'_> {
+        BulkWalk::new(self, root, max_repetitions)
+    }
+
+    /// Retrieves the rows of a table given its column OIDs, e.g. ifDescr and
+    /// ifInOctets, with GetBulkRequests of up to `max_repetitions` rows.
+    /// Not available in SNMPv1.
+    pub fn table(&mut self, columns: &[&[u32]], max_repetitions: u32) -> SnmpResult<Table> {
+        table::fetch(self, columns, max_repetitions)
+    }
+}
@@ -0,0 +1,110 @@
+use std::collections::BTreeMap;
+
+use super::{SnmpError, SnmpResult};
+use super::snmp::*;
+use super::sync::SyncSession;
+use super::oid::Oid;
+use super::value::OwnedValue;
+
+/// A conceptual table assembled from its columns, like net-snmp's
+/// `snmptable`.
+///
+/// Rows are keyed by the index suffix that follows each column OID and
+/// ordered by it. Each row has one cell per requested column; cells the
+/// agent did not return (sparse rows) are `None`.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Table {
+    columns: Vec<Oid>,
+    rows: BTreeMap<Vec<u32>, Vec<Option<OwnedValue>>>,
+}
+
+impl Table {
+    fn new(columns: &[&[u32]]) -> Table {
+        Table {
+            columns: columns.iter().map(|&column| Oid::from(column)).collect(),
+            rows: BTreeMap::new(),
+        }
+    }
+
+    fn insert(&mut self, column: usize, index: &[u32], value: OwnedValue) {
+        let width = self.columns.len();
+        let row = self.rows.entry(index.to_vec()).or_insert_with(|| vec![None; width]);
+        row[column] = Some(value);
+    }
+
+    pub fn columns(&self) -> &[Oid] {
+        &self.columns
+    }
+
+    /// Rows in index order, as (index, cells).
+    pub fn rows(&self) -> impl Iterator<Item = (&[u32], &[Option<OwnedValue>])> {
+        self.rows.iter().map(|(index, cells)| (&index[..], &cells[..]))
+    }
+
+    pub fn row(&self, index: &[u32]) -> Option<&[Option<OwnedValue>]> {
+        self.rows.get(index).map(|cells| &cells[..])
+    }
+
+    pub fn cell(&self, index: &[u32], column: usize) -> Option<&OwnedValue> {
+        self.rows.get(index).and_then(|cells| cells.get(column)).and_then(|cell| cell.as_ref())
+    }
+
+    pub fn len(&self) -> usize {
+        self.rows.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.rows.is_empty()
+    }
+}
+
+/// Retrieves the table made of `columns` with GetBulkRequests covering all
+/// unfinished columns at once. A column is finished when the agent moves past
+/// its subtree or reports endOfMibView, so columns may end at different rows.
+///
+/// Agents may truncate a response to fewer varbinds than columns requested
+/// (RFC 3416 4.2.3); the columns left out are asked for again. A response
+/// that moves no column forward fails with `SnmpError::NoProgress`.
+pub(crate) fn fetch(session: &mut SyncSession, columns: &[&[u32]], max_repetitions: u32) -> SnmpResult<Table> {
+    let mut table = Table::new(columns);
+    let mut cursors: Vec<Oid> = table.columns.clone();
+    // Indices into `columns` of the columns still being retrieved.
+    let mut active: Vec<usize> = (0..columns.len()).collect();
+
+    while !active.is_empty() {
+        let names: Vec<&[u32]> = active.iter().map(|&column| &cursors[column][..]).collect();
+        let resp = session.getbulk(&names, 0, max_repetitions.max(1))?;
+        if resp.error_status != ERRSTATUS_NOERROR {
+            return Err(SnmpError::ErrorStatus { status: resp.error_status, index: resp.error_index });
+        }
+
+        // Varbinds come in repetitions of one per requested column.
+        let mut finished = vec![false; active.len()];
+        let mut progressed = false;
+        for (i, (name, value)) in resp.varbinds.enumerate() {
+            let slot = i % active.len();
+            if finished[slot] {
+                continue;
+            }
+            let column = active[slot];
+            let name = Oid::from(name.to_vec()?);
+            if value.is_exception() || !name.starts_with(&table.columns[column]) {
+                finished[slot] = true;
+                progressed = true;
+                continue;
+            }
+            if name <= cursors[column] {
+                return Err(SnmpError::NonIncreasingOid);
+            }
+            let index = &name[table.columns[column].len()..];
+            table.insert(column, index, value.into_owned()?);
+            cursors[column] = name;
+            progressed = true;
+        }
+        if !progressed {
+            return Err(SnmpError::NoProgress);
+        }
+        active = active.iter().zip(finished).filter(|&(_, done)| !done).map(|(&column, _)| column).collect();
+    }
+    Ok(table)
+}
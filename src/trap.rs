@@ -0,0 +1,330 @@
+//! Receiving notifications, like net-snmp's `snmptrapd`.
+//!
+//! A `TrapReceiver` listens on a UDP socket (usually port 162) for SNMPv1
+//! Trap-PDUs, SNMPv2-Trap-PDUs and InformRequests and delivers each as a
+//! `Notification`. Datagrams that are not well-formed community-based
+//! notifications are dropped.
+//!
+//! InformRequests are acknowledged with a Response echoing their request-id
+//! and variable bindings, as soon as they are received or, with
+//! `Acknowledge::AfterHandler`, once the handler passed to `run` succeeds.
+//!
+//! `v1_to_v2` and `v2_to_v1` translate between SNMPv1 Trap-PDUs and SNMPv2
+//! notifications as a proxy does (RFC 3584 3.1 and 3.2). Received SNMPv1
+//! traps are given the translated snmpTrapOID.0.
+//!
+//! ```no_run
+//! use snmplib::trap::TrapReceiver;
+//!
+//! let mut receiver = TrapReceiver::bind("0.0.0.0:162").unwrap();
+//! receiver.set_communities(&[b"public"]);
+//! for notification in receiver.iter() {
+//!     let notification = notification.unwrap();
+//!     println!("{} sent {} at {}", notification.source, notification.trap_oid, notification.uptime);
+//! }
+//! ```
+
+use std::convert::TryFrom;
+use std::io;
+use std::net::{SocketAddr, ToSocketAddrs, UdpSocket};
+use std::time::Duration;
+
+use super::BUFFER_SIZE;
+use super::oid::Oid;
+use super::pdu;
+use super::snmp::*;
+use super::value::{OwnedValue, Value};
+
+/// sysUpTime.0, the first variable binding of an SNMPv2 notification.
+pub const SYS_UP_TIME: &[u32] = crate::oid!("1.3.6.1.2.1.1.3.0");
+/// snmpTrapOID.0, the second variable binding of an SNMPv2 notification.
+pub const SNMP_TRAP_OID: &[u32] = crate::oid!("1.3.6.1.6.3.1.1.4.1.0");
+/// snmpTrapAddress.0, the agent-addr of a translated SNMPv1 trap.
+pub const SNMP_TRAP_ADDRESS: &[u32] = crate::oid!("1.3.6.1.6.3.18.1.3.0");
+/// snmpTrapEnterprise.0, the enterprise of a translated SNMPv1 trap.
+pub const SNMP_TRAP_ENTERPRISE: &[u32] = crate::oid!("1.3.6.1.6.3.1.1.4.3.0");
+/// snmpTraps; the generic traps coldStart(0) to egpNeighborLoss(5) are
+/// its children 1 to 6.
+pub const SNMP_TRAPS: &[u32] = crate::oid!("1.3.6.1.6.3.1.1.5");
+
+/// generic-trap value of enterprise-specific SNMPv1 traps.
+pub const ENTERPRISE_SPECIFIC: i64 = 6;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum NotificationKind {
+    /// SNMPv1 Trap-PDU.
+    TrapV1,
+    /// SNMPv2-Trap-PDU.
+    Trap,
+    /// InformRequest, which the sender expects to be acknowledged.
+    Inform,
+}
+
+/// A received notification.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Notification {
+    pub source: SocketAddr,
+    /// `VERSION_1` or `VERSION_2`.
+    pub version: i64,
+    pub community: Vec<u8>,
+    pub kind: NotificationKind,
+    /// Zero for SNMPv1 traps, which have no request-id.
+    pub req_id: i32,
+    /// sysUpTime.0, or the time-stamp of an SNMPv1 trap.
+    pub uptime: u32,
+    /// snmpTrapOID.0, or the translation of an SNMPv1 trap's enterprise,
+    /// generic-trap and specific-trap (see `v1_trap_oid`).
+    pub trap_oid: Oid,
+    /// The Trap-PDU header fields of an SNMPv1 trap.
+    pub trap_v1: Option<OwnedTrapV1>,
+    /// The variable bindings following sysUpTime.0 and snmpTrapOID.0, or
+    /// all of those of an SNMPv1 trap.
+    pub varbinds: Vec<(Oid, OwnedValue)>,
+}
+
+impl Notification {
+    /// Decodes a notification received from `source`, or `None` if the
+    /// datagram is not one.
+    pub fn from_bytes(bytes: &[u8], source: SocketAddr) -> Option<Notification> {
+        let pdu = SnmpPdu::from_bytes(bytes).ok()?.into_owned().ok()?;
+        let kind = match pdu.message_type {
+            SnmpMessageType::TrapV1 => NotificationKind::TrapV1,
+            SnmpMessageType::Trap => NotificationKind::Trap,
+            SnmpMessageType::InformRequest => NotificationKind::Inform,
+            _ => return None,
+        };
+        let mut varbinds = pdu.varbinds.to_vec();
+        let (uptime, trap_oid) = match pdu.trap_v1 {
+            Some(ref trap) => (trap.timestamp, v1_trap_oid(trap)?),
+            None => {
+                let header = v2_header(&varbinds)?;
+                varbinds.drain(..2);
+                header
+            }
+        };
+        Some(Notification {
+            source,
+            version: pdu.version(),
+            community: pdu.community,
+            kind,
+            req_id: pdu.req_id,
+            uptime,
+            trap_oid,
+            trap_v1: pdu.trap_v1,
+            varbinds,
+        })
+    }
+}
+
+/// sysUpTime.0 and snmpTrapOID.0, which must begin the variable bindings of
+/// an SNMPv2 notification (RFC 3416 4.2.6).
+fn v2_header(varbinds: &[(Oid, OwnedValue)]) -> Option<(u32, Oid)> {
+    match varbinds.get(..2) {
+        Some([(up_name, OwnedValue::Timeticks(uptime)), (oid_name, OwnedValue::ObjectIdentifier(trap_oid))])
+            if up_name[..] == *SYS_UP_TIME && oid_name[..] == *SNMP_TRAP_OID => Some((*uptime, trap_oid.clone())),
+        _ => None,
+    }
+}
+
+/// The snmpTrapOID.0 of an SNMPv1 trap: snmpTraps.(generic-trap + 1) for
+/// the generic traps, enterprise.0.specific-trap for enterprise-specific
+/// ones (RFC 3584 3.1). `None` if the trap numbers are out of range.
+pub fn v1_trap_oid(trap: &OwnedTrapV1) -> Option<Oid> {
+    match trap.generic_trap {
+        0..=5 => Some(Oid::from(SNMP_TRAPS).child(trap.generic_trap as u32 + 1)),
+        ENTERPRISE_SPECIFIC => {
+            let specific = u32::try_from(trap.specific_trap).ok()?;
+            Some(trap.enterprise.child(0).child(specific))
+        }
+        _ => None,
+    }
+}
+
+/// Translates an SNMPv1 trap into the variable bindings of the equivalent
+/// SNMPv2 notification (RFC 3584 3.1): sysUpTime.0 and snmpTrapOID.0, then
+/// `varbinds`, then snmpTrapAddress.0 and snmpTrapEnterprise.0.
+pub fn v1_to_v2(trap: &OwnedTrapV1, varbinds: &[(Oid, OwnedValue)]) -> Option<Vec<(Oid, OwnedValue)>> {
+    let mut v2 = Vec::with_capacity(varbinds.len() + 4);
+    v2.push((Oid::from(SYS_UP_TIME), OwnedValue::Timeticks(trap.timestamp)));
+    v2.push((Oid::from(SNMP_TRAP_OID), OwnedValue::ObjectIdentifier(v1_trap_oid(trap)?)));
+    v2.extend_from_slice(varbinds);
+    v2.push((Oid::from(SNMP_TRAP_ADDRESS), OwnedValue::IpAddress(trap.agent_addr)));
+    v2.push((Oid::from(SNMP_TRAP_ENTERPRISE), OwnedValue::ObjectIdentifier(trap.enterprise.clone())));
+    Some(v2)
+}
+
+/// Translates the variable bindings of an SNMPv2 notification into an
+/// SNMPv1 trap (RFC 3584 3.2), or `None` if they do not begin with
+/// sysUpTime.0 and snmpTrapOID.0.
+///
+/// The agent-addr is taken from snmpTrapAddress.0, or 0.0.0.0 without it,
+/// and a generic trap's enterprise from snmpTrapEnterprise.0, or snmpTraps
+/// without it. Those two bindings are dropped, as are Counter64 values,
+/// which SNMPv1 cannot carry.
+pub fn v2_to_v1(varbinds: &[(Oid, OwnedValue)]) -> Option<(OwnedTrapV1, Vec<(Oid, OwnedValue)>)> {
+    let (timestamp, trap_oid) = v2_header(varbinds)?;
+    let mut agent_addr = [0; 4];
+    let mut trap_enterprise = None;
+    let mut v1 = Vec::with_capacity(varbinds.len() - 2);
+    for (name, value) in &varbinds[2..] {
+        match value {
+            OwnedValue::IpAddress(addr) if name[..] == *SNMP_TRAP_ADDRESS => agent_addr = *addr,
+            OwnedValue::ObjectIdentifier(oid) if name[..] == *SNMP_TRAP_ENTERPRISE => {
+                trap_enterprise = Some(oid.clone())
+            }
+            OwnedValue::Counter64(_) => {}
+            _ => v1.push((name.clone(), value.clone())),
+        }
+    }
+
+    let generic = match trap_oid.strip_prefix(SNMP_TRAPS) {
+        Some(&[n]) if (1..=6).contains(&n) => Some(i64::from(n) - 1),
+        _ => None,
+    };
+    let (enterprise, generic_trap, specific_trap) = match generic {
+        Some(generic) => (trap_enterprise.unwrap_or_else(|| Oid::from(SNMP_TRAPS)), generic, 0),
+        None => {
+            let (&specific, prefix) = trap_oid.split_last()?;
+            // enterprise.0.specific, or enterprise.specific for
+            // notifications defined without the zero
+            let enterprise = match prefix.split_last() {
+                Some((0, enterprise)) => enterprise,
+                _ => prefix,
+            };
+            (Oid::from(enterprise), ENTERPRISE_SPECIFIC, i64::from(specific))
+        }
+    };
+    let trap = OwnedTrapV1 { enterprise, agent_addr, generic_trap, specific_trap, timestamp };
+    Some((trap, v1))
+}
+
+/// When a `TrapReceiver` acknowledges InformRequests.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Acknowledge {
+    /// As soon as they are received, before they are delivered.
+    OnReceipt,
+    /// Once the handler passed to `TrapReceiver::run` succeeds. Informs
+    /// delivered by `recv` or `iter` have to be acknowledged with
+    /// `TrapReceiver::acknowledge`.
+    AfterHandler,
+}
+
+/// Listens for notifications.
+pub struct TrapReceiver {
+    socket: UdpSocket,
+    /// Accepted communities; any if empty.
+    communities: Vec<Vec<u8>>,
+    acknowledge: Acknowledge,
+    recv_buf: [u8; BUFFER_SIZE],
+    send_buf: pdu::Buf,
+}
+
+impl TrapReceiver {
+    pub fn bind<A: ToSocketAddrs>(addr: A) -> io::Result<TrapReceiver> {
+        Ok(TrapReceiver {
+            socket: UdpSocket::bind(addr)?,
+            communities: Vec::new(),
+            acknowledge: Acknowledge::OnReceipt,
+            recv_buf: [0; BUFFER_SIZE],
+            send_buf: pdu::Buf::default(),
+        })
+    }
+
+    /// Drops notifications not carrying one of `communities`. By default
+    /// every community is accepted.
+    pub fn set_communities(&mut self, communities: &[&[u8]]) {
+        self.communities = communities.iter().map(|community| community.to_vec()).collect();
+    }
+
+    /// Defaults to `Acknowledge::OnReceipt`.
+    pub fn set_acknowledge(&mut self, acknowledge: Acknowledge) {
+        self.acknowledge = acknowledge;
+    }
+
+    pub fn local_addr(&self) -> io::Result<SocketAddr> {
+        self.socket.local_addr()
+    }
+
+    /// Makes `recv` fail with `WouldBlock` or `TimedOut` when no
+    /// notification arrives within `timeout`.
+    pub fn set_read_timeout(&self, timeout: Option<Duration>) -> io::Result<()> {
+        self.socket.set_read_timeout(timeout)
+    }
+
+    /// Blocks until a notification arrives. An acknowledgement that cannot
+    /// be sent is dropped, leaving the sender to retransmit the inform.
+    pub fn recv(&mut self) -> io::Result<Notification> {
+        let notification = self.recv_unacknowledged()?;
+        if self.acknowledge == Acknowledge::OnReceipt {
+            let _ = self.acknowledge(&notification);
+        }
+        Ok(notification)
+    }
+
+    fn recv_unacknowledged(&mut self) -> io::Result<Notification> {
+        loop {
+            let (len, source) = self.socket.recv_from(&mut self.recv_buf)?;
+            let notification = match Notification::from_bytes(&self.recv_buf[..len], source) {
+                Some(notification) => notification,
+                None => continue,
+            };
+            if self.communities.is_empty() || self.communities.contains(&notification.community) {
+                return Ok(notification);
+            }
+        }
+    }
+
+    /// Notifications as they arrive. Ends only if receiving fails.
+    pub fn iter(&mut self) -> Notifications<'_> {
+        Notifications { receiver: self, failed: false }
+    }
+
+    /// Calls `f` with each notification until receiving fails. `f` returns
+    /// whether it handled the notification; with `Acknowledge::AfterHandler`
+    /// an InformRequest that was not handled is left unacknowledged, so that
+    /// its sender retransmits it.
+    pub fn run<F: FnMut(&Notification) -> bool>(&mut self, mut f: F) -> io::Result<()> {
+        loop {
+            let notification = self.recv()?;
+            let handled = f(&notification);
+            if handled && self.acknowledge == Acknowledge::AfterHandler {
+                let _ = self.acknowledge(&notification);
+            }
+        }
+    }
+
+    /// Sends the Response to `notification` if it is an InformRequest.
+    pub fn acknowledge(&mut self, notification: &Notification) -> io::Result<()> {
+        if notification.kind != NotificationKind::Inform {
+            return Ok(());
+        }
+        let mut values = Vec::with_capacity(notification.varbinds.len() + 2);
+        values.push((SYS_UP_TIME, Value::Timeticks(notification.uptime)));
+        values.push((SNMP_TRAP_OID, Value::ObjectIdentifier(notification.trap_oid.as_object_identifier())));
+        values.extend(notification.varbinds.iter().map(|(name, value)| (&name[..], value.as_value())));
+        pdu::build_response(notification.version, &notification.community, notification.req_id, &values,
+                            &mut self.send_buf);
+        self.socket.send_to(&self.send_buf[..], notification.source)?;
+        Ok(())
+    }
+}
+
+/// Iterator over received notifications; see `TrapReceiver::iter`. Yields
+/// the error that ends it.
+pub struct Notifications<'a> {
+    receiver: &'a mut TrapReceiver,
+    failed: bool,
+}
+
+impl<'a> Iterator for Notifications<'a> {
+    type Item = io::Result<Notification>;
+
+    fn next(&mut self) -> Option<io::Result<Notification>> {
+        if self.failed {
+            return None;
+        }
+        let result = self.receiver.recv();
+        self.failed = result.is_err();
+        Some(result)
+    }
+}
@@ -0,0 +1,671 @@
+//! SNMPv3 User-based Security Model (RFC 3414, RFC 3826, RFC 7860).
+
+use std::convert::TryInto;
+use std::time::{Instant, SystemTime, UNIX_EPOCH};
+
+use cbc::cipher::{AsyncStreamCipher, BlockDecryptMut, BlockEncryptMut, KeyIvInit};
+use cbc::cipher::block_padding::NoPadding;
+use des::Des;
+use hmac::{Mac, SimpleHmac};
+use hmac::digest::Digest;
+use hmac::digest::core_api::BlockSizeUser;
+
+use super::asn1;
+use super::asnreader::AsnReader;
+use super::snmp::{SnmpMessageType, SnmpPdu};
+use super::{SnmpError, SnmpResult};
+
+/// Value of msgSecurityModel for the User-based Security Model.
+pub const SECURITY_MODEL_USM: i64 = 3;
+
+pub const FLAG_AUTH:       u8 = 0b001;
+pub const FLAG_PRIV:       u8 = 0b010;
+pub const FLAG_REPORTABLE: u8 = 0b100;
+
+/// Seconds an authenticated message may lag the engine's clock.
+const TIME_WINDOW: u32 = 150;
+
+pub const USM_STATS_UNSUPPORTED_SEC_LEVELS: &[u32] = &[1, 3, 6, 1, 6, 3, 15, 1, 1, 1, 0];
+pub const USM_STATS_NOT_IN_TIME_WINDOWS:    &[u32] = &[1, 3, 6, 1, 6, 3, 15, 1, 1, 2, 0];
+pub const USM_STATS_UNKNOWN_USER_NAMES:     &[u32] = &[1, 3, 6, 1, 6, 3, 15, 1, 1, 3, 0];
+pub const USM_STATS_UNKNOWN_ENGINE_IDS:     &[u32] = &[1, 3, 6, 1, 6, 3, 15, 1, 1, 4, 0];
+pub const USM_STATS_WRONG_DIGESTS:          &[u32] = &[1, 3, 6, 1, 6, 3, 15, 1, 1, 5, 0];
+pub const USM_STATS_DECRYPTION_ERRORS:      &[u32] = &[1, 3, 6, 1, 6, 3, 15, 1, 1, 6, 0];
+
+/// Number of octets hashed when expanding a passphrase into a key (RFC 3414 A.2).
+const PASSWORD_EXPANSION_LEN: usize = 1_048_576;
+
+/// Message authentication protocols.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum AuthProtocol {
+    /// usmHMACMD5AuthProtocol (RFC 3414).
+    Md5,
+    /// usmHMACSHAAuthProtocol (RFC 3414).
+    Sha1,
+    /// usmHMAC128SHA224AuthProtocol (RFC 7860).
+    Sha224,
+    /// usmHMAC192SHA256AuthProtocol (RFC 7860).
+    Sha256,
+    /// usmHMAC256SHA384AuthProtocol (RFC 7860).
+    Sha384,
+    /// usmHMAC384SHA512AuthProtocol (RFC 7860).
+    Sha512,
+}
+
+impl AuthProtocol {
+    /// Length of the truncated MAC carried in msgAuthenticationParameters.
+    pub fn mac_len(self) -> usize {
+        use AuthProtocol::*;
+        match self {
+            Md5 | Sha1 => 12,
+            Sha224     => 16,
+            Sha256     => 24,
+            Sha384     => 32,
+            Sha512     => 48,
+        }
+    }
+
+    /// Turns a passphrase into a non-localized key (Ku).
+    pub fn password_to_key(self, password: &[u8]) -> Vec<u8> {
+        use AuthProtocol::*;
+        match self {
+            Md5    => password_to_key::<md5::Md5>(password),
+            Sha1   => password_to_key::<sha1::Sha1>(password),
+            Sha224 => password_to_key::<sha2::Sha224>(password),
+            Sha256 => password_to_key::<sha2::Sha256>(password),
+            Sha384 => password_to_key::<sha2::Sha384>(password),
+            Sha512 => password_to_key::<sha2::Sha512>(password),
+        }
+    }
+
+    /// Localizes a key to an authoritative snmpEngineID (Kul).
+    pub fn localize_key(self, key: &[u8], engine_id: &[u8]) -> Vec<u8> {
+        use AuthProtocol::*;
+        match self {
+            Md5    => localize_key::<md5::Md5>(key, engine_id),
+            Sha1   => localize_key::<sha1::Sha1>(key, engine_id),
+            Sha224 => localize_key::<sha2::Sha224>(key, engine_id),
+            Sha256 => localize_key::<sha2::Sha256>(key, engine_id),
+            Sha384 => localize_key::<sha2::Sha384>(key, engine_id),
+            Sha512 => localize_key::<sha2::Sha512>(key, engine_id),
+        }
+    }
+
+    /// Computes the truncated HMAC of `msg` under a localized key.
+    pub fn mac(self, localized_key: &[u8], msg: &[u8]) -> Vec<u8> {
+        use AuthProtocol::*;
+        let mut mac = match self {
+            Md5    => hmac::<md5::Md5>(localized_key, msg),
+            Sha1   => hmac::<sha1::Sha1>(localized_key, msg),
+            Sha224 => hmac::<sha2::Sha224>(localized_key, msg),
+            Sha256 => hmac::<sha2::Sha256>(localized_key, msg),
+            Sha384 => hmac::<sha2::Sha384>(localized_key, msg),
+            Sha512 => hmac::<sha2::Sha512>(localized_key, msg),
+        };
+        mac.truncate(self.mac_len());
+        mac
+    }
+
+    fn digest(self, data: &[u8]) -> Vec<u8> {
+        use AuthProtocol::*;
+        match self {
+            Md5    => md5::Md5::digest(data).to_vec(),
+            Sha1   => sha1::Sha1::digest(data).to_vec(),
+            Sha224 => sha2::Sha224::digest(data).to_vec(),
+            Sha256 => sha2::Sha256::digest(data).to_vec(),
+            Sha384 => sha2::Sha384::digest(data).to_vec(),
+            Sha512 => sha2::Sha512::digest(data).to_vec(),
+        }
+    }
+}
+
+/// Privacy (encryption) protocols.
+///
+/// The AES-192/256 variants differ only in how a localized key that is
+/// shorter than the cipher key is extended: `Aes192`/`Aes256` follow
+/// draft-blumenthal-aes-usm-04 (net-snmp), the `Reeder` variants follow
+/// draft-reeder-snmpv3-usm-3desede-00 (Cisco).
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum PrivProtocol {
+    /// usmDESPrivProtocol (RFC 3414).
+    Des,
+    /// usmAesCfb128Protocol (RFC 3826).
+    Aes128,
+    Aes192,
+    Aes256,
+    Aes192Reeder,
+    Aes256Reeder,
+}
+
+impl PrivProtocol {
+    /// Octets of localized key material the protocol consumes.
+    pub fn key_len(self) -> usize {
+        use PrivProtocol::*;
+        match self {
+            Des | Aes128           => 16,
+            Aes192 | Aes192Reeder  => 24,
+            Aes256 | Aes256Reeder  => 32,
+        }
+    }
+
+    /// Derives the localized privacy key from a passphrase.
+    pub fn localize_key(self, auth: AuthProtocol, password: &[u8], engine_id: &[u8]) -> Vec<u8> {
+        self.localize_master_key(auth, &auth.password_to_key(password), engine_id)
+    }
+
+    fn localize_master_key(self, auth: AuthProtocol, master_key: &[u8], engine_id: &[u8]) -> Vec<u8> {
+        use PrivProtocol::*;
+        let mut key = auth.localize_key(master_key, engine_id);
+        while key.len() < self.key_len() {
+            let extension = match self {
+                Des | Aes128 | Aes192 | Aes256 => auth.digest(&key),
+                Aes192Reeder | Aes256Reeder => {
+                    let last = &key[key.len() - auth.digest(&[]).len()..];
+                    auth.localize_key(&auth.password_to_key(last), engine_id)
+                }
+            };
+            key.extend_from_slice(&extension);
+        }
+        key.truncate(self.key_len());
+        key
+    }
+
+    /// Encrypts a ScopedPDU; `salt` becomes msgPrivacyParameters.
+    pub fn encrypt(self, localized_key: &[u8], engine_boots: u32, engine_time: u32,
+                   salt: &[u8; 8], plaintext: &[u8]) -> Vec<u8> {
+        let iv = self.iv(localized_key, engine_boots, engine_time, salt);
+        let mut data = plaintext.to_vec();
+        match self {
+            PrivProtocol::Des => {
+                // Pad to the block size; RFC 3414 8.1.1.2 leaves the value open.
+                data.resize(plaintext.len().div_ceil(8) * 8, 0);
+                let len = data.len();
+                cbc::Encryptor::<Des>::new_from_slices(&localized_key[..8], &iv)
+                    .expect("DES key and IV are 8 octets")
+                    .encrypt_padded_mut::<NoPadding>(&mut data, len)
+                    .expect("plaintext is padded to the block size");
+            }
+            _ => self.aes_cfb(localized_key, &iv, &mut data, true),
+        }
+        data
+    }
+
+    pub fn decrypt(self, localized_key: &[u8], engine_boots: u32, engine_time: u32,
+                   priv_params: &[u8], ciphertext: &[u8]) -> SnmpResult<Vec<u8>> {
+        let salt: &[u8; 8] = priv_params.try_into().map_err(|_| SnmpError::DecryptionError)?;
+        let iv = self.iv(localized_key, engine_boots, engine_time, salt);
+        let mut data = ciphertext.to_vec();
+        match self {
+            PrivProtocol::Des => {
+                if !data.len().is_multiple_of(8) {
+                    return Err(SnmpError::DecryptionError);
+                }
+                cbc::Decryptor::<Des>::new_from_slices(&localized_key[..8], &iv)
+                    .expect("DES key and IV are 8 octets")
+                    .decrypt_padded_mut::<NoPadding>(&mut data)
+                    .map_err(|_| SnmpError::DecryptionError)?;
+            }
+            _ => self.aes_cfb(localized_key, &iv, &mut data, false),
+        }
+        Ok(data)
+    }
+
+    fn iv(self, localized_key: &[u8], engine_boots: u32, engine_time: u32, salt: &[u8; 8]) -> Vec<u8> {
+        match self {
+            PrivProtocol::Des => {
+                // RFC 3414 8.1.1.1: pre-IV XOR salt
+                localized_key[8..16].iter().zip(salt).map(|(a, b)| a ^ b).collect()
+            }
+            _ => {
+                // RFC 3826 3.1.2.1: boots || time || salt
+                let mut iv = Vec::with_capacity(16);
+                iv.extend_from_slice(&engine_boots.to_be_bytes());
+                iv.extend_from_slice(&engine_time.to_be_bytes());
+                iv.extend_from_slice(salt);
+                iv
+            }
+        }
+    }
+
+    fn aes_cfb(self, localized_key: &[u8], iv: &[u8], data: &mut [u8], encrypt: bool) {
+        use PrivProtocol::*;
+        let key = &localized_key[..self.key_len()];
+        macro_rules! cfb {
+            ($cipher:ty) => {
+                if encrypt {
+                    cfb_mode::Encryptor::<$cipher>::new_from_slices(key, iv)
+                        .expect("key and IV lengths match the cipher")
+                        .encrypt(data)
+                } else {
+                    cfb_mode::Decryptor::<$cipher>::new_from_slices(key, iv)
+                        .expect("key and IV lengths match the cipher")
+                        .decrypt(data)
+                }
+            }
+        }
+        match self {
+            Aes128                => cfb!(aes::Aes128),
+            Aes192 | Aes192Reeder => cfb!(aes::Aes192),
+            Aes256 | Aes256Reeder => cfb!(aes::Aes256),
+            Des => unreachable!(),
+        }
+    }
+}
+
+fn password_to_key<D: Digest>(password: &[u8]) -> Vec<u8> {
+    let mut hasher = D::new();
+    if !password.is_empty() {
+        let mut chunk = [0u8; 64];
+        let mut index = 0;
+        let mut count = 0;
+        while count < PASSWORD_EXPANSION_LEN {
+            for b in chunk.iter_mut() {
+                *b = password[index % password.len()];
+                index += 1;
+            }
+            hasher.update(&chunk[..]);
+            count += chunk.len();
+        }
+    }
+    hasher.finalize().to_vec()
+}
+
+fn localize_key<D: Digest>(key: &[u8], engine_id: &[u8]) -> Vec<u8> {
+    let mut hasher = D::new();
+    hasher.update(key);
+    hasher.update(engine_id);
+    hasher.update(key);
+    hasher.finalize().to_vec()
+}
+
+fn hmac<D: Digest + BlockSizeUser>(key: &[u8], msg: &[u8]) -> Vec<u8> {
+    let mut mac = <SimpleHmac<D> as Mac>::new_from_slice(key)
+        .expect("HMAC accepts keys of any length");
+    mac.update(msg);
+    mac.finalize().into_bytes().to_vec()
+}
+
+/// Authoritative engine state as seen by a non-authoritative engine.
+#[derive(Debug, Clone)]
+pub struct Engine {
+    pub id: Vec<u8>,
+    pub boots: u32,
+    pub time: u32,
+    synced_at: Instant,
+}
+
+impl Engine {
+    pub fn new(id: &[u8], boots: u32, time: u32) -> Engine {
+        Engine {
+            id: id.to_vec(),
+            boots,
+            time,
+            synced_at: Instant::now(),
+        }
+    }
+
+    /// Estimated snmpEngineTime of the authoritative engine right now.
+    pub fn current_time(&self) -> u32 {
+        let elapsed = self.synced_at.elapsed().as_secs();
+        (u64::from(self.time) + elapsed).min(i32::MAX as u64) as u32
+    }
+
+    /// Applies the boots/time of an authenticated message from the engine
+    /// (RFC 3414 3.2.7b), rejecting messages outside the time window.
+    pub fn update(&mut self, boots: u32, time: u32) -> SnmpResult<()> {
+        let current_time = self.current_time();
+        if boots > self.boots || (boots == self.boots && time > current_time) {
+            self.boots = boots;
+            self.time = time;
+            self.synced_at = Instant::now();
+            Ok(())
+        } else if boots < self.boots || time + TIME_WINDOW < current_time {
+            Err(SnmpError::NotInTimeWindow)
+        } else {
+            Ok(())
+        }
+    }
+}
+
+#[derive(Debug, Clone)]
+pub(crate) struct Auth {
+    pub(crate) protocol: AuthProtocol,
+    key: Vec<u8>,
+    pub(crate) localized_key: Vec<u8>,
+}
+
+#[derive(Debug, Clone)]
+pub(crate) struct Priv {
+    pub(crate) protocol: PrivProtocol,
+    key: Vec<u8>,
+    pub(crate) localized_key: Vec<u8>,
+    salt: u64,
+}
+
+/// Security parameters of a USM user.
+///
+/// Sessions discover the authoritative engine on first use unless one is
+/// given with `with_engine`.
+///
+/// ```no_run
+/// use snmplib::SyncSession;
+/// use snmplib::v3::{AuthProtocol, PrivProtocol, Security};
+///
+/// let security = Security::new(b"admin")
+///     .with_auth(AuthProtocol::Sha256, b"maplesyrup")
+///     .with_priv(PrivProtocol::Aes128, b"maplesyrup");
+/// let mut session = SyncSession::new_v3("192.0.2.1:161", security, None, 0).unwrap();
+/// let _ = session.get(&[1, 3, 6, 1, 2, 1, 1, 1, 0]);
+/// ```
+#[derive(Debug, Clone)]
+pub struct Security {
+    pub(crate) username: Vec<u8>,
+    pub(crate) auth: Option<Auth>,
+    pub(crate) privacy: Option<Priv>,
+    pub(crate) engine: Option<Engine>,
+    pub(crate) context_name: Vec<u8>,
+}
+
+impl Security {
+    /// A noAuthNoPriv user.
+    pub fn new(username: &[u8]) -> Security {
+        Security {
+            username: username.to_vec(),
+            auth: None,
+            privacy: None,
+            engine: None,
+            context_name: Vec::new(),
+        }
+    }
+
+    /// Authenticates messages with a key derived from `password`.
+    pub fn with_auth(mut self, protocol: AuthProtocol, password: &[u8]) -> Security {
+        let key = protocol.password_to_key(password);
+        self.auth = Some(Auth {
+            protocol,
+            localized_key: Vec::new(),
+            key,
+        });
+        self.localize();
+        self
+    }
+
+    /// Encrypts ScopedPDUs with a key derived from `password`.
+    ///
+    /// # Panics if the user has no authentication protocol.
+    pub fn with_priv(mut self, protocol: PrivProtocol, password: &[u8]) -> Security {
+        let auth = self.auth.as_ref().expect("privacy requires authentication");
+        let seed = SystemTime::now().duration_since(UNIX_EPOCH).map_or(0, |d| d.as_nanos() as u64);
+        self.privacy = Some(Priv {
+            protocol,
+            key: auth.protocol.password_to_key(password),
+            localized_key: Vec::new(),
+            salt: seed,
+        });
+        self.localize();
+        self
+    }
+
+    /// Sets the authoritative engine the user talks to.
+    pub fn with_engine(mut self, engine_id: &[u8], boots: u32, time: u32) -> Security {
+        self.set_engine(Engine::new(engine_id, boots, time));
+        self
+    }
+
+    pub fn with_context_name(mut self, context_name: &[u8]) -> Security {
+        self.context_name = context_name.to_vec();
+        self
+    }
+
+    pub fn username(&self) -> &[u8] {
+        &self.username
+    }
+
+    pub fn engine(&self) -> Option<&Engine> {
+        self.engine.as_ref()
+    }
+
+    pub(crate) fn set_engine(&mut self, engine: Engine) {
+        self.engine = Some(engine);
+        self.localize();
+    }
+
+    fn localize(&mut self) {
+        if let (Some(auth), Some(engine)) = (self.auth.as_mut(), self.engine.as_ref()) {
+            auth.localized_key = auth.protocol.localize_key(&auth.key, &engine.id);
+            if let Some(ref mut privacy) = self.privacy {
+                privacy.localized_key = privacy.protocol.localize_master_key(auth.protocol, &privacy.key, &engine.id);
+            }
+        }
+    }
+
+    /// Encrypts a ScopedPDU for the current engine, returning the ciphertext
+    /// and msgPrivacyParameters. `engine_time` must be the
+    /// msgAuthoritativeEngineTime the message is sent with, as the AES IV is
+    /// derived from it (RFC 3826 3.1.2.1).
+    pub(crate) fn encrypt(&mut self, engine_time: u32, scoped_pdu: &[u8]) -> (Vec<u8>, [u8; 8]) {
+        let engine = self.engine.as_ref().expect("SNMPv3 engine not known");
+        let privacy = self.privacy.as_mut().expect("user has no privacy protocol");
+        privacy.salt = privacy.salt.wrapping_add(1);
+        let salt = match privacy.protocol {
+            // RFC 3414 8.1.1.1: snmpEngineBoots || local 32-bit integer
+            PrivProtocol::Des => ((u64::from(engine.boots) << 32) | (privacy.salt & 0xffff_ffff)).to_be_bytes(),
+            _ => privacy.salt.to_be_bytes(),
+        };
+        let ciphertext = privacy.protocol.encrypt(&privacy.localized_key, engine.boots, engine_time,
+                                                  &salt, scoped_pdu);
+        (ciphertext, salt)
+    }
+
+    /// Learns the authoritative engine from the report answering a
+    /// discovery probe.
+    pub(crate) fn process_discovery(&mut self, msg_id: i32, bytes: &[u8]) -> SnmpResult<()> {
+        let msg = Message::from_bytes(bytes)?;
+        if msg.msg_id != msg_id {
+            return Err(SnmpError::RequestIdMismatch);
+        }
+        let report = SnmpPdu::from_scoped_pdu(msg.scoped_pdu)?;
+        if report.message_type != SnmpMessageType::Report || msg.engine_id.is_empty() {
+            return Err(SnmpError::UnknownEngineId);
+        }
+        self.set_engine(Engine::new(msg.engine_id, msg.engine_boots, msg.engine_time));
+        Ok(())
+    }
+
+    /// Authenticates and decrypts a reply into `plaintext`, acting on USM
+    /// reports.
+    ///
+    /// `NotInTimeWindow` and `UnknownEngineId` mean the engine has been
+    /// resynchronized and the request may be resent.
+    pub(crate) fn process_response(&mut self, msg_id: i32, bytes: &[u8], plaintext: &mut Vec<u8>) -> SnmpResult<()> {
+        let msg = Message::from_bytes(bytes)?;
+        if msg.msg_id != msg_id {
+            return Err(SnmpError::RequestIdMismatch);
+        }
+        if msg.is_authenticated() {
+            if msg.username != &self.username[..] {
+                return Err(SnmpError::AuthFailure);
+            }
+            let auth = self.auth.as_ref().ok_or(SnmpError::UnsupportedSecurityLevel)?;
+            msg.verify(auth.protocol, &auth.localized_key)?;
+        }
+        match self.privacy {
+            Some(ref privacy) if msg.is_encrypted() => {
+                *plaintext = msg.decrypt(privacy.protocol, &privacy.localized_key)?;
+            }
+            _ => {
+                plaintext.clear();
+                plaintext.extend_from_slice(msg.scoped_pdu);
+            }
+        }
+
+        let resp = SnmpPdu::from_scoped_pdu(plaintext)?;
+        if resp.message_type != SnmpMessageType::Report {
+            // Only reports may drop to a lower security level.
+            if self.auth.is_some() && !msg.is_authenticated() {
+                return Err(SnmpError::AuthFailure);
+            }
+            if self.privacy.is_some() && !msg.is_encrypted() {
+                return Err(SnmpError::UnsupportedSecurityLevel);
+            }
+            if msg.is_authenticated() {
+                if let Some(ref mut engine) = self.engine {
+                    engine.update(msg.engine_boots, msg.engine_time)?;
+                }
+            }
+            return Ok(());
+        }
+
+        let (name, _) = resp.varbinds.clone().next().ok_or(SnmpError::AsnWrongType)?;
+        if name == USM_STATS_UNKNOWN_ENGINE_IDS {
+            self.set_engine(Engine::new(msg.engine_id, msg.engine_boots, msg.engine_time));
+            Err(SnmpError::UnknownEngineId)
+        } else if name == USM_STATS_NOT_IN_TIME_WINDOWS {
+            if msg.is_authenticated() {
+                let engine_id = self.engine.as_ref().map(|engine| engine.id.clone()).unwrap_or_default();
+                self.set_engine(Engine::new(&engine_id, msg.engine_boots, msg.engine_time));
+            }
+            Err(SnmpError::NotInTimeWindow)
+        } else if name == USM_STATS_UNKNOWN_USER_NAMES {
+            Err(SnmpError::UnknownUserName)
+        } else if name == USM_STATS_UNSUPPORTED_SEC_LEVELS {
+            Err(SnmpError::UnsupportedSecurityLevel)
+        } else if name == USM_STATS_WRONG_DIGESTS {
+            Err(SnmpError::AuthFailure)
+        } else if name == USM_STATS_DECRYPTION_ERRORS {
+            Err(SnmpError::DecryptionError)
+        } else {
+            Err(SnmpError::AsnWrongType)
+        }
+    }
+
+    /// msgFlags for a request sent by this user.
+    pub(crate) fn flags(&self) -> u8 {
+        let mut flags = FLAG_REPORTABLE;
+        if self.auth.is_some() {
+            flags |= FLAG_AUTH;
+        }
+        if self.privacy.is_some() {
+            flags |= FLAG_PRIV;
+        }
+        flags
+    }
+}
+
+/// Decoded SNMPv3 message (RFC 3412) carrying USM security parameters.
+#[derive(Debug)]
+pub struct Message<'a> {
+    pub msg_id: i32,
+    pub max_size: i32,
+    pub flags: u8,
+    pub security_model: i64,
+    pub engine_id: &'a [u8],
+    pub engine_boots: u32,
+    pub engine_time: u32,
+    pub username: &'a [u8],
+    pub auth_params: &'a [u8],
+    pub priv_params: &'a [u8],
+    /// The encoded ScopedPDU, or the encryptedPDU OCTET STRING when
+    /// `is_encrypted()`.
+    pub scoped_pdu: &'a [u8],
+    bytes: &'a [u8],
+}
+
+impl<'a> Message<'a> {
+    pub fn from_bytes(bytes: &'a [u8]) -> SnmpResult<Message<'a>> {
+        let seq = AsnReader::from_bytes(bytes).read_raw(asn1::TYPE_SEQUENCE)?;
+        let mut rdr = AsnReader::from_bytes(seq);
+        if rdr.read_asn_integer()? != super::snmp::VERSION_3 {
+            return Err(SnmpError::UnsupportedVersion);
+        }
+
+        let mut global = AsnReader::from_bytes(rdr.read_raw(asn1::TYPE_SEQUENCE)?);
+        let msg_id = read_i32(&mut global)?;
+        let max_size = read_i32(&mut global)?;
+        let flags = match global.read_asn_octetstring()? {
+            [flags] => *flags,
+            _ => return Err(SnmpError::AsnInvalidLen),
+        };
+        let security_model = global.read_asn_integer()?;
+        if security_model != SECURITY_MODEL_USM {
+            return Err(SnmpError::UnsupportedSecurityModel);
+        }
+
+        let mut params = AsnReader::from_bytes(rdr.read_asn_octetstring()?);
+        let mut params = AsnReader::from_bytes(params.read_raw(asn1::TYPE_SEQUENCE)?);
+        let engine_id = params.read_asn_octetstring()?;
+        let engine_boots = read_i32(&mut params)? as u32;
+        let engine_time = read_i32(&mut params)? as u32;
+        let username = params.read_asn_octetstring()?;
+        let auth_params = params.read_asn_octetstring()?;
+        let priv_params = params.read_asn_octetstring()?;
+
+        let scoped_pdu = rdr.remaining();
+
+        Ok(Message {
+            msg_id,
+            max_size,
+            flags,
+            security_model,
+            engine_id,
+            engine_boots,
+            engine_time,
+            username,
+            auth_params,
+            priv_params,
+            scoped_pdu,
+            bytes,
+        })
+    }
+
+    pub fn is_authenticated(&self) -> bool {
+        self.flags & FLAG_AUTH != 0
+    }
+
+    pub fn is_encrypted(&self) -> bool {
+        self.flags & FLAG_PRIV != 0
+    }
+
+    pub fn is_reportable(&self) -> bool {
+        self.flags & FLAG_REPORTABLE != 0
+    }
+
+    /// Decrypts the encryptedPDU into a plaintext ScopedPDU followed by any
+    /// padding.
+    pub fn decrypt(&self, protocol: PrivProtocol, localized_key: &[u8]) -> SnmpResult<Vec<u8>> {
+        if !self.is_encrypted() {
+            return Err(SnmpError::DecryptionError);
+        }
+        let ciphertext = AsnReader::from_bytes(self.scoped_pdu).read_asn_octetstring()?;
+        protocol.decrypt(localized_key, self.engine_boots, self.engine_time, self.priv_params, ciphertext)
+    }
+
+    /// Checks msgAuthenticationParameters against the whole message.
+    pub fn verify(&self, protocol: AuthProtocol, localized_key: &[u8]) -> SnmpResult<()> {
+        if !self.is_authenticated() || self.auth_params.len() != protocol.mac_len() {
+            return Err(SnmpError::AuthFailure);
+        }
+        let offset = self.auth_params.as_ptr() as usize - self.bytes.as_ptr() as usize;
+        let mut whole = self.bytes.to_vec();
+        for b in &mut whole[offset..offset + self.auth_params.len()] {
+            *b = 0;
+        }
+        let expected = protocol.mac(localized_key, &whole);
+        let diff = expected.iter()
+            .zip(self.auth_params)
+            .fold(0u8, |acc, (a, b)| acc | (a ^ b));
+        if diff == 0 {
+            Ok(())
+        } else {
+            Err(SnmpError::AuthFailure)
+        }
+    }
+}
+
+fn read_i32(rdr: &mut AsnReader) -> SnmpResult<i32> {
+    let n = rdr.read_asn_integer()?;
+    if n < 0 || n > i64::from(i32::MAX) {
+        return Err(SnmpError::ValueOutOfRange);
+    }
+    Ok(n as i32)
+}
@@ -1,8 +1,27 @@
 use std::fmt;
+use std::net::Ipv4Addr;
 
 use super::asnreader::AsnReader;
 use super::objectidentifier::*;
+use super::oid::Oid;
+use super::{SnmpError, SnmpResult};
 
+/// A value decoded from, or to be encoded into, a variable binding.
+///
+/// Values borrow their data. Besides the variants, the `From` conversions
+/// build the common ones:
+///
+/// ```
+/// use snmplib::{ObjectIdentifier, Value};
+///
+/// let contact = Value::from("noc@example.com");
+/// let enabled = Value::from(1i64);
+/// let object_id = Value::ObjectIdentifier(ObjectIdentifier::from_name(&[1, 3, 6, 1, 4, 1, 8072]));
+/// assert_eq!(format!("{:?}", contact), "OCTET STRING: noc@example.com");
+/// assert_eq!(format!("{:?}", enabled), "INTEGER: 1");
+/// assert_eq!(format!("{:?}", object_id), "OBJECT IDENTIFIER: 1.3.6.1.4.1.8072");
+/// ```
+#[non_exhaustive]
 pub enum Value<'a> {
     Boolean(bool),
     Null,
@@ -34,6 +53,241 @@ pub enum Value<'a> {
     SnmpReport(AsnReader<'a>),
 }
 
+impl<'a> Value<'a> {
+    /// Whether this is one of the SNMPv2 exception values noSuchObject,
+    /// noSuchInstance or endOfMibView.
+    pub fn is_exception(&self) -> bool {
+        matches!(*self, Value::EndOfMibView | Value::NoSuchObject | Value::NoSuchInstance)
+    }
+
+    /// Copies a variable binding value out of the buffer it was decoded
+    /// from. Constructed values have no owned form.
+    pub fn into_owned(self) -> SnmpResult<OwnedValue> {
+        use Value::*;
+        Ok(match self {
+            Boolean(v)               => OwnedValue::Boolean(v),
+            Null                     => OwnedValue::Null,
+            Integer(n)               => OwnedValue::Integer(n),
+            OctetString(slice)       => OwnedValue::OctetString(slice.to_vec()),
+            ObjectIdentifier(obj_id) => OwnedValue::ObjectIdentifier(Oid::from(obj_id.to_vec()?)),
+            IpAddress(val)           => OwnedValue::IpAddress(val),
+            Counter32(val)           => OwnedValue::Counter32(val),
+            Unsigned32(val)          => OwnedValue::Unsigned32(val),
+            Timeticks(val)           => OwnedValue::Timeticks(val),
+            Opaque(val)              => OwnedValue::Opaque(val.to_vec()),
+            Counter64(val)           => OwnedValue::Counter64(val),
+            EndOfMibView             => OwnedValue::EndOfMibView,
+            NoSuchObject             => OwnedValue::NoSuchObject,
+            NoSuchInstance           => OwnedValue::NoSuchInstance,
+            _                        => return Err(SnmpError::AsnUnsupportedType),
+        })
+    }
+}
+
+impl<'a> From<bool> for Value<'a> {
+    fn from(v: bool) -> Value<'a> {
+        Value::Boolean(v)
+    }
+}
+
+impl<'a> From<i64> for Value<'a> {
+    fn from(n: i64) -> Value<'a> {
+        Value::Integer(n)
+    }
+}
+
+impl<'a> From<&'a [u8]> for Value<'a> {
+    fn from(bytes: &'a [u8]) -> Value<'a> {
+        Value::OctetString(bytes)
+    }
+}
+
+impl<'a> From<&'a str> for Value<'a> {
+    fn from(s: &'a str) -> Value<'a> {
+        Value::OctetString(s.as_bytes())
+    }
+}
+
+impl<'a> From<ObjectIdentifier<'a>> for Value<'a> {
+    fn from(obj_id: ObjectIdentifier<'a>) -> Value<'a> {
+        Value::ObjectIdentifier(obj_id)
+    }
+}
+
+/// A variable binding value that owns its data.
+#[derive(Clone, PartialEq)]
+#[non_exhaustive]
+pub enum OwnedValue {
+    Boolean(bool),
+    Null,
+    Integer(i64),
+    OctetString(Vec<u8>),
+    ObjectIdentifier(Oid),
+
+    IpAddress([u8;4]),
+    Counter32(u32),
+    Unsigned32(u32),
+    Timeticks(u32),
+    Opaque(Vec<u8>),
+    Counter64(u64),
+
+    EndOfMibView,
+    NoSuchObject,
+    NoSuchInstance,
+}
+
+impl OwnedValue {
+    pub fn is_exception(&self) -> bool {
+        matches!(*self, OwnedValue::EndOfMibView | OwnedValue::NoSuchObject | OwnedValue::NoSuchInstance)
+    }
+
+    /// Borrows this as a `Value`, e.g. to pass it to `SyncSession::set`.
+    pub fn as_value(&self) -> Value<'_> {
+        use OwnedValue::*;
+        match *self {
+            Boolean(v)                 => Value::Boolean(v),
+            Null                       => Value::Null,
+            Integer(n)                 => Value::Integer(n),
+            OctetString(ref val)       => Value::OctetString(val),
+            ObjectIdentifier(ref oid)  => Value::ObjectIdentifier(oid.as_object_identifier()),
+            IpAddress(val)             => Value::IpAddress(val),
+            Counter32(val)             => Value::Counter32(val),
+            Unsigned32(val)            => Value::Unsigned32(val),
+            Timeticks(val)             => Value::Timeticks(val),
+            Opaque(ref val)            => Value::Opaque(val),
+            Counter64(val)             => Value::Counter64(val),
+            EndOfMibView               => Value::EndOfMibView,
+            NoSuchObject               => Value::NoSuchObject,
+            NoSuchInstance             => Value::NoSuchInstance,
+        }
+    }
+}
+
+impl<'a> From<&'a OwnedValue> for Value<'a> {
+    fn from(value: &'a OwnedValue) -> Value<'a> {
+        value.as_value()
+    }
+}
+
+/// Conversion of a received value to a plain Rust type, as used by the
+/// row structs `Mib::generate_tables` emits.
+///
+/// ```
+/// use snmplib::{FromValue, OwnedValue};
+///
+/// assert_eq!(u32::from_value(&OwnedValue::Counter32(7)), Some(7));
+/// assert_eq!(String::from_value(&OwnedValue::OctetString(b"eth0".to_vec())), Some("eth0".to_string()));
+/// assert_eq!(i64::from_value(&OwnedValue::NoSuchInstance), None);
+/// ```
+pub trait FromValue: Sized {
+    /// `None` if the value is of another type.
+    fn from_value(value: &OwnedValue) -> Option<Self>;
+}
+
+impl FromValue for OwnedValue {
+    fn from_value(value: &OwnedValue) -> Option<OwnedValue> {
+        Some(value.clone())
+    }
+}
+
+impl FromValue for bool {
+    fn from_value(value: &OwnedValue) -> Option<bool> {
+        match *value {
+            OwnedValue::Boolean(v) => Some(v),
+            _ => None,
+        }
+    }
+}
+
+impl FromValue for i64 {
+    fn from_value(value: &OwnedValue) -> Option<i64> {
+        match *value {
+            OwnedValue::Integer(n) => Some(n),
+            _ => None,
+        }
+    }
+}
+
+impl FromValue for u32 {
+    fn from_value(value: &OwnedValue) -> Option<u32> {
+        match *value {
+            OwnedValue::Counter32(n) | OwnedValue::Unsigned32(n) | OwnedValue::Timeticks(n) => Some(n),
+            _ => None,
+        }
+    }
+}
+
+impl FromValue for u64 {
+    fn from_value(value: &OwnedValue) -> Option<u64> {
+        match *value {
+            OwnedValue::Counter64(n) => Some(n),
+            OwnedValue::Counter32(n) | OwnedValue::Unsigned32(n) | OwnedValue::Timeticks(n) => Some(n.into()),
+            _ => None,
+        }
+    }
+}
+
+/// OCTET STRINGs, with invalid UTF-8 replaced.
+impl FromValue for String {
+    fn from_value(value: &OwnedValue) -> Option<String> {
+        match *value {
+            OwnedValue::OctetString(ref bytes) => Some(String::from_utf8_lossy(bytes).into_owned()),
+            _ => None,
+        }
+    }
+}
+
+impl FromValue for Vec<u8> {
+    fn from_value(value: &OwnedValue) -> Option<Vec<u8>> {
+        match *value {
+            OwnedValue::OctetString(ref bytes) | OwnedValue::Opaque(ref bytes) => Some(bytes.clone()),
+            _ => None,
+        }
+    }
+}
+
+impl FromValue for Oid {
+    fn from_value(value: &OwnedValue) -> Option<Oid> {
+        match *value {
+            OwnedValue::ObjectIdentifier(ref oid) => Some(oid.clone()),
+            _ => None,
+        }
+    }
+}
+
+impl FromValue for Ipv4Addr {
+    fn from_value(value: &OwnedValue) -> Option<Ipv4Addr> {
+        match *value {
+            OwnedValue::IpAddress(addr) => Some(addr.into()),
+            _ => None,
+        }
+    }
+}
+
+impl fmt::Debug for OwnedValue {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        use OwnedValue::*;
+        match *self {
+            Boolean(v)                   => write!(f, "BOOLEAN: {}", v),
+            Integer(n)                   => write!(f, "INTEGER: {}", n),
+            OctetString(ref val)         => write!(f, "OCTET STRING: {}", String::from_utf8_lossy(val)),
+            ObjectIdentifier(ref oid)    => write!(f, "OBJECT IDENTIFIER: {}", oid),
+            Null                         => write!(f, "NULL"),
+
+            IpAddress(val)               => write!(f, "IP ADDRESS: {}.{}.{}.{}", val[0], val[1], val[2], val[3]),
+            Counter32(val)               => write!(f, "COUNTER32: {}", val),
+            Unsigned32(val)              => write!(f, "UNSIGNED32: {}", val),
+            Timeticks(val)               => write!(f, "TIMETICKS: {}", val),
+            Opaque(ref val)              => write!(f, "OPAQUE: {:?}", val),
+            Counter64(val)               => write!(f, "COUNTER64: {}", val),
+
+            EndOfMibView                 => write!(f, "END OF MIB VIEW"),
+            NoSuchObject                 => write!(f, "NO SUCH OBJECT"),
+            NoSuchInstance               => write!(f, "NO SUCH INSTANCE"),
+        }
+    }
+}
+
 impl<'a> fmt::Debug for Value<'a> {
     fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
         use Value::*;
@@ -0,0 +1,161 @@
+use std::collections::VecDeque;
+
+use super::{SnmpError, SnmpResult};
+use super::snmp::*;
+use super::sync::SyncSession;
+use super::oid::Oid;
+use super::value::OwnedValue;
+
+type Item = SnmpResult<(Oid, OwnedValue)>;
+
+/// Next variable binding after `cursor`, or `None` at the end of `root`'s
+/// subtree.
+type Step = SnmpResult<Option<(Oid, OwnedValue)>>;
+
+/// Checks a response to a walk request and returns its variable bindings.
+/// An SNMPv1 noSuchName is how the end of the MIB is reported.
+fn walk_response(resp: SnmpPdu<'_>) -> SnmpResult<Vec<(Oid, OwnedValue)>> {
+    match resp.error_status {
+        ERRSTATUS_NOERROR => (),
+        ERRSTATUS_NOSUCHNAME if resp.version() == VERSION_1 => return Ok(Vec::new()),
+        status => return Err(SnmpError::ErrorStatus { status, index: resp.error_index }),
+    }
+    resp.varbinds.map(|(name, value)| Ok((Oid::from(name.to_vec()?), value.into_owned()?))).collect()
+}
+
+/// Validates one variable binding returned while walking `root` from `cursor`.
+fn step(root: &[u32], cursor: &[u32], (name, value): (Oid, OwnedValue)) -> Step {
+    if value == OwnedValue::EndOfMibView || !name.starts_with(root) {
+        return Ok(None);
+    }
+    if name[..] <= *cursor {
+        return Err(SnmpError::NonIncreasingOid);
+    }
+    Ok(Some((name, value)))
+}
+
+/// Iterator over a subtree using GetNextRequests, one per item.
+///
+/// Created by `SyncSession::walk`. Stops at the end of the subtree; after
+/// an error, including an agent returning a non-increasing OID, it yields
+/// nothing more.
+pub struct Walk<'s> {
+    session: &'s mut SyncSession,
+    root: Oid,
+    cursor: Oid,
+    done: bool,
+}
+
+impl<'s> Walk<'s> {
+    pub(crate) fn new(session: &'s mut SyncSession, root: &[u32]) -> Walk<'s> {
+        Walk {
+            session,
+            root: Oid::from(root),
+            cursor: Oid::from(root),
+            done: false,
+        }
+    }
+
+    fn advance(&mut self) -> Step {
+        let next = walk_response(self.session.getnext(&self.cursor)?)?.into_iter().next();
+        match next {
+            Some(varbind) => step(&self.root, &self.cursor, varbind),
+            None => Ok(None),
+        }
+    }
+}
+
+impl<'s> Iterator for Walk<'s> {
+    type Item = Item;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        if self.done {
+            return None;
+        }
+        match self.advance() {
+            Ok(Some((name, value))) => {
+                self.cursor = name.clone();
+                Some(Ok((name, value)))
+            }
+            Ok(None) => {
+                self.done = true;
+                None
+            }
+            Err(err) => {
+                self.done = true;
+                Some(Err(err))
+            }
+        }
+    }
+}
+
+/// Iterator over a subtree using GetBulkRequests for up to `max_repetitions`
+/// items at a time.
+///
+/// Created by `SyncSession::bulkwalk`; stops like `Walk`.
+pub struct BulkWalk<'s> {
+    session: &'s mut SyncSession,
+    root: Oid,
+    cursor: Oid,
+    max_repetitions: u32,
+    buffered: VecDeque<Item>,
+    done: bool,
+}
+
+impl<'s> BulkWalk<'s> {
+    pub(crate) fn new(session: &'s mut SyncSession, root: &[u32], max_repetitions: u32) -> BulkWalk<'s> {
+        BulkWalk {
+            session,
+            root: Oid::from(root),
+            cursor: Oid::from(root),
+            max_repetitions: max_repetitions.max(1),
+            buffered: VecDeque::new(),
+            done: false,
+        }
+    }
+
+    /// Fetches the next batch into `buffered`, setting `done` once the end
+    /// of the subtree or an error is reached.
+    fn fill(&mut self) {
+        let resp = self.session.getbulk(&[&self.cursor], 0, self.max_repetitions);
+        let varbinds = match resp.and_then(walk_response) {
+            Ok(varbinds) => varbinds,
+            Err(err) => {
+                self.buffered.push_back(Err(err));
+                self.done = true;
+                return;
+            }
+        };
+        if varbinds.is_empty() {
+            self.done = true;
+        }
+        for varbind in varbinds {
+            match step(&self.root, &self.cursor, varbind) {
+                Ok(Some((name, value))) => {
+                    self.cursor = name.clone();
+                    self.buffered.push_back(Ok((name, value)));
+                }
+                Ok(None) => {
+                    self.done = true;
+                    break;
+                }
+                Err(err) => {
+                    self.buffered.push_back(Err(err));
+                    self.done = true;
+                    break;
+                }
+            }
+        }
+    }
+}
+
+impl<'s> Iterator for BulkWalk<'s> {
+    type Item = Item;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        if self.buffered.is_empty() && !self.done {
+            self.fill();
+        }
+        self.buffered.pop_front()
+    }
+}
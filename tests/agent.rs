@@ -0,0 +1,332 @@
+extern crate snmplib;
+
+mod common;
+
+use std::collections::BTreeMap;
+use std::net::{SocketAddr, UdpSocket};
+use std::ops::Bound;
+use std::thread;
+use std::time::Duration;
+
+use snmplib::{Oid, OwnedValue, SnmpError, SyncSession, Value};
+use snmplib::agent::{Agent, HandlerResult, MibHandler};
+use snmplib::snmp::*;
+
+use common::{community_message, null, pdu, varbind};
+
+const SYSTEM: &[u32] = &[1, 3, 6, 1, 2, 1, 1];
+const SYS_DESCR: &[u32] = &[1, 3, 6, 1, 2, 1, 1, 1, 0];
+const SYS_CONTACT: &[u32] = &[1, 3, 6, 1, 2, 1, 1, 4, 0];
+const SYS_NAME: &[u32] = &[1, 3, 6, 1, 2, 1, 1, 5, 0];
+const SYS_LOCATION: &[u32] = &[1, 3, 6, 1, 2, 1, 1, 6, 0];
+const ENTERPRISE: &[u32] = &[1, 3, 6, 1, 4, 1, 99999];
+
+/// Scalars held in a map; those listed as writable accept OCTET STRINGs.
+/// Committing "fail" fails, and so does undoing a commit of "sticky".
+struct Scalars {
+    objects: BTreeMap<Oid, OwnedValue>,
+    writable: Vec<Oid>,
+    /// Values replaced by commits, for undo.
+    saved: BTreeMap<Oid, Option<OwnedValue>>,
+}
+
+impl Scalars {
+    fn new(objects: &[(&[u32], OwnedValue)], writable: &[&[u32]]) -> Scalars {
+        Scalars {
+            objects: objects.iter().map(|(name, value)| (Oid::from(*name), value.clone())).collect(),
+            writable: writable.iter().map(|&name| Oid::from(name)).collect(),
+            saved: BTreeMap::new(),
+        }
+    }
+}
+
+impl MibHandler for Scalars {
+    fn get(&mut self, name: &[u32]) -> HandlerResult<OwnedValue> {
+        Ok(self.objects.get(&Oid::from(name)).cloned().unwrap_or(OwnedValue::NoSuchInstance))
+    }
+
+    fn get_next(&mut self, name: &[u32]) -> HandlerResult<Option<(Oid, OwnedValue)>> {
+        let next = self.objects.range((Bound::Excluded(Oid::from(name)), Bound::Unbounded)).next();
+        Ok(next.map(|(name, value)| (name.clone(), value.clone())))
+    }
+
+    fn test_set(&mut self, name: &[u32], value: &OwnedValue) -> HandlerResult<()> {
+        if !self.writable.contains(&Oid::from(name)) {
+            return Err(ERRSTATUS_NOTWRITABLE);
+        }
+        match *value {
+            OwnedValue::OctetString(_) => Ok(()),
+            _ => Err(ERRSTATUS_WRONGTYPE),
+        }
+    }
+
+    fn commit_set(&mut self, name: &[u32], value: &OwnedValue) -> HandlerResult<()> {
+        if *value == OwnedValue::OctetString(b"fail".to_vec()) {
+            return Err(ERRSTATUS_GENERR);
+        }
+        let name = Oid::from(name);
+        let old = self.objects.insert(name.clone(), value.clone());
+        self.saved.insert(name, old);
+        Ok(())
+    }
+
+    fn undo_set(&mut self, name: &[u32]) -> HandlerResult<()> {
+        let name = Oid::from(name);
+        if self.objects.get(&name) == Some(&OwnedValue::OctetString(b"sticky".to_vec())) {
+            return Err(ERRSTATUS_UNDOFAILED);
+        }
+        match self.saved.remove(&name) {
+            Some(Some(old)) => self.objects.insert(name, old),
+            Some(None) => self.objects.remove(&name),
+            None => return Err(ERRSTATUS_UNDOFAILED),
+        };
+        Ok(())
+    }
+}
+
+fn agent() -> SocketAddr {
+    let mut agent = Agent::bind("127.0.0.1:0", b"public").unwrap();
+    agent.set_write_community(b"private");
+    agent.register(ENTERPRISE, Scalars::new(&[(&[1, 3, 6, 1, 4, 1, 99999, 1, 0], OwnedValue::Counter32(7))], &[]));
+    agent.register(SYSTEM, Scalars::new(&[
+        (SYS_DESCR, OwnedValue::OctetString(b"stand-in appliance".to_vec())),
+        (SYS_CONTACT, OwnedValue::OctetString(b"noc".to_vec())),
+        (SYS_LOCATION, OwnedValue::OctetString(b"lab".to_vec())),
+    ], &[SYS_CONTACT, SYS_LOCATION]));
+    let addr = agent.local_addr().unwrap();
+    thread::spawn(move || agent.run());
+    addr
+}
+
+fn session(addr: SocketAddr, community: &[u8]) -> SyncSession {
+    SyncSession::new(addr, community, Some(Duration::from_secs(2)), 0).unwrap()
+}
+
+fn first(resp: &SnmpPdu) -> (String, String) {
+    let (name, value) = resp.varbinds.clone().next().unwrap();
+    (name.to_string(), format!("{:?}", value))
+}
+
+#[test]
+fn get() {
+    let mut sess = session(agent(), b"public");
+    let resp = sess.get(SYS_DESCR).unwrap();
+    assert_eq!(resp.error_status, ERRSTATUS_NOERROR);
+    assert_eq!(first(&resp), ("1.3.6.1.2.1.1.1.0".to_string(), "OCTET STRING: stand-in appliance".to_string()));
+    let resp = sess.get(SYS_NAME).unwrap();
+    assert_eq!(first(&resp).1, "NO SUCH INSTANCE");
+    let resp = sess.get(&[1, 3, 6, 1, 2, 1, 2, 1, 0]).unwrap();
+    assert_eq!(first(&resp).1, "NO SUCH OBJECT");
+}
+
+#[test]
+fn get_v1_reports_no_such_name() {
+    let mut sess = SyncSession::new_v1(agent(), b"public", Some(Duration::from_secs(2)), 0).unwrap();
+    let resp = sess.get(SYS_NAME).unwrap();
+    assert_eq!((resp.error_status, resp.error_index), (ERRSTATUS_NOSUCHNAME, 1));
+}
+
+#[test]
+fn walk_across_handlers() {
+    let mut sess = session(agent(), b"public");
+    let names: Vec<String> = sess.walk(&[1, 3, 6, 1])
+        .map(|item| item.unwrap().0.to_string())
+        .collect();
+    assert_eq!(names, ["1.3.6.1.2.1.1.1.0", "1.3.6.1.2.1.1.4.0", "1.3.6.1.2.1.1.6.0", "1.3.6.1.4.1.99999.1.0"]);
+
+    let resp = sess.getnext(&[1, 3, 6, 1, 4, 1, 99999, 1, 0]).unwrap();
+    assert_eq!(first(&resp).1, "END OF MIB VIEW");
+}
+
+#[test]
+fn set_requires_write_community() {
+    let addr = agent();
+    let contact = || Value::OctetString(b"ops@example.com");
+
+    let mut reader = session(addr, b"public");
+    let resp = reader.set(&[(SYS_CONTACT, contact())]).unwrap();
+    assert_eq!((resp.error_status, resp.error_index), (ERRSTATUS_NOACCESS, 1));
+
+    let mut writer = session(addr, b"private");
+    let resp = writer.set(&[(SYS_CONTACT, contact())]).unwrap();
+    assert_eq!(resp.error_status, ERRSTATUS_NOERROR);
+    assert_eq!(first(&resp).1, "OCTET STRING: ops@example.com");
+    let resp = reader.get(SYS_CONTACT).unwrap();
+    assert_eq!(first(&resp).1, "OCTET STRING: ops@example.com");
+}
+
+#[test]
+fn set_errors_carry_index() {
+    let addr = agent();
+    let mut sess = session(addr, b"private");
+    let resp = sess.set(&[(SYS_CONTACT, Value::OctetString(b"x")), (SYS_DESCR, Value::OctetString(b"y"))]).unwrap();
+    assert_eq!((resp.error_status, resp.error_index), (ERRSTATUS_NOTWRITABLE, 2));
+    let resp = sess.set(&[(SYS_CONTACT, Value::Integer(5))]).unwrap();
+    assert_eq!((resp.error_status, resp.error_index), (ERRSTATUS_WRONGTYPE, 1));
+
+    // SNMPv1 has neither notWritable nor wrongType.
+    let mut v1 = SyncSession::new_v1(addr, b"private", Some(Duration::from_secs(2)), 0).unwrap();
+    let resp = v1.set(&[(SYS_DESCR, Value::OctetString(b"y"))]).unwrap();
+    assert_eq!((resp.error_status, resp.error_index), (ERRSTATUS_NOSUCHNAME, 1));
+    let resp = v1.set(&[(SYS_CONTACT, Value::Integer(5))]).unwrap();
+    assert_eq!((resp.error_status, resp.error_index), (ERRSTATUS_BADVALUE, 1));
+}
+
+#[test]
+fn wrong_community_is_ignored() {
+    let mut sess = SyncSession::new(agent(), b"secret", Some(Duration::from_millis(200)), 0).unwrap();
+    assert_eq!(sess.get(SYS_DESCR).unwrap_err(), SnmpError::Timeout);
+}
+
+#[test]
+fn keeps_serving_after_manager_leaves() {
+    let mut agent = Agent::bind("127.0.0.1:0", b"public").unwrap();
+    agent.register(SYSTEM, Scalars::new(&[(SYS_DESCR, OwnedValue::OctetString(b"stand-in".to_vec()))], &[]));
+    let addr = agent.local_addr().unwrap();
+    let request = community_message(1, b"public", pdu(0xa0, 1, 0, 0, &[varbind(SYS_DESCR, null())]));
+    {
+        let gone = UdpSocket::bind("127.0.0.1:0").unwrap();
+        gone.send_to(&request, addr).unwrap();
+    }
+    agent.serve_one().unwrap();
+
+    let manager = UdpSocket::bind("127.0.0.1:0").unwrap();
+    manager.set_read_timeout(Some(Duration::from_secs(2))).unwrap();
+    manager.send_to(&request, addr).unwrap();
+    agent.serve_one().unwrap();
+    let mut buf = [0; 512];
+    assert!(manager.recv(&mut buf).unwrap() > 0);
+}
+
+#[test]
+#[should_panic(expected = "overlaps")]
+fn overlapping_subtrees() {
+    let mut agent = Agent::bind("127.0.0.1:0", b"public").unwrap();
+    agent.register(SYSTEM, Scalars::new(&[], &[]));
+    agent.register(SYS_DESCR, Scalars::new(&[], &[]));
+}
+
+#[test]
+fn getbulk() {
+    let mut sess = session(agent(), b"public");
+    let resp = sess.getbulk(&[&[1, 3, 6, 1, 2], SYSTEM, &[1, 3, 6, 1, 4, 1, 99999]], 1, 3).unwrap();
+    assert_eq!(resp.error_status, ERRSTATUS_NOERROR);
+    let varbinds: Vec<String> = resp.varbinds.clone()
+        .map(|(name, value)| format!("{} {:?}", name, value))
+        .collect();
+    assert_eq!(varbinds, [
+        "1.3.6.1.2.1.1.1.0 OCTET STRING: stand-in appliance",
+        "1.3.6.1.2.1.1.1.0 OCTET STRING: stand-in appliance",
+        "1.3.6.1.4.1.99999.1.0 COUNTER32: 7",
+        "1.3.6.1.2.1.1.4.0 OCTET STRING: noc",
+        "1.3.6.1.4.1.99999.1.0 END OF MIB VIEW",
+        "1.3.6.1.2.1.1.6.0 OCTET STRING: lab",
+        "1.3.6.1.4.1.99999.1.0 END OF MIB VIEW",
+    ]);
+
+    // Only non-repeaters
+    let resp = sess.getbulk(&[SYS_DESCR], 1, 10).unwrap();
+    assert_eq!(resp.varbinds.clone().count(), 1);
+}
+
+/// An agent with 100 50-byte strings under the enterprise subtree and a
+/// 600-byte one after them, limited to 484-byte messages.
+fn small_agent() -> SocketAddr {
+    let mut objects: Vec<(Vec<u32>, OwnedValue)> = (1..=100)
+        .map(|i| ([ENTERPRISE, &[1, i]].concat(), OwnedValue::OctetString(vec![b'x'; 50])))
+        .collect();
+    objects.push(([ENTERPRISE, &[2, 0]].concat(), OwnedValue::OctetString(vec![b'y'; 600])));
+    // Not encodable: an OID must have at least two sub-IDs.
+    objects.push(([ENTERPRISE, &[3, 0]].concat(), OwnedValue::ObjectIdentifier(Oid::from(&[1][..]))));
+    let objects: Vec<(&[u32], OwnedValue)> = objects.iter().map(|(name, value)| (&name[..], value.clone())).collect();
+
+    let mut agent = Agent::bind("127.0.0.1:0", b"public").unwrap();
+    agent.set_max_message_size(484);
+    agent.register(ENTERPRISE, Scalars::new(&objects, &[]));
+    let addr = agent.local_addr().unwrap();
+    thread::spawn(move || agent.run());
+    addr
+}
+
+#[test]
+fn getbulk_truncates_to_message_size() {
+    let addr = small_agent();
+    let socket = UdpSocket::bind("127.0.0.1:0").unwrap();
+    socket.set_read_timeout(Some(Duration::from_secs(2))).unwrap();
+    let request = pdu(0xa5, 9, 0, 50, &[varbind(ENTERPRISE, null())]);
+    socket.send_to(&community_message(1, b"public", request), addr).unwrap();
+    let mut buf = [0u8; 4096];
+    let len = socket.recv(&mut buf).unwrap();
+    assert!(len <= 484, "{} bytes", len);
+
+    let resp = SnmpPdu::from_bytes(&buf[..len]).unwrap();
+    assert_eq!((resp.req_id, resp.error_status), (9, ERRSTATUS_NOERROR));
+    let names: Vec<String> = resp.varbinds.clone().map(|(name, _)| name.to_string()).collect();
+    // Each binding takes 66 bytes, so 6 fit after the 40-odd byte header.
+    assert_eq!(names.len(), 6);
+    assert_eq!(names[0], "1.3.6.1.4.1.99999.1.1");
+    assert_eq!(names[5], "1.3.6.1.4.1.99999.1.6");
+}
+
+#[test]
+fn responses_too_big_or_unencodable() {
+    let addr = small_agent();
+    let mut sess = session(addr, b"public");
+    let resp = sess.get(&[1, 3, 6, 1, 4, 1, 99999, 2, 0]).unwrap();
+    assert_eq!((resp.error_status, resp.error_index), (ERRSTATUS_TOOBIG, 0));
+    assert_eq!(resp.varbinds.clone().count(), 0);
+
+    // SNMPv1 echoes the request's bindings with tooBig.
+    let mut v1 = SyncSession::new_v1(addr, b"public", Some(Duration::from_secs(2)), 0).unwrap();
+    let resp = v1.get(&[1, 3, 6, 1, 4, 1, 99999, 2, 0]).unwrap();
+    assert_eq!((resp.error_status, resp.error_index), (ERRSTATUS_TOOBIG, 0));
+    assert_eq!(first(&resp), ("1.3.6.1.4.1.99999.2.0".to_string(), "NULL".to_string()));
+
+    let resp = sess.get(&[1, 3, 6, 1, 4, 1, 99999, 3, 0]).unwrap();
+    assert_eq!((resp.error_status, resp.error_index), (ERRSTATUS_GENERR, 1));
+    let resp = sess.getbulk(&[&[1, 3, 6, 1, 4, 1, 99999, 2, 0]], 0, 5).unwrap();
+    assert_eq!((resp.error_status, resp.error_index), (ERRSTATUS_GENERR, 1));
+}
+
+fn get_string(sess: &mut SyncSession, name: &[u32]) -> String {
+    let resp = sess.get(name).unwrap();
+    first(&resp).1
+}
+
+#[test]
+fn set_is_all_or_nothing() {
+    let mut sess = session(agent(), b"private");
+    let octets = |s: &'static str| Value::OctetString(s.as_bytes());
+
+    // The second binding fails its test, so the first is never committed.
+    let resp = sess.set(&[(SYS_CONTACT, octets("a")), (SYS_LOCATION, Value::Integer(1))]).unwrap();
+    assert_eq!((resp.error_status, resp.error_index), (ERRSTATUS_WRONGTYPE, 2));
+    assert_eq!(get_string(&mut sess, SYS_CONTACT), "OCTET STRING: noc");
+
+    // The second binding fails to commit, so the first is undone.
+    let resp = sess.set(&[(SYS_CONTACT, octets("a")), (SYS_LOCATION, octets("fail"))]).unwrap();
+    assert_eq!((resp.error_status, resp.error_index), (ERRSTATUS_COMMITFAILED, 2));
+    assert_eq!(get_string(&mut sess, SYS_CONTACT), "OCTET STRING: noc");
+    assert_eq!(get_string(&mut sess, SYS_LOCATION), "OCTET STRING: lab");
+
+    let resp = sess.set(&[(SYS_CONTACT, octets("a")), (SYS_LOCATION, octets("b"))]).unwrap();
+    assert_eq!(resp.error_status, ERRSTATUS_NOERROR);
+    assert_eq!(get_string(&mut sess, SYS_CONTACT), "OCTET STRING: a");
+    assert_eq!(get_string(&mut sess, SYS_LOCATION), "OCTET STRING: b");
+}
+
+#[test]
+fn set_undo_failure() {
+    let addr = agent();
+    let mut sess = session(addr, b"private");
+    let resp = sess.set(&[(SYS_CONTACT, Value::OctetString(b"sticky")), (SYS_LOCATION, Value::OctetString(b"fail"))])
+        .unwrap();
+    assert_eq!((resp.error_status, resp.error_index), (ERRSTATUS_UNDOFAILED, 0));
+    assert_eq!(get_string(&mut sess, SYS_CONTACT), "OCTET STRING: sticky");
+
+    // SNMPv1 knows neither commitFailed nor undoFailed.
+    let mut v1 = SyncSession::new_v1(addr, b"private", Some(Duration::from_secs(2)), 0).unwrap();
+    let resp = v1.set(&[(SYS_CONTACT, Value::OctetString(b"x")), (SYS_LOCATION, Value::OctetString(b"fail"))])
+        .unwrap();
+    assert_eq!((resp.error_status, resp.error_index), (ERRSTATUS_GENERR, 2));
+}
@@ -0,0 +1,47 @@
+extern crate snmplib;
+
+mod common;
+
+use std::error::Error;
+use std::time::Duration;
+
+use snmplib::snmp::{ERRSTATUS_NOERROR, VERSION_2};
+use snmplib::{ObjectIdentifier, SnmpError, SnmpMessageType, SnmpPdu, SnmpResult, SyncSession, Value};
+
+use common::*;
+
+const SYS_CONTACT: &[u32] = &[1, 3, 6, 1, 2, 1, 1, 4, 0];
+const SYS_OBJECT_ID: &[u32] = &[1, 3, 6, 1, 2, 1, 1, 2, 0];
+
+/// Downstream code can name the response types.
+fn first_value(pdu: &SnmpPdu) -> SnmpResult<String> {
+    match pdu.varbinds.clone().next() {
+        Some((_, value)) => Ok(format!("{:?}", value)),
+        None => Err(SnmpError::AsnEof),
+    }
+}
+
+#[test]
+fn set_with_constructed_values() {
+    let addr = mib_agent(1, b"private", vec![]);
+    let mut sess = SyncSession::new(addr, b"private", Some(Duration::from_secs(2)), 0).unwrap();
+    let object_id = [1, 3, 6, 1, 4, 1, 8072];
+    let resp = sess.set(&[
+        (SYS_CONTACT, Value::from("noc@example.com")),
+        (SYS_OBJECT_ID, ObjectIdentifier::from_name(&object_id).into()),
+    ]).unwrap();
+    assert_eq!(resp.version(), VERSION_2);
+    assert_eq!(resp.message_type, SnmpMessageType::Response);
+    assert_eq!(resp.error_status, ERRSTATUS_NOERROR);
+    assert_eq!(first_value(&resp).unwrap(), "OCTET STRING: noc@example.com");
+
+    let resp = sess.get(SYS_OBJECT_ID).unwrap();
+    assert_eq!(first_value(&resp).unwrap(), "OBJECT IDENTIFIER: 1.3.6.1.4.1.8072");
+}
+
+#[test]
+fn errors_are_std_errors() {
+    let err: Box<dyn Error> = Box::new(SnmpError::ErrorStatus { status: 5, index: 1 });
+    assert_eq!(err.to_string(), "agent returned error-status 5 at index 1");
+    assert_eq!(SnmpError::Timeout.to_string(), "request timed out");
+}
@@ -0,0 +1,113 @@
+#![cfg(feature = "tokio")]
+
+extern crate snmplib;
+
+mod common;
+
+use std::net::SocketAddr;
+use std::time::Duration;
+
+use snmplib::{AsyncSession, RetryPolicy, SnmpError};
+
+use common::*;
+
+const SYS_DESCR: &[u32] = &[1, 3, 6, 1, 2, 1, 1, 1, 0];
+const SYS_NAME: &[u32] = &[1, 3, 6, 1, 2, 1, 1, 5, 0];
+
+fn agent() -> SocketAddr {
+    mib_agent(1, b"public", vec![
+        (SYS_DESCR.to_vec(), octets(b"async stand-in")),
+        (SYS_NAME.to_vec(), octets(b"router1")),
+    ])
+}
+
+async fn session(addr: SocketAddr) -> AsyncSession {
+    AsyncSession::new(addr, b"public", Some(Duration::from_secs(1)), 0).await.unwrap()
+}
+
+#[tokio::test]
+async fn async_get() {
+    let mut sess = session(agent()).await;
+    let resp = sess.get(SYS_DESCR).await.unwrap();
+    let (name, value) = resp.varbinds.clone().next().unwrap();
+    assert_eq!(name, SYS_DESCR);
+    assert_eq!(format!("{:?}", value), "OCTET STRING: async stand-in");
+}
+
+#[tokio::test]
+async fn async_getnext_and_getbulk() {
+    let mut sess = session(agent()).await;
+    {
+        let resp = sess.getnext(SYS_DESCR).await.unwrap();
+        let (name, _) = resp.varbinds.clone().next().unwrap();
+        assert_eq!(name, SYS_NAME);
+    }
+    let resp = sess.getbulk(&[&[1, 3, 6, 1, 2, 1, 1]], 0, 5).await.unwrap();
+    let names: Vec<String> = resp.varbinds.clone().map(|(name, _)| name.to_string()).collect();
+    assert_eq!(names, ["1.3.6.1.2.1.1.1.0", "1.3.6.1.2.1.1.5.0", "1.3.6.1.2.1.1.5.0",
+                       "1.3.6.1.2.1.1.5.0", "1.3.6.1.2.1.1.5.0"]);
+}
+
+#[tokio::test]
+async fn async_timeout() {
+    let mut sess = AsyncSession::new(agent(), b"private", Some(Duration::from_millis(100)), 0).await.unwrap();
+    assert_eq!(sess.get(SYS_DESCR).await.unwrap_err(), SnmpError::Timeout);
+}
+
+#[tokio::test]
+async fn many_concurrent_sessions() {
+    let addr = agent();
+    let polls = (0..20).map(|i| async move {
+        let mut sess = AsyncSession::new(addr, b"public", Some(Duration::from_secs(1)), i * 100).await.unwrap();
+        let resp = sess.get(SYS_NAME).await.unwrap();
+        let (_, value) = resp.varbinds.clone().next().unwrap();
+        format!("{:?}", value)
+    });
+    let mut handles = Vec::new();
+    for poll in polls {
+        handles.push(tokio::spawn(poll));
+    }
+    for handle in handles {
+        assert_eq!(handle.await.unwrap(), "OCTET STRING: router1");
+    }
+}
+
+#[tokio::test]
+async fn async_retries_then_times_out() {
+    let silent = std::net::UdpSocket::bind("127.0.0.1:0").unwrap();
+    let mut sess = AsyncSession::new(silent.local_addr().unwrap(), b"private", None, 0).await.unwrap();
+    sess.set_retry_policy(RetryPolicy::new(Duration::from_millis(50)).with_retries(2));
+    assert_eq!(sess.get(SYS_DESCR).await.unwrap_err(), SnmpError::Timeout);
+    let mut buf = [0u8; 512];
+    silent.set_nonblocking(true).unwrap();
+    let sent = std::iter::from_fn(|| silent.recv(&mut buf).ok()).count();
+    assert_eq!(sent, 3);
+}
+
+#[tokio::test]
+async fn async_send_trap_and_inform() {
+    use snmplib::trap::{NotificationKind, TrapReceiver};
+
+    let mut receiver = TrapReceiver::bind("127.0.0.1:0").unwrap();
+    receiver.set_read_timeout(Some(Duration::from_secs(2))).unwrap();
+    let addr = receiver.local_addr().unwrap();
+    let handler = std::thread::spawn(move || {
+        let trap = receiver.recv().unwrap();
+        let inform = receiver.recv().unwrap();
+        (trap.kind, inform.kind)
+    });
+
+    let link_down = &[1, 3, 6, 1, 6, 3, 1, 1, 5, 3];
+    let mut sess = session(addr).await;
+    sess.send_trap_v2c(100, link_down, &[]).await.unwrap();
+    let resp = sess.send_inform(200, link_down, &[]).await.unwrap();
+    assert_eq!(resp.varbinds.clone().count(), 2);
+    assert_eq!(handler.join().unwrap(), (NotificationKind::Trap, NotificationKind::Inform));
+}
+
+#[tokio::test]
+async fn async_open_without_addresses() {
+    let no_addrs: &[SocketAddr] = &[];
+    let err = AsyncSession::new(no_addrs, b"public", None, 0).await.err().unwrap();
+    assert_eq!(err.kind(), std::io::ErrorKind::InvalidInput);
+}
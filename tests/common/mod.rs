@@ -0,0 +1,226 @@
+#![allow(dead_code)]
+
+//! Helpers for stand-in agents: a tiny BER writer/reader and a loopback
+//! UDP responder.
+
+use std::net::{SocketAddr, UdpSocket};
+use std::thread;
+use std::time::Duration;
+
+pub fn tlv(tag: u8, content: &[u8]) -> Vec<u8> {
+    let mut out = vec![tag];
+    let len = content.len();
+    if len < 128 {
+        out.push(len as u8);
+    } else if len < 256 {
+        out.extend_from_slice(&[0x81, len as u8]);
+    } else {
+        out.extend_from_slice(&[0x82, (len >> 8) as u8, len as u8]);
+    }
+    out.extend_from_slice(content);
+    out
+}
+
+pub fn seq(parts: &[Vec<u8>]) -> Vec<u8> {
+    tlv(0x30, &parts.concat())
+}
+
+pub fn int(n: i64) -> Vec<u8> {
+    let bytes = n.to_be_bytes();
+    let mut start = 0;
+    while start < 7 && ((bytes[start] == 0 && bytes[start + 1] < 0x80)
+                        || (bytes[start] == 0xff && bytes[start + 1] >= 0x80)) {
+        start += 1;
+    }
+    tlv(0x02, &bytes[start..])
+}
+
+pub fn octets(bytes: &[u8]) -> Vec<u8> {
+    tlv(0x04, bytes)
+}
+
+pub fn null() -> Vec<u8> {
+    vec![0x05, 0x00]
+}
+
+pub fn oid(name: &[u32]) -> Vec<u8> {
+    let mut content = vec![(name[0] * 40 + name[1]) as u8];
+    for &subid in &name[2..] {
+        let mut chunk = vec![(subid & 0x7f) as u8];
+        let mut rest = subid >> 7;
+        while rest > 0 {
+            chunk.insert(0, (rest & 0x7f) as u8 | 0x80);
+            rest >>= 7;
+        }
+        content.extend(chunk);
+    }
+    tlv(0x06, &content)
+}
+
+/// A PDU with the given tag, request-id, error fields and varbinds.
+pub fn pdu(tag: u8, req_id: i32, error_status: i64, error_index: i64, varbinds: &[Vec<u8>]) -> Vec<u8> {
+    tlv(tag, &[int(req_id.into()), int(error_status), int(error_index), seq(varbinds)].concat())
+}
+
+pub fn varbind(name: &[u32], value: Vec<u8>) -> Vec<u8> {
+    seq(&[oid(name), value])
+}
+
+/// Splits one TLV off the front of `bytes`: (tag, content, rest).
+pub fn read_tlv(bytes: &[u8]) -> (u8, &[u8], &[u8]) {
+    let tag = bytes[0];
+    let (len, header) = match bytes[1] {
+        n if n < 0x80 => (n as usize, 2),
+        0x81 => (bytes[2] as usize, 3),
+        0x82 => (((bytes[2] as usize) << 8) | bytes[3] as usize, 4),
+        _ => panic!("unsupported length"),
+    };
+    (tag, &bytes[header..header + len], &bytes[header + len..])
+}
+
+pub fn read_int(bytes: &[u8]) -> (i64, &[u8]) {
+    let (tag, content, rest) = read_tlv(bytes);
+    assert_eq!(tag, 0x02);
+    let mut n: i64 = if content[0] & 0x80 != 0 { -1 } else { 0 };
+    for &b in content {
+        n = (n << 8) | b as i64;
+    }
+    (n, rest)
+}
+
+/// Tag and request-id of an encoded PDU.
+pub fn read_pdu_header(pdu: &[u8]) -> (u8, i32) {
+    let (tag, content, _) = read_tlv(pdu);
+    let (req_id, _) = read_int(content);
+    (tag, req_id as i32)
+}
+
+/// Binds a loopback socket and answers each datagram with `handler`'s
+/// output until the socket has been idle for two seconds.
+pub fn spawn_agent<F>(mut handler: F) -> SocketAddr
+    where F: FnMut(&[u8]) -> Option<Vec<u8>> + Send + 'static
+{
+    let socket = UdpSocket::bind("127.0.0.1:0").unwrap();
+    socket.set_read_timeout(Some(Duration::from_secs(2))).unwrap();
+    let addr = socket.local_addr().unwrap();
+    thread::spawn(move || {
+        let mut buf = [0u8; 4096];
+        while let Ok((len, peer)) = socket.recv_from(&mut buf) {
+            if let Some(reply) = handler(&buf[..len]) {
+                socket.send_to(&reply, peer).unwrap();
+            }
+        }
+    });
+    addr
+}
+
+pub fn community_message(version: i64, community: &[u8], pdu: Vec<u8>) -> Vec<u8> {
+    seq(&[int(version), octets(community), pdu])
+}
+
+/// Splits a community-based message into (version, community, PDU).
+pub fn read_community_message(bytes: &[u8]) -> (i64, &[u8], &[u8]) {
+    let (_, message, _) = read_tlv(bytes);
+    let (version, rest) = read_int(message);
+    let (_, community, pdu) = read_tlv(rest);
+    (version, community, pdu)
+}
+
+pub fn read_oid(bytes: &[u8]) -> (Vec<u32>, &[u8]) {
+    let (tag, content, rest) = read_tlv(bytes);
+    assert_eq!(tag, 0x06);
+    let mut name = vec![(content[0] / 40) as u32, (content[0] % 40) as u32];
+    let mut subid = 0u32;
+    for &b in &content[1..] {
+        subid = (subid << 7) | (b & 0x7f) as u32;
+        if b & 0x80 == 0 {
+            name.push(subid);
+            subid = 0;
+        }
+    }
+    (name, rest)
+}
+
+/// Decoded request PDU: (tag, request-id, error-status/non-repeaters,
+/// error-index/max-repetitions, [(name, encoded value)]).
+pub type RequestPdu = (u8, i32, i64, i64, Vec<(Vec<u32>, Vec<u8>)>);
+
+pub fn read_request_pdu(pdu: &[u8]) -> RequestPdu {
+    let (tag, body, _) = read_tlv(pdu);
+    let (req_id, rest) = read_int(body);
+    let (field1, rest) = read_int(rest);
+    let (field2, rest) = read_int(rest);
+    let (_, mut varbinds, _) = read_tlv(rest);
+    let mut out = Vec::new();
+    while !varbinds.is_empty() {
+        let (_, pair, rest) = read_tlv(varbinds);
+        let (name, value) = read_oid(pair);
+        out.push((name, value.to_vec()));
+        varbinds = rest;
+    }
+    (tag, req_id as i32, field1, field2, out)
+}
+
+pub const END_OF_MIB_VIEW: [u8; 2] = [0x82, 0x00];
+pub const NO_SUCH_OBJECT: [u8; 2] = [0x80, 0x00];
+
+/// Answers a request PDU from an ordered object table, SNMPv2 style.
+pub fn answer(objects: &mut Vec<(Vec<u32>, Vec<u8>)>, pdu_bytes: &[u8]) -> Vec<u8> {
+    let (tag, req_id, field1, field2, names) = read_request_pdu(pdu_bytes);
+    let next = |objects: &Vec<(Vec<u32>, Vec<u8>)>, name: &[u32]| -> (Vec<u32>, Vec<u8>) {
+        match objects.iter().find(|(n, _)| &n[..] > name) {
+            Some((n, v)) => (n.clone(), v.clone()),
+            None => (name.to_vec(), END_OF_MIB_VIEW.to_vec()),
+        }
+    };
+    let mut out = Vec::new();
+    match tag {
+        0xa0 => for (name, _) in &names {
+            let value = objects.iter().find(|(n, _)| n == name)
+                .map_or(NO_SUCH_OBJECT.to_vec(), |(_, v)| v.clone());
+            out.push(varbind(name, value));
+        },
+        0xa1 => for (name, _) in &names {
+            let (n, v) = next(objects, name);
+            out.push(varbind(&n, v));
+        },
+        0xa5 => {
+            let non_repeaters = (field1.max(0) as usize).min(names.len());
+            for (name, _) in &names[..non_repeaters] {
+                let (n, v) = next(objects, name);
+                out.push(varbind(&n, v));
+            }
+            let mut cursors: Vec<Vec<u32>> = names[non_repeaters..].iter().map(|(n, _)| n.clone()).collect();
+            for _ in 0..field2 {
+                for cursor in cursors.iter_mut() {
+                    let (n, v) = next(objects, cursor);
+                    out.push(varbind(&n, v));
+                    *cursor = n;
+                }
+            }
+        }
+        0xa3 => for (name, value) in &names {
+            match objects.iter_mut().find(|(n, _)| n == name) {
+                Some(object) => object.1 = value.clone(),
+                None => {
+                    objects.push((name.clone(), value.clone()));
+                    objects.sort();
+                }
+            }
+            out.push(varbind(name, value.clone()));
+        },
+        _ => panic!("unexpected PDU tag {:#x}", tag),
+    }
+    pdu(0xa2, req_id, 0, 0, &out)
+}
+
+/// Community-based stand-in agent serving `objects`, which must be sorted.
+pub fn mib_agent(version: i64, community: &'static [u8], mut objects: Vec<(Vec<u32>, Vec<u8>)>) -> SocketAddr {
+    spawn_agent(move |req| {
+        let (req_version, req_community, pdu_bytes) = read_community_message(req);
+        if req_version != version || req_community != community {
+            return None;
+        }
+        Some(community_message(version, community, answer(&mut objects, pdu_bytes)))
+    })
+}
@@ -0,0 +1,111 @@
+// Generated from IF-MIB by snmplib. Do not edit.
+
+/// A row of IF-MIB::ifTable.
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct IfEntry {
+    /// The instance sub-IDs following the column OIDs.
+    pub index: snmplib::Oid,
+    /// IF-MIB::ifIndex
+    pub if_index: Option<i64>,
+    /// IF-MIB::ifDescr
+    pub if_descr: Option<String>,
+    /// IF-MIB::ifType
+    pub if_type: Option<i64>,
+    /// IF-MIB::ifMtu
+    pub if_mtu: Option<i64>,
+    /// IF-MIB::ifSpeed
+    pub if_speed: Option<u32>,
+    /// IF-MIB::ifPhysAddress
+    pub if_phys_address: Option<Vec<u8>>,
+    /// IF-MIB::ifAdminStatus
+    pub if_admin_status: Option<i64>,
+    /// IF-MIB::ifOperStatus
+    pub if_oper_status: Option<i64>,
+    /// IF-MIB::ifLastChange
+    pub if_last_change: Option<u32>,
+    /// IF-MIB::ifInOctets
+    pub if_in_octets: Option<u32>,
+}
+
+impl IfEntry {
+    /// IF-MIB::ifEntry
+    pub const OID: &'static [u32] = &[1, 3, 6, 1, 2, 1, 2, 2, 1];
+
+    /// The columns fetched, in field order.
+    pub const COLUMNS: &'static [&'static [u32]] = &[
+        &[1, 3, 6, 1, 2, 1, 2, 2, 1, 1],
+        &[1, 3, 6, 1, 2, 1, 2, 2, 1, 2],
+        &[1, 3, 6, 1, 2, 1, 2, 2, 1, 3],
+        &[1, 3, 6, 1, 2, 1, 2, 2, 1, 4],
+        &[1, 3, 6, 1, 2, 1, 2, 2, 1, 5],
+        &[1, 3, 6, 1, 2, 1, 2, 2, 1, 6],
+        &[1, 3, 6, 1, 2, 1, 2, 2, 1, 7],
+        &[1, 3, 6, 1, 2, 1, 2, 2, 1, 8],
+        &[1, 3, 6, 1, 2, 1, 2, 2, 1, 9],
+        &[1, 3, 6, 1, 2, 1, 2, 2, 1, 10],
+    ];
+
+    /// Builds a row from cells in `COLUMNS` order; cells of another type are `None`.
+    pub fn from_cells(index: &[u32], cells: &[Option<snmplib::OwnedValue>]) -> IfEntry {
+        let cell = |i: usize| cells.get(i).and_then(|cell| cell.as_ref());
+        IfEntry {
+            index: snmplib::Oid::from(index),
+            if_index: cell(0).and_then(snmplib::FromValue::from_value),
+            if_descr: cell(1).and_then(snmplib::FromValue::from_value),
+            if_type: cell(2).and_then(snmplib::FromValue::from_value),
+            if_mtu: cell(3).and_then(snmplib::FromValue::from_value),
+            if_speed: cell(4).and_then(snmplib::FromValue::from_value),
+            if_phys_address: cell(5).and_then(snmplib::FromValue::from_value),
+            if_admin_status: cell(6).and_then(snmplib::FromValue::from_value),
+            if_oper_status: cell(7).and_then(snmplib::FromValue::from_value),
+            if_last_change: cell(8).and_then(snmplib::FromValue::from_value),
+            if_in_octets: cell(9).and_then(snmplib::FromValue::from_value),
+        }
+    }
+
+    /// Fetches every row of ifTable with GETBULK.
+    pub fn fetch(sess: &mut snmplib::SyncSession, max_repetitions: u32)
+                 -> snmplib::SnmpResult<Vec<IfEntry>> {
+        let table = sess.table(Self::COLUMNS, max_repetitions)?;
+        Ok(table.rows().map(|(index, cells)| IfEntry::from_cells(index, cells)).collect())
+    }
+}
+
+/// A row of IF-MIB::ifXTable.
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct IfXEntry {
+    /// The instance sub-IDs following the column OIDs.
+    pub index: snmplib::Oid,
+    /// IF-MIB::ifName
+    pub if_name: Option<String>,
+    /// IF-MIB::ifPromiscuousMode
+    pub if_promiscuous_mode: Option<i64>,
+}
+
+impl IfXEntry {
+    /// IF-MIB::ifXEntry
+    pub const OID: &'static [u32] = &[1, 3, 6, 1, 2, 1, 31, 1, 1, 1];
+
+    /// The columns fetched, in field order.
+    pub const COLUMNS: &'static [&'static [u32]] = &[
+        &[1, 3, 6, 1, 2, 1, 31, 1, 1, 1, 1],
+        &[1, 3, 6, 1, 2, 1, 31, 1, 1, 1, 16],
+    ];
+
+    /// Builds a row from cells in `COLUMNS` order; cells of another type are `None`.
+    pub fn from_cells(index: &[u32], cells: &[Option<snmplib::OwnedValue>]) -> IfXEntry {
+        let cell = |i: usize| cells.get(i).and_then(|cell| cell.as_ref());
+        IfXEntry {
+            index: snmplib::Oid::from(index),
+            if_name: cell(0).and_then(snmplib::FromValue::from_value),
+            if_promiscuous_mode: cell(1).and_then(snmplib::FromValue::from_value),
+        }
+    }
+
+    /// Fetches every row of ifXTable with GETBULK.
+    pub fn fetch(sess: &mut snmplib::SyncSession, max_repetitions: u32)
+                 -> snmplib::SnmpResult<Vec<IfXEntry>> {
+        let table = sess.table(Self::COLUMNS, max_repetitions)?;
+        Ok(table.rows().map(|(index, cells)| IfXEntry::from_cells(index, cells)).collect())
+    }
+}
@@ -0,0 +1,258 @@
+extern crate snmplib;
+
+use std::path::Path;
+
+use snmplib::{ObjectIdentifier, Value};
+use snmplib::mib::{IndexItem, Mib, MibError, NodeKind, Syntax};
+
+fn mibs() -> Mib {
+    let mut mib = Mib::new();
+    let dir = Path::new(env!("CARGO_MANIFEST_DIR")).join("tests/mibs");
+    let mut loaded = mib.load_dir(dir).unwrap();
+    loaded.sort();
+    assert_eq!(loaded, ["DEMO-MIB", "DEMO-TRAP-MIB", "IF-MIB", "INET-ADDRESS-MIB", "SNMPv2-TC"]);
+    mib
+}
+
+#[test]
+fn resolve_names() {
+    let mib = mibs();
+    assert_eq!(mib.resolve("IF-MIB::ifInOctets.3").unwrap().to_string(), "1.3.6.1.2.1.2.2.1.10.3");
+    assert_eq!(mib.resolve(".ifInOctets.3").unwrap().to_string(), "1.3.6.1.2.1.2.2.1.10.3");
+    assert_eq!(mib.resolve(".ifNumber").unwrap().to_string(), "1.3.6.1.2.1.2.1");
+    assert_eq!(mib.resolve("ifDescr").unwrap().to_string(), "1.3.6.1.2.1.2.2.1.2");
+    assert_eq!(mib.resolve("ifName.2").unwrap().to_string(), "1.3.6.1.2.1.31.1.1.1.1.2");
+    assert_eq!(mib.resolve("SNMPv2-SMI::enterprises").unwrap().to_string(), "1.3.6.1.4.1");
+    assert_eq!(mib.resolve(".1.3.6.1").unwrap().to_string(), "1.3.6.1");
+
+    for bad in &["IF-MIB::noSuchThing", "SNMPv2-TC::ifDescr", "ifDescr.x", "1.3..6"] {
+        match mib.resolve(bad) {
+            Err(MibError::UnknownName(name)) => assert_eq!(&name, bad),
+            other => panic!("{}: {:?}", bad, other),
+        }
+    }
+}
+
+#[test]
+fn display_names() {
+    let mib = mibs();
+    let oid = mib.resolve("IF-MIB::ifInOctets.3").unwrap();
+    assert_eq!(mib.display(&oid).to_string(), "IF-MIB::ifInOctets.3");
+    assert_eq!(oid.display_with(&mib).to_string(), "IF-MIB::ifInOctets.3");
+    let ber = ObjectIdentifier::from_bytes(&[0x2b, 6, 1, 4, 1, 0x86, 0x8d, 0x1f, 1, 1, 0]);
+    assert_eq!(ber.display_with(&mib).to_string(), "DEMO-TRAP-MIB::demoDrops.0");
+    assert_eq!(mib.display(&[1, 3, 6, 1, 2, 1, 99]).to_string(), "SNMPv2-SMI::mib-2.99");
+    assert_eq!(mib.display(&[3, 1]).to_string(), "3.1");
+
+    let (node, index) = mib.lookup(&oid).unwrap();
+    assert_eq!(node.name, "ifInOctets");
+    assert_eq!(index, &[3]);
+}
+
+#[test]
+fn object_metadata() {
+    let mib = mibs();
+    let entry = mib.node("ifEntry").unwrap();
+    assert_eq!(entry.index, [IndexItem { name: "ifIndex".into(), implied: false }]);
+    assert_eq!(entry.syntax, Some(Syntax::Named { name: "IfEntry".into(), enums: Vec::new() }));
+    assert_eq!(mib.node("ifXEntry").unwrap().augments.as_deref(), Some("ifEntry"));
+    assert_eq!(mib.node("ifTable").unwrap().syntax, Some(Syntax::SequenceOf("IfEntry".into())));
+
+    let status = mib.node("IF-MIB::ifAdminStatus").unwrap();
+    assert_eq!(status.kind, NodeKind::ObjectType);
+    assert_eq!(status.access.as_deref(), Some("read-write"));
+    assert_eq!(status.status.as_deref(), Some("current"));
+    assert_eq!(status.syntax, Some(Syntax::Integer(vec![
+        ("up".into(), 1), ("down".into(), 2), ("testing".into(), 3),
+    ])));
+    assert_eq!(mib.node("ifSpeed").unwrap().units.as_deref(), Some("bits per second"));
+    assert!(mib.node("ifNumber").unwrap().description.as_ref().unwrap().starts_with("The number of"));
+
+    let module = mib.node("ifMIB").unwrap();
+    assert_eq!(module.kind, NodeKind::ModuleIdentity);
+    assert!(module.description.as_ref().unwrap().starts_with("The MIB module"));
+    assert_eq!(mib.node("ifGeneralInformationGroup").unwrap().kind, NodeKind::Group);
+
+    let row = mib.type_def("IF-MIB", "IfEntry").unwrap();
+    match row.syntax {
+        Syntax::Sequence(ref columns) => assert_eq!(columns[1].0, "ifDescr"),
+        ref other => panic!("{:?}", other),
+    }
+}
+
+#[test]
+fn textual_conventions() {
+    let mib = mibs();
+    let display = mib.type_def("IF-MIB", "DisplayString").unwrap();
+    assert_eq!(display.module, "SNMPv2-TC");
+    assert!(display.textual_convention);
+    assert_eq!(display.display_hint.as_deref(), Some("255a"));
+    assert_eq!(display.syntax, Syntax::OctetString);
+    let truth = mib.type_def("IF-MIB", "TruthValue").unwrap();
+    assert_eq!(truth.syntax, Syntax::Integer(vec![("true".into(), 1), ("false".into(), 2)]));
+    assert_eq!(mib.type_def("IF-MIB", "InterfaceIndex").unwrap().display_hint.as_deref(), Some("d"));
+}
+
+#[test]
+fn smiv1_traps() {
+    let mib = mibs();
+    let trap = mib.node("demoOverload").unwrap();
+    assert_eq!(trap.kind, NodeKind::Trap);
+    assert_eq!(trap.oid.to_string(), "1.3.6.1.4.1.99999.0.7");
+    assert_eq!(mib.node("demoDrops").unwrap().access.as_deref(), Some("read-only"));
+}
+
+#[test]
+fn deferred_resolution() {
+    let mut mib = mibs();
+    // linkDown hangs off snmpTraps, which is defined in SNMPv2-MIB.
+    assert!(mib.node("linkDown").is_none());
+    assert_eq!(mib.unresolved().collect::<Vec<_>>(), [("IF-MIB", "linkDown")]);
+
+    mib.load_str("
+        SNMPv2-MIB DEFINITIONS ::= BEGIN
+        IMPORTS snmpModules FROM SNMPv2-SMI;
+        snmpMIB         OBJECT IDENTIFIER ::= { snmpModules 1 }
+        snmpMIBObjects  OBJECT IDENTIFIER ::= { snmpMIB 1 }
+        snmpTrap        OBJECT IDENTIFIER ::= { snmpMIBObjects 4 }
+        snmpTraps       OBJECT IDENTIFIER ::= { snmpMIBObjects 5 }
+        END
+    ").unwrap();
+    assert_eq!(mib.resolve("IF-MIB::linkDown").unwrap().to_string(), "1.3.6.1.6.3.1.1.5.3");
+    assert_eq!(mib.node("linkDown").unwrap().kind, NodeKind::Notification);
+    assert_eq!(mib.unresolved().count(), 0);
+}
+
+#[test]
+fn parse_errors() {
+    let mut mib = Mib::new();
+    match mib.load_str("BROKEN-MIB DEFINITIONS ::= BEGIN\n\nfoo OBJECT IDENTIFIER ::= { bar 1 \nEND\n") {
+        Err(MibError::Parse { line, .. }) => assert_eq!(line, 4),
+        other => panic!("{:?}", other),
+    }
+    match mib.load_str("X DEFINITIONS ::= BEGIN\n  \"unterminated\nEND\n") {
+        Err(err @ MibError::Parse { .. }) => assert!(err.to_string().starts_with("line 2:"), "{}", err),
+        other => panic!("{:?}", other),
+    }
+    for text in &["X DEFINITIONS ::= BEGIN\nfoo OBJECT IDENTIFIER ::= { iso bar(-1) }\nEND\n",
+                  "X DEFINITIONS ::= BEGIN\nfoo OBJECT IDENTIFIER ::= { iso bar(4294967296) }\nEND\n",
+                  "X DEFINITIONS ::= BEGIN\nfoo OBJECT IDENTIFIER ::= { iso bar(-) }\nEND\n"] {
+        match mib.load_str(text) {
+            Err(MibError::Parse { line, .. }) => assert_eq!(line, 2),
+            other => panic!("{:?}", other),
+        }
+    }
+    match mib.load_file("tests/mibs/NO-SUCH-MIB") {
+        Err(MibError::InFile(path, err)) => {
+            assert!(path.ends_with("NO-SUCH-MIB"));
+            assert!(matches!(*err, MibError::Io(_)));
+        }
+        other => panic!("{:?}", other),
+    }
+}
+
+#[test]
+fn render_enumerations() {
+    let mib = mibs();
+    let oper_status = mib.resolve("ifOperStatus.2").unwrap();
+    assert_eq!(mib.format_value(&oper_status, &Value::Integer(1)), "up(1)");
+    assert_eq!(mib.format_value(&oper_status, &Value::Integer(7)), "lowerLayerDown(7)");
+    assert_eq!(mib.format_value(&oper_status, &Value::Integer(42)), "42");
+    assert_eq!(mib.format_varbind(&oper_status, &Value::Integer(2)), "IF-MIB::ifOperStatus.2 = down(2)");
+    // Enumerations inherited from a textual convention
+    let promiscuous = mib.resolve("ifPromiscuousMode.1").unwrap();
+    assert_eq!(mib.format_value(&promiscuous, &Value::Integer(2)), "false(2)");
+    assert_eq!(mib.format_value(&mib.resolve("demoPeerType.0").unwrap(), &Value::Integer(2)), "ipv6(2)");
+}
+
+#[test]
+fn render_display_hints() {
+    let mib = mibs();
+    let mac = [0x00, 0x1a, 0x2b, 0x3c, 0x4d, 0x5e];
+    assert_eq!(mib.format_value(&mib.resolve("ifPhysAddress.1").unwrap(), &Value::OctetString(&mac)),
+               "00:1a:2b:3c:4d:5e");
+    assert_eq!(mib.format_value(&mib.resolve("ifDescr.1").unwrap(), &Value::OctetString(b"eth0")), "eth0");
+    assert_eq!(mib.format_value(&mib.resolve("ifIndex.1").unwrap(), &Value::Integer(1)), "1");
+
+    let boot_time = mib.resolve("demoBootTime.0").unwrap();
+    let local = [0x07, 0xea, 10, 17, 13, 5, 2, 0, b'+', 2, 0];
+    assert_eq!(mib.format_value(&boot_time, &Value::OctetString(&local)), "2026-10-17,13:5:2.0,+2:0");
+    assert_eq!(mib.format_value(&boot_time, &Value::OctetString(&local[..8])), "2026-10-17,13:5:2.0");
+
+    let temperature = mib.resolve("demoTemperature.0").unwrap();
+    assert_eq!(mib.format_value(&temperature, &Value::Integer(235)), "23.5");
+    assert_eq!(mib.format_value(&temperature, &Value::Integer(-5)), "-0.5");
+}
+
+#[test]
+fn render_malformed_display_hints() {
+    let mut mib = Mib::new();
+    mib.load_str("
+        HINT-MIB DEFINITIONS ::= BEGIN
+        ZeroLength ::= TEXTUAL-CONVENTION
+            DISPLAY-HINT \"0x\"
+            STATUS current
+            DESCRIPTION \"\"
+            SYNTAX OCTET STRING
+        ZeroRepeat ::= TEXTUAL-CONVENTION
+            DISPLAY-HINT \"*0d\"
+            STATUS current
+            DESCRIPTION \"\"
+            SYNTAX OCTET STRING
+        Wide ::= TEXTUAL-CONVENTION
+            DISPLAY-HINT \"32d\"
+            STATUS current
+            DESCRIPTION \"\"
+            SYNTAX OCTET STRING
+        hintMIB OBJECT IDENTIFIER ::= { enterprises 99997 }
+        zeroLength OBJECT-TYPE SYNTAX ZeroLength MAX-ACCESS read-only STATUS current
+            DESCRIPTION \"\" ::= { hintMIB 1 }
+        zeroRepeat OBJECT-TYPE SYNTAX ZeroRepeat MAX-ACCESS read-only STATUS current
+            DESCRIPTION \"\" ::= { hintMIB 2 }
+        wide OBJECT-TYPE SYNTAX Wide MAX-ACCESS read-only STATUS current
+            DESCRIPTION \"\" ::= { hintMIB 3 }
+        END
+    ").unwrap();
+
+    // Hints that cannot be applied fall back to plain rendering.
+    for name in &["zeroLength.0", "zeroRepeat.0"] {
+        let oid = mib.resolve(name).unwrap();
+        assert_eq!(mib.format_value(&oid, &Value::OctetString(&[3, 0xff, 0x10])), "03 FF 10");
+    }
+    let wide = mib.resolve("wide.0").unwrap();
+    assert_eq!(mib.format_value(&wide, &Value::OctetString(&[0xff; 32])), ["FF"; 32].join(" "));
+}
+
+#[test]
+fn render_bits_and_addresses() {
+    let mib = mibs();
+    let alarms = mib.resolve("demoAlarms.0").unwrap();
+    assert_eq!(mib.format_value(&alarms, &Value::OctetString(&[0xa0, 0x40])), "power(0) temperature(2) door(9)");
+    assert_eq!(mib.format_value(&alarms, &Value::OctetString(&[0x10])), "3");
+    assert_eq!(mib.format_value(&alarms, &Value::OctetString(&[0])), "");
+
+    let peer = mib.resolve("demoPeer.0").unwrap();
+    assert_eq!(mib.format_value(&peer, &Value::OctetString(&[192, 0, 2, 1])), "192.0.2.1");
+    let v6 = [0x20, 0x01, 0x0d, 0xb8, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 1];
+    assert_eq!(mib.format_value(&peer, &Value::OctetString(&v6)), "2001:db8::1");
+    assert_eq!(mib.format_value(&peer, &Value::OctetString(b"")), "");
+}
+
+#[test]
+fn render_by_type() {
+    let mib = mibs();
+    let serial = mib.resolve("demoSerial.0").unwrap();
+    assert_eq!(mib.format_value(&serial, &Value::OctetString(b"SN-1234")), "SN-1234");
+    assert_eq!(mib.format_value(&serial, &Value::OctetString(&[0, 0xff, 0x10])), "00 FF 10");
+
+    let unknown = [1, 3, 6, 1, 4, 1, 1, 1, 0];
+    assert_eq!(mib.format_value(&unknown, &Value::Timeticks(12345)), "(12345) 0:02:03.45");
+    assert_eq!(mib.format_value(&unknown, &Value::Timeticks(8_640_000 * 2 + 100)), "(17280100) 2 days, 0:00:01.00");
+    assert_eq!(mib.format_value(&unknown, &Value::IpAddress([10, 0, 0, 1])), "10.0.0.1");
+    assert_eq!(mib.format_value(&unknown, &Value::Counter64(1 << 40)), "1099511627776");
+    let if_mib = [1, 3, 6, 1, 2, 1, 31];
+    assert_eq!(mib.format_value(&unknown, &Value::ObjectIdentifier(ObjectIdentifier::from_name(&if_mib))),
+               "IF-MIB::ifMIB");
+    assert_eq!(mib.format_value(&unknown, &Value::NoSuchInstance),
+               "No Such Instance currently exists at this OID");
+}
@@ -0,0 +1,121 @@
+extern crate snmplib;
+
+mod common;
+
+use std::net::{Ipv4Addr, SocketAddr};
+use std::time::Duration;
+
+use snmplib::{Oid, SnmpError, SyncSession, mib2};
+use snmplib::mib2::{IfStatus, IpAddrEntry};
+
+use common::*;
+
+fn at(column: &[u32], index: &[u32]) -> Vec<u32> {
+    [column, index].concat()
+}
+
+fn agent(version: i64) -> SocketAddr {
+    let mut objects = vec![
+        (mib2::SYS_DESCR.to_vec(), octets(b"stand-in router")),
+        (mib2::SYS_OBJECT_ID.to_vec(), oid(&[1, 3, 6, 1, 4, 1, 8072, 3, 2, 10])),
+        (mib2::SYS_UP_TIME.to_vec(), tlv(0x43, &[0x01, 0x00, 0x00])),
+        (mib2::SYS_NAME.to_vec(), octets(b"router1")),
+        (mib2::SYS_LOCATION.to_vec(), octets(b"rack 4")),
+        (at(mib2::IF_DESCR, &[1]), octets(b"lo")),
+        (at(mib2::IF_DESCR, &[2]), octets(b"eth0")),
+        (at(mib2::IF_TYPE, &[1]), int(24)),
+        (at(mib2::IF_TYPE, &[2]), int(6)),
+        (at(mib2::IF_PHYS_ADDRESS, &[2]), octets(&[0, 0x1a, 0x2b, 0x3c, 0x4d, 0x5e])),
+        (at(mib2::IF_ADMIN_STATUS, &[1]), int(1)),
+        (at(mib2::IF_ADMIN_STATUS, &[2]), int(1)),
+        (at(mib2::IF_OPER_STATUS, &[1]), int(1)),
+        (at(mib2::IF_OPER_STATUS, &[2]), int(7)),
+        (at(mib2::IF_IN_OCTETS, &[1]), tlv(0x41, &[0x10])),
+        (at(mib2::IF_IN_OCTETS, &[2]), tlv(0x41, &[0x20])),
+        (at(mib2::IF_NAME, &[2]), octets(b"eth0")),
+        (at(mib2::IF_HC_IN_OCTETS, &[2]), tlv(0x46, &[0x01, 0x00, 0x00, 0x00, 0x20])),
+        (at(mib2::IF_HIGH_SPEED, &[2]), tlv(0x42, &[0x03, 0xe8])),
+        (at(mib2::IP_AD_ENT_ADDR, &[10, 0, 0, 1]), tlv(0x40, &[10, 0, 0, 1])),
+        (at(mib2::IP_AD_ENT_ADDR, &[127, 0, 0, 1]), tlv(0x40, &[127, 0, 0, 1])),
+        (at(mib2::IP_AD_ENT_IF_INDEX, &[10, 0, 0, 1]), int(2)),
+        (at(mib2::IP_AD_ENT_IF_INDEX, &[127, 0, 0, 1]), int(1)),
+        (at(mib2::IP_AD_ENT_NET_MASK, &[10, 0, 0, 1]), tlv(0x40, &[255, 255, 255, 0])),
+        (at(mib2::IP_AD_ENT_NET_MASK, &[127, 0, 0, 1]), tlv(0x40, &[255, 0, 0, 0])),
+    ];
+    objects.sort();
+    mib_agent(version, b"public", objects)
+}
+
+fn check_interfaces(sess: &mut SyncSession) {
+    let interfaces = mib2::interfaces(sess).unwrap();
+    assert_eq!(interfaces.len(), 2);
+    let lo = &interfaces[0];
+    assert_eq!((lo.index, lo.descr.as_str(), lo.if_type), (1, "lo", Some(24)));
+    assert_eq!(lo.oper_status, Some(IfStatus::Up));
+    assert_eq!(lo.name, None);
+    assert_eq!(lo.in_octets64(), Some(16));
+    assert_eq!(lo.out_octets64(), None);
+
+    let eth0 = &interfaces[1];
+    assert_eq!(eth0.name.as_deref(), Some("eth0"));
+    assert_eq!(eth0.phys_address, [0, 0x1a, 0x2b, 0x3c, 0x4d, 0x5e]);
+    assert_eq!(eth0.admin_status, Some(IfStatus::Up));
+    assert_eq!(eth0.oper_status, Some(IfStatus::LowerLayerDown));
+    assert_eq!(eth0.high_speed, Some(1000));
+    assert_eq!(eth0.in_octets, Some(32));
+    assert_eq!(eth0.in_octets64(), Some((1 << 32) + 32));
+}
+
+#[test]
+fn system_group() {
+    let mut sess = SyncSession::new(agent(1), b"public", Some(Duration::from_secs(2)), 0).unwrap();
+    let system = mib2::system(&mut sess).unwrap();
+    assert_eq!(system.descr, "stand-in router");
+    assert_eq!(system.object_id, "1.3.6.1.4.1.8072.3.2.10".parse::<Oid>().unwrap());
+    assert_eq!(system.up_time, 0x10000);
+    assert_eq!(system.name, "router1");
+    assert_eq!(system.location, "rack 4");
+    // Missing from the agent
+    assert_eq!(system.contact, "");
+}
+
+#[test]
+fn system_group_v1() {
+    let mut sess = SyncSession::new_v1(agent(0), b"public", Some(Duration::from_secs(2)), 0).unwrap();
+    let system = mib2::system(&mut sess).unwrap();
+    assert_eq!(system.name, "router1");
+    // noSuchName from an SNMPv1 agent
+    assert_eq!(system.contact, "");
+}
+
+#[test]
+fn system_group_error_status() {
+    let addr = spawn_agent(|req| {
+        let (_, community, pdu_bytes) = read_community_message(req);
+        let (_, req_id) = read_pdu_header(pdu_bytes);
+        Some(community_message(1, community, pdu(0xa2, req_id, 5, 1, &[varbind(mib2::SYS_DESCR, null())])))
+    });
+    let mut sess = SyncSession::new(addr, b"public", Some(Duration::from_secs(2)), 0).unwrap();
+    assert_eq!(mib2::system(&mut sess), Err(SnmpError::ErrorStatus { status: 5, index: 1 }));
+}
+
+#[test]
+fn interfaces_v2c() {
+    let mut sess = SyncSession::new(agent(1), b"public", Some(Duration::from_secs(2)), 0).unwrap();
+    check_interfaces(&mut sess);
+}
+
+#[test]
+fn interfaces_v1() {
+    let mut sess = SyncSession::new_v1(agent(0), b"public", Some(Duration::from_secs(2)), 0).unwrap();
+    check_interfaces(&mut sess);
+}
+
+#[test]
+fn ip_addresses() {
+    let mut sess = SyncSession::new(agent(1), b"public", Some(Duration::from_secs(2)), 0).unwrap();
+    assert_eq!(mib2::ip_addresses(&mut sess).unwrap(), [
+        IpAddrEntry { addr: Ipv4Addr::new(10, 0, 0, 1), if_index: Some(2), net_mask: Some(Ipv4Addr::new(255, 255, 255, 0)) },
+        IpAddrEntry { addr: Ipv4Addr::LOCALHOST, if_index: Some(1), net_mask: Some(Ipv4Addr::new(255, 0, 0, 0)) },
+    ]);
+}
@@ -0,0 +1,88 @@
+extern crate snmplib;
+
+mod common;
+
+use std::net::UdpSocket;
+use std::time::{Duration, Instant};
+
+use snmplib::SnmpError;
+use snmplib::mux::{Client, Target};
+
+use common::*;
+
+const SYS_DESCR: &[u32] = &[1, 3, 6, 1, 2, 1, 1, 1, 0];
+const TIMEOUT: Duration = Duration::from_secs(2);
+
+fn descr_agent(version: i64, descr: &str) -> std::net::SocketAddr {
+    mib_agent(version, b"public", vec![(SYS_DESCR.to_vec(), octets(descr.as_bytes()))])
+}
+
+#[test]
+fn many_agents_one_socket() {
+    let client = Client::new("127.0.0.1:0").unwrap();
+    let targets: Vec<_> = (0..50)
+        .map(|i| Target::v2c(descr_agent(1, &format!("agent {}", i)), b"public"))
+        .collect();
+    let pending: Vec<_> = targets.iter()
+        .map(|target| client.get(target, SYS_DESCR, TIMEOUT).unwrap())
+        .collect();
+    // Wait in reverse so that most responses arrive before their waiter.
+    for (i, request) in pending.into_iter().enumerate().rev() {
+        let resp = request.wait().unwrap();
+        assert_eq!(resp.source(), targets[i].addr());
+        let pdu = resp.pdu();
+        let (name, value) = pdu.varbinds.clone().next().unwrap();
+        assert_eq!(format!("{}", name), "1.3.6.1.2.1.1.1.0");
+        assert_eq!(format!("{:?}", value), format!("OCTET STRING: agent {}", i));
+    }
+}
+
+#[test]
+fn v1_and_v2c_targets() {
+    let client = Client::new("127.0.0.1:0").unwrap();
+    let v1 = Target::v1(descr_agent(0, "old"), b"public");
+    let v2 = Target::v2c(descr_agent(1, "new"), b"public");
+    assert_eq!(client.getbulk(&v1, &[SYS_DESCR], 0, 1, TIMEOUT).err(), Some(SnmpError::UnsupportedVersion));
+    let a = client.getnext(&v1, &[1, 3, 6, 1, 2, 1, 1], TIMEOUT).unwrap();
+    let b = client.getbulk(&v2, &[&[1, 3, 6, 1, 2, 1, 1]], 0, 1, TIMEOUT).unwrap();
+    assert_eq!(a.wait().unwrap().pdu().varbinds.count(), 1);
+    assert_eq!(b.wait().unwrap().pdu().varbinds.count(), 1);
+}
+
+#[test]
+fn per_request_timeout() {
+    // Bound but silent.
+    let silent = UdpSocket::bind("127.0.0.1:0").unwrap();
+    let client = Client::new("127.0.0.1:0").unwrap();
+    let target = Target::v2c(silent.local_addr().unwrap(), b"public");
+    let answering = Target::v2c(descr_agent(1, "up"), b"public");
+
+    let start = Instant::now();
+    let lost = client.get(&target, SYS_DESCR, Duration::from_millis(200)).unwrap();
+    let found = client.get(&answering, SYS_DESCR, TIMEOUT).unwrap();
+    assert!(found.wait().is_ok());
+    assert!(lost.wait().is_err());
+    assert!(start.elapsed() < TIMEOUT);
+}
+
+#[test]
+fn response_from_other_address_is_ignored() {
+    let client = Client::new("127.0.0.1:0").unwrap();
+    let client_addr = client.local_addr().unwrap();
+    let silent = UdpSocket::bind("127.0.0.1:0").unwrap();
+    let target = Target::v2c(silent.local_addr().unwrap(), b"public");
+    let request = client.get(&target, SYS_DESCR, Duration::from_millis(300)).unwrap();
+
+    // Same request-id, wrong source.
+    let (len, buf) = {
+        let mut buf = [0u8; 4096];
+        let (len, _) = silent.recv_from(&mut buf).unwrap();
+        (len, buf)
+    };
+    let (_, _, pdu_bytes) = read_community_message(&buf[..len]);
+    let (_, req_id) = read_pdu_header(pdu_bytes);
+    let spoof = UdpSocket::bind("127.0.0.1:0").unwrap();
+    let reply = community_message(1, b"public", pdu(0xa2, req_id, 0, 0, &[varbind(SYS_DESCR, null())]));
+    spoof.send_to(&reply, client_addr).unwrap();
+    assert!(request.wait().is_err());
+}
@@ -0,0 +1,89 @@
+extern crate snmplib;
+
+mod common;
+
+use std::convert::TryFrom;
+use std::time::Duration;
+
+use snmplib::{ObjectIdentifier, Oid, SnmpError, SyncSession};
+
+use common::*;
+
+#[test]
+fn parse_and_display() {
+    let oid: Oid = "1.3.6.1.2.1.1.1.0".parse().unwrap();
+    assert_eq!(oid, &[1, 3, 6, 1, 2, 1, 1, 1, 0][..]);
+    assert_eq!(".1.3.6.1.2.1.1.1.0".parse::<Oid>().unwrap(), oid);
+    assert_eq!(oid.to_string(), "1.3.6.1.2.1.1.1.0");
+    assert_eq!(format!("{:?}", oid), "Oid(1.3.6.1.2.1.1.1.0)");
+    assert_eq!("1.3.6.1.4.1.4294967295".parse::<Oid>().unwrap().last(), Some(&u32::MAX));
+
+    for bad in &["", ".", "1..3", "1.3.", "1.3.x", "1.-3", "1.+3", "1.4294967296", " 1.3"] {
+        assert_eq!(bad.parse::<Oid>(), Err(SnmpError::InvalidOid), "{:?}", bad);
+    }
+}
+
+#[test]
+fn snmp_ordering() {
+    let mut oids: Vec<Oid> = ["1.3.6.1.2.1.2", "1.3.6.1.2.1.10", "1.3.6.1.2.1.2.2.1.1", "1.3.6.1.2.1", "1.3.6.1.2.1.2.1"]
+        .iter().map(|s| s.parse().unwrap()).collect();
+    oids.sort();
+    let sorted: Vec<String> = oids.iter().map(Oid::to_string).collect();
+    assert_eq!(sorted, ["1.3.6.1.2.1", "1.3.6.1.2.1.2", "1.3.6.1.2.1.2.1", "1.3.6.1.2.1.2.2.1.1", "1.3.6.1.2.1.10"]);
+}
+
+#[test]
+fn prefix_operations() {
+    let if_table: Oid = "1.3.6.1.2.1.2.2".parse().unwrap();
+    let cell = if_table.child(1).child(10).child(3);
+    assert!(cell.starts_with(&if_table));
+    assert!(!if_table.starts_with(&cell));
+    assert_eq!(cell.strip_prefix(&if_table), Some(&[1, 10, 3][..]));
+    assert_eq!(if_table.strip_prefix(&cell), None);
+    assert_eq!(cell.parent().unwrap().parent().unwrap().parent().unwrap(), if_table);
+    assert_eq!(Oid::default().parent(), None);
+}
+
+#[test]
+fn object_identifier_conversions() {
+    // 200 sub-IDs, beyond ObjIdBuf's 128.
+    let long: Vec<u32> = [&[1, 3][..], &vec![300; 198]].concat();
+    let ber = oid(&long);
+    let (_, content, _) = read_tlv(&ber);
+    let decoded = Oid::try_from(ObjectIdentifier::from_bytes(content)).unwrap();
+    assert_eq!(decoded.as_slice(), &long[..]);
+    assert_eq!(decoded.as_object_identifier(), ObjectIdentifier::from_bytes(content));
+    assert_eq!(ObjectIdentifier::from_bytes(content).to_string(), decoded.to_string());
+    assert_eq!(ObjectIdentifier::from_bytes(content).raw(), Some(content));
+    assert_eq!(ObjectIdentifier::from_bytes(content).name(), None);
+    assert_eq!(decoded.as_object_identifier().name(), Some(&long[..]));
+    assert_eq!(decoded.as_object_identifier().raw(), None);
+    let mut buf = [0; 128];
+    assert_eq!(ObjectIdentifier::from_bytes(content).read_name(&mut buf), Err(SnmpError::AsnEof));
+
+    // sub-ID overflowing 32 bits
+    assert_eq!(Oid::try_from(ObjectIdentifier::from_bytes(&[0x2b, 0x90, 0x80, 0x80, 0x80, 0x00])),
+               Err(SnmpError::AsnIntOverflow));
+}
+
+#[test]
+fn oid_as_request_name() {
+    let sys_descr: Oid = "1.3.6.1.2.1.1.1.0".parse().unwrap();
+    let addr = mib_agent(1, b"public", vec![(sys_descr.to_vec(), octets(b"stand-in"))]);
+    let mut sess = SyncSession::new(addr, b"public", Some(Duration::from_secs(2)), 0).unwrap();
+    let resp = sess.get(&sys_descr).unwrap();
+    let (name, _) = resp.varbinds.clone().next().unwrap();
+    assert_eq!(Oid::try_from(name).unwrap(), sys_descr);
+}
+
+#[test]
+fn oid_macro() {
+    const SYS_DESCR: &[u32] = snmplib::oid!("1.3.6.1.2.1.1.1.0");
+    assert_eq!(SYS_DESCR, &[1, 3, 6, 1, 2, 1, 1, 1, 0]);
+    assert_eq!(snmplib::oid!(".1.3.6.1.4.1.4294967295"), &[1, 3, 6, 1, 4, 1, u32::MAX]);
+    assert_eq!(snmplib::oid!("2.39"), &[2, 39]);
+
+    let addr = mib_agent(1, b"public", vec![(SYS_DESCR.to_vec(), octets(b"stand-in"))]);
+    let mut sess = SyncSession::new(addr, b"public", Some(Duration::from_secs(2)), 0).unwrap();
+    assert_eq!(sess.get(snmplib::oid!("1.3.6.1.2.1.1.1.0")).unwrap().varbinds.count(), 1);
+}
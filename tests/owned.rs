@@ -0,0 +1,61 @@
+extern crate snmplib;
+
+mod common;
+
+use std::sync::Arc;
+use std::thread;
+use std::time::Duration;
+
+use snmplib::{Oid, OwnedPdu, OwnedValue, SyncSession};
+
+use common::*;
+
+const SYS_DESCR: &[u32] = &[1, 3, 6, 1, 2, 1, 1, 1, 0];
+const SYS_OBJECT_ID: &[u32] = &[1, 3, 6, 1, 2, 1, 1, 2, 0];
+
+fn session() -> SyncSession {
+    let addr = mib_agent(1, b"public", vec![
+        (SYS_DESCR.to_vec(), octets(b"stand-in")),
+        (SYS_OBJECT_ID.to_vec(), oid(&[1, 3, 6, 1, 4, 1, 8072, 3, 2, 10])),
+    ]);
+    SyncSession::new(addr, b"public", Some(Duration::from_secs(2)), 0).unwrap()
+}
+
+fn assert_send_static<T: Send + 'static>(_: &T) {}
+
+#[test]
+fn owned_pdu_outlives_the_next_request() {
+    let mut sess = session();
+    let descr = sess.get(SYS_DESCR).unwrap().into_owned().unwrap();
+    let object_id = sess.get(SYS_OBJECT_ID).unwrap().into_owned().unwrap();
+    assert_eq!(descr.community, b"public");
+    assert_eq!(descr.version(), 1);
+    assert_eq!(&descr.varbinds[..], &[(Oid::from(SYS_DESCR), OwnedValue::OctetString(b"stand-in".to_vec()))]);
+    assert_eq!(object_id.varbinds[0].1, OwnedValue::ObjectIdentifier("1.3.6.1.4.1.8072.3.2.10".parse().unwrap()));
+    assert_eq!(format!("{:?}", object_id.varbinds[0].1), "OBJECT IDENTIFIER: 1.3.6.1.4.1.8072.3.2.10");
+}
+
+#[test]
+fn owned_pdu_is_send_and_cheap_to_clone() {
+    let pdu = session().get(SYS_DESCR).unwrap().into_owned().unwrap();
+    assert_send_static(&pdu);
+    let copy = pdu.clone();
+    assert!(Arc::ptr_eq(&pdu.varbinds, &copy.varbinds));
+    let from_thread: OwnedPdu = thread::spawn(move || copy).join().unwrap();
+    assert_eq!(from_thread, pdu);
+}
+
+#[test]
+fn owned_values_encode_back() {
+    let mut sess = session();
+    let read = sess.getbulk(&[&[1, 3, 6, 1, 2, 1, 1]], 0, 2).unwrap().into_owned().unwrap();
+    let copied: Vec<(Oid, OwnedValue)> = read.varbinds.iter()
+        .map(|(name, value)| (name.parent().unwrap().child(1), value.clone()))
+        .collect();
+    let bindings: Vec<_> = copied.iter().map(|(name, value)| (&name[..], value.as_value())).collect();
+    let written = sess.set(&bindings).unwrap().into_owned().unwrap();
+    assert_eq!(&written.varbinds[..], &copied[..]);
+
+    let echoed = sess.set(&written.as_bindings()).unwrap().into_owned().unwrap();
+    assert_eq!(echoed.varbinds, written.varbinds);
+}
@@ -0,0 +1,111 @@
+extern crate snmplib;
+
+mod common;
+
+use snmplib::snmp::*;
+use snmplib::{ObjectIdentifier, OwnedValue, PduBuilder, SnmpMessageType, SnmpPdu, TrapV1, Value};
+
+use common::{community_message, int, null, octets, oid, pdu, seq, tlv, varbind};
+
+const SYS_DESCR: &[u32] = &[1, 3, 6, 1, 2, 1, 1, 1, 0];
+const SYS_UP_TIME: &[u32] = &[1, 3, 6, 1, 2, 1, 1, 3, 0];
+
+#[test]
+fn encodes_every_pdu_type() {
+    let types = [
+        (SnmpMessageType::GetRequest, 0xa0),
+        (SnmpMessageType::GetNextRequest, 0xa1),
+        (SnmpMessageType::GetBulkRequest, 0xa5),
+        (SnmpMessageType::Response, 0xa2),
+        (SnmpMessageType::SetRequest, 0xa3),
+        (SnmpMessageType::InformRequest, 0xa6),
+        (SnmpMessageType::Trap, 0xa7),
+        (SnmpMessageType::Report, 0xa8),
+    ];
+    for &(message_type, tag) in &types {
+        let builder = PduBuilder::new(message_type, 1234)
+            .with_error(ERRSTATUS_GENERR, 2)
+            .with_varbind(SYS_DESCR, Value::Null)
+            .with_varbind(SYS_UP_TIME, Value::Timeticks(100));
+        let expected = pdu(tag, 1234, 5, 2, &[varbind(SYS_DESCR, null()), varbind(SYS_UP_TIME, tlv(0x43, &[100]))]);
+        assert_eq!(builder.encode(), expected);
+        assert_eq!(builder.encode_message(VERSION_2, b"public"), community_message(1, b"public", expected));
+    }
+}
+
+#[test]
+fn encodes_getbulk_and_v1_trap_fields() {
+    let bulk = PduBuilder::new(SnmpMessageType::GetBulkRequest, 7).with_bulk(1, 10).with_varbind(SYS_DESCR, Value::Null);
+    let message = bulk.encode_message(VERSION_2, b"public");
+    let decoded = SnmpPdu::from_bytes(&message).unwrap();
+    assert_eq!((decoded.error_status, decoded.error_index), (1, 10));
+
+    let enterprise = [1, 3, 6, 1, 4, 1, 8072];
+    let trap = PduBuilder::new(SnmpMessageType::TrapV1, 0)
+        .with_trap_v1(TrapV1 {
+            enterprise: ObjectIdentifier::from_name(&enterprise),
+            agent_addr: [192, 0, 2, 7],
+            generic_trap: 6,
+            specific_trap: 17,
+            timestamp: 256,
+        })
+        .with_varbind(SYS_DESCR, Value::OctetString(b"x"));
+    assert_eq!(trap.encode(), tlv(0xa4, &[
+        oid(&enterprise),
+        tlv(0x40, &[192, 0, 2, 7]),
+        int(6),
+        int(17),
+        tlv(0x43, &[0x01, 0x00]),
+        seq(&[varbind(SYS_DESCR, octets(b"x"))]),
+    ].concat()));
+}
+
+#[test]
+fn reencodes_decoded_pdus_byte_identically() {
+    let values = vec![
+        tlv(0x01, &[0x01]),
+        int(-129),
+        octets(b"\x00\xffbinary"),
+        oid(&[1, 3, 6, 1, 4, 1, 4294967295]),
+        tlv(0x40, &[10, 0, 0, 1]),
+        tlv(0x41, &[0x00, 0xff, 0xff, 0xff, 0xff]),
+        tlv(0x42, &[0x7f]),
+        tlv(0x43, &[0x00, 0x80]),
+        tlv(0x44, &[0x9f, 0x78, 0x04, 0x42, 0xf6, 0x00, 0x00]),
+        tlv(0x46, &[0x00, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff]),
+        tlv(0x46, &[0x7f, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff]),
+        seq(&[int(1), octets(b"nested")]),
+        tlv(0x80, &[]),
+        tlv(0x81, &[]),
+        tlv(0x82, &[]),
+    ];
+    let varbinds: Vec<Vec<u8>> = values.into_iter().map(|value| varbind(SYS_DESCR, value)).collect();
+    for &tag in &[0xa2, 0xa3, 0xa7, 0xa8] {
+        let message = community_message(1, b"public", pdu(tag, -5, 18, 15, &varbinds));
+        let decoded = SnmpPdu::from_bytes(&message).unwrap();
+        assert_eq!(PduBuilder::from_pdu(&decoded).encode_message(VERSION_2, b"public"), message);
+    }
+
+    let trap = tlv(0xa4, &[
+        oid(&[1, 3, 6, 1, 4, 1, 8072]),
+        tlv(0x40, &[192, 0, 2, 7]),
+        int(2),
+        int(0),
+        tlv(0x43, &[0x01, 0x00]),
+        seq(&varbinds),
+    ].concat());
+    let message = community_message(0, b"public", trap);
+    let decoded = SnmpPdu::from_bytes(&message).unwrap();
+    assert_eq!(PduBuilder::from_pdu(&decoded).encode_message(VERSION_1, b"public"), message);
+}
+
+#[test]
+fn counter64_uses_the_full_range() {
+    let message = PduBuilder::new(SnmpMessageType::Response, 1)
+        .with_varbind(SYS_DESCR, Value::Counter64(u64::MAX))
+        .with_varbind(SYS_DESCR, Value::Counter64(1 << 63))
+        .encode_message(VERSION_2, b"public");
+    let owned = SnmpPdu::from_bytes(&message).unwrap().into_owned().unwrap();
+    let values: Vec<OwnedValue> = owned.varbinds.iter().map(|(_, value)| value.clone()).collect();
+    assert_eq!(values, [OwnedValue::Counter64(u64::MAX), OwnedValue::Counter64(1 << 63)]);
+}
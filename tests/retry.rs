@@ -0,0 +1,96 @@
+extern crate snmplib;
+
+mod common;
+
+use std::net::{SocketAddr, UdpSocket};
+use std::sync::{Arc, Mutex};
+use std::thread;
+use std::time::{Duration, Instant};
+
+use snmplib::{RetryPolicy, SnmpError, SyncSession};
+
+use common::*;
+
+const SYS_DESCR: &[u32] = &[1, 3, 6, 1, 2, 1, 1, 1, 0];
+
+/// Stand-in agent that ignores the first `drop` requests and counts every
+/// request it sees.
+fn lossy_agent(drop: usize) -> (SocketAddr, Arc<Mutex<usize>>) {
+    let seen = Arc::new(Mutex::new(0));
+    let counter = Arc::clone(&seen);
+    let addr = spawn_agent(move |req| {
+        let mut seen = counter.lock().unwrap();
+        *seen += 1;
+        if *seen <= drop {
+            return None;
+        }
+        let (_, community, pdu_bytes) = read_community_message(req);
+        let (_, req_id) = read_pdu_header(pdu_bytes);
+        let resp = pdu(0xa2, req_id, 0, 0, &[varbind(SYS_DESCR, octets(b"lossy"))]);
+        Some(community_message(1, community, resp))
+    });
+    (addr, seen)
+}
+
+fn session(addr: SocketAddr, policy: RetryPolicy) -> SyncSession {
+    let mut sess = SyncSession::new(addr, b"public", None, 0).unwrap();
+    sess.set_retry_policy(policy);
+    sess
+}
+
+#[test]
+fn policy_backoff() {
+    let policy = RetryPolicy::new(Duration::from_millis(100)).with_retries(3).with_backoff(2.0);
+    assert_eq!(policy.attempts(), 4);
+    let waits: Vec<_> = (0..4).map(|n| policy.attempt_timeout(n).unwrap().as_millis()).collect();
+    assert_eq!(waits, [100, 200, 400, 800]);
+    assert_eq!(RetryPolicy::default().attempt_timeout(0), None);
+}
+
+#[test]
+fn policy_jitter_stays_in_bounds() {
+    let policy = RetryPolicy::new(Duration::from_millis(1000)).with_jitter(0.25);
+    for _ in 0..100 {
+        let wait = policy.attempt_timeout(0).unwrap();
+        assert!(wait >= Duration::from_millis(750) && wait <= Duration::from_millis(1250));
+    }
+}
+
+#[test]
+fn retransmits_until_answered() {
+    let (addr, seen) = lossy_agent(2);
+    let mut sess = session(addr, RetryPolicy::new(Duration::from_millis(100)).with_retries(2));
+    assert_eq!(sess.get(SYS_DESCR).unwrap().varbinds.count(), 1);
+    assert_eq!(*seen.lock().unwrap(), 3);
+}
+
+#[test]
+fn timeout_after_retries_exhausted() {
+    let (addr, seen) = lossy_agent(usize::MAX);
+    let mut sess = session(addr, RetryPolicy::new(Duration::from_millis(50)).with_retries(2).with_backoff(2.0));
+    let start = Instant::now();
+    assert_eq!(sess.get(SYS_DESCR).unwrap_err(), SnmpError::Timeout);
+    assert!(start.elapsed() >= Duration::from_millis(350));
+    assert_eq!(*seen.lock().unwrap(), 3);
+}
+
+#[test]
+fn stale_responses_are_discarded() {
+    // Answers with a late reply to the previous request before the real one.
+    let socket = UdpSocket::bind("127.0.0.1:0").unwrap();
+    let addr = socket.local_addr().unwrap();
+    thread::spawn(move || {
+        let mut buf = [0u8; 4096];
+        let (len, peer) = socket.recv_from(&mut buf).unwrap();
+        let (_, community, pdu_bytes) = read_community_message(&buf[..len]);
+        let (_, req_id) = read_pdu_header(pdu_bytes);
+        let stale = pdu(0xa2, req_id - 1, 0, 0, &[varbind(SYS_DESCR, octets(b"stale"))]);
+        let fresh = pdu(0xa2, req_id, 0, 0, &[varbind(SYS_DESCR, octets(b"fresh"))]);
+        socket.send_to(&community_message(1, community, stale), peer).unwrap();
+        socket.send_to(&community_message(1, community, fresh), peer).unwrap();
+    });
+    let mut sess = session(addr, RetryPolicy::new(Duration::from_secs(2)));
+    let resp = sess.get(SYS_DESCR).unwrap();
+    let (_, value) = resp.varbinds.clone().next().unwrap();
+    assert_eq!(format!("{:?}", value), "OCTET STRING: fresh");
+}
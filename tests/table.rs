@@ -0,0 +1,124 @@
+extern crate snmplib;
+
+mod common;
+
+use std::net::SocketAddr;
+use std::time::Duration;
+
+use snmplib::{OwnedValue, SnmpError, SyncSession};
+
+use common::*;
+
+const IF_DESCR: &[u32] = &[1, 3, 6, 1, 2, 1, 2, 2, 1, 2];
+const IF_TYPE: &[u32] = &[1, 3, 6, 1, 2, 1, 2, 2, 1, 3];
+const IF_IN_OCTETS: &[u32] = &[1, 3, 6, 1, 2, 1, 2, 2, 1, 10];
+
+fn column(column: &[u32], index: u32) -> Vec<u32> {
+    [column, &[index]].concat()
+}
+
+/// ifTable with interfaces 1, 2 and 5; interface 2 has no ifType and the
+/// counters are only present for interface 1.
+fn if_table() -> Vec<(Vec<u32>, Vec<u8>)> {
+    let mut objects = vec![
+        (column(IF_DESCR, 1), octets(b"lo")),
+        (column(IF_DESCR, 2), octets(b"eth0")),
+        (column(IF_DESCR, 5), octets(b"eth1")),
+        (column(IF_TYPE, 1), int(24)),
+        (column(IF_TYPE, 5), int(6)),
+        (column(IF_IN_OCTETS, 1), tlv(0x41, &[0x10])),
+        (vec![1, 3, 6, 1, 2, 1, 4, 1, 0], int(1)),
+    ];
+    objects.sort();
+    objects
+}
+
+fn session(addr: SocketAddr) -> SyncSession {
+    SyncSession::new(addr, b"public", Some(Duration::from_secs(2)), 0).unwrap()
+}
+
+#[test]
+fn sparse_table() {
+    let mut sess = session(mib_agent(1, b"public", if_table()));
+    let table = sess.table(&[IF_DESCR, IF_TYPE, IF_IN_OCTETS], 10).unwrap();
+    assert_eq!(table.len(), 3);
+    let indices: Vec<&[u32]> = table.rows().map(|(index, _)| index).collect();
+    assert_eq!(indices, [&[1][..], &[2], &[5]]);
+    assert_eq!(table.row(&[1]).unwrap(), &[Some(OwnedValue::OctetString(b"lo".to_vec())),
+                                            Some(OwnedValue::Integer(24)),
+                                            Some(OwnedValue::Counter32(16))]);
+    assert_eq!(table.row(&[2]).unwrap(), &[Some(OwnedValue::OctetString(b"eth0".to_vec())), None, None]);
+    assert_eq!(table.cell(&[5], 1), Some(&OwnedValue::Integer(6)));
+    assert_eq!(table.cell(&[5], 2), None);
+}
+
+#[test]
+fn several_requests_give_the_same_table() {
+    let mut sess = session(mib_agent(1, b"public", if_table()));
+    let whole = sess.table(&[IF_DESCR, IF_TYPE, IF_IN_OCTETS], 10).unwrap();
+    let paged = sess.table(&[IF_DESCR, IF_TYPE, IF_IN_OCTETS], 1).unwrap();
+    assert_eq!(whole, paged);
+}
+
+/// Stand-in agent answering with at most `limit` varbinds, cutting
+/// repetitions short.
+fn truncating_agent(limit: usize) -> SocketAddr {
+    let mut objects = if_table();
+    spawn_agent(move |req| {
+        let (_, community, pdu_bytes) = read_community_message(req);
+        let resp = answer(&mut objects, pdu_bytes);
+        let (_, body, _) = read_tlv(&resp);
+        let (req_id, rest) = read_int(body);
+        let (_, rest) = read_int(rest);
+        let (_, rest) = read_int(rest);
+        let (_, mut list, _) = read_tlv(rest);
+        let mut varbinds = Vec::new();
+        while !list.is_empty() && varbinds.len() < limit {
+            let (_, content, rest) = read_tlv(list);
+            varbinds.push(seq(&[content.to_vec()]));
+            list = rest;
+        }
+        Some(community_message(1, community, pdu(0xa2, req_id as i32, 0, 0, &varbinds)))
+    })
+}
+
+#[test]
+fn truncated_responses() {
+    let expected = session(mib_agent(1, b"public", if_table())).table(&[IF_DESCR, IF_TYPE, IF_IN_OCTETS], 10).unwrap();
+    // Four varbinds cut repetitions short; one leaves columns unanswered.
+    for &limit in &[4, 1] {
+        let mut sess = session(truncating_agent(limit));
+        let table = sess.table(&[IF_DESCR, IF_TYPE, IF_IN_OCTETS], 10).unwrap();
+        assert_eq!(table, expected);
+    }
+    // A response without varbinds moves nothing forward.
+    let mut sess = session(truncating_agent(0));
+    assert_eq!(sess.table(&[IF_DESCR, IF_TYPE, IF_IN_OCTETS], 10), Err(SnmpError::NoProgress));
+
+    // Neither does one after the first, leaving the table partial.
+    let mut objects = if_table();
+    let mut answered = false;
+    let addr = spawn_agent(move |req| {
+        let (_, community, pdu_bytes) = read_community_message(req);
+        if std::mem::replace(&mut answered, true) {
+            let (_, req_id) = read_pdu_header(pdu_bytes);
+            return Some(community_message(1, community, pdu(0xa2, req_id, 0, 0, &[])));
+        }
+        Some(community_message(1, community, answer(&mut objects, pdu_bytes)))
+    });
+    let mut sess = session(addr);
+    assert_eq!(sess.table(&[IF_DESCR, IF_TYPE, IF_IN_OCTETS], 1), Err(SnmpError::NoProgress));
+}
+
+#[test]
+fn empty_table_and_errors() {
+    let mut sess = session(mib_agent(1, b"public", if_table()));
+    assert!(sess.table(&[&[1, 3, 6, 1, 2, 1, 3, 1, 1, 1]], 10).unwrap().is_empty());
+
+    let addr = spawn_agent(|req| {
+        let (_, community, pdu_bytes) = read_community_message(req);
+        let (_, req_id) = read_pdu_header(pdu_bytes);
+        Some(community_message(1, community, pdu(0xa2, req_id, 5, 0, &[])))
+    });
+    assert_eq!(session(addr).table(&[IF_DESCR], 10), Err(SnmpError::ErrorStatus { status: 5, index: 0 }));
+}
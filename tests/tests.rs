@@ -6,7 +6,14 @@ use snmplib::SyncSession;
 #[test]
 fn it_works() {
 
-    let session = SyncSession::new("192.168.88.1", "public".as_bytes(), Some(std::time::Duration::from_secs(2)), 0);
+    let _session = SyncSession::new("192.168.88.1", "public".as_bytes(), Some(std::time::Duration::from_secs(2)), 0);
 
     assert_eq!(2 + 2, 4); // TODO add real tests
 }
+
+#[test]
+fn open_without_addresses() {
+    let no_addrs: &[std::net::SocketAddr] = &[];
+    let err = SyncSession::new(no_addrs, b"public", None, 0).err().unwrap();
+    assert_eq!(err.kind(), std::io::ErrorKind::InvalidInput);
+}
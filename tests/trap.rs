@@ -0,0 +1,327 @@
+extern crate snmplib;
+
+mod common;
+
+use std::net::{SocketAddr, UdpSocket};
+use std::thread;
+use std::time::Duration;
+
+use snmplib::{Oid, OwnedTrapV1, OwnedValue, RetryPolicy, SnmpError, SyncSession, Value};
+use snmplib::snmp::*;
+use snmplib::trap::{self, Acknowledge, Notification, NotificationKind, TrapReceiver, SNMP_TRAP_OID, SYS_UP_TIME};
+
+use common::{community_message, int, read_community_message, read_pdu_header, octets, oid, pdu, seq, tlv, varbind};
+
+const LINK_DOWN: &[u32] = &[1, 3, 6, 1, 6, 3, 1, 1, 5, 3];
+const IF_INDEX_2: &[u32] = &[1, 3, 6, 1, 2, 1, 2, 2, 1, 1, 2];
+const ENTERPRISE: &[u32] = &[1, 3, 6, 1, 4, 1, 8072];
+
+fn receiver() -> (TrapReceiver, SocketAddr) {
+    let receiver = TrapReceiver::bind("127.0.0.1:0").unwrap();
+    receiver.set_read_timeout(Some(Duration::from_secs(5))).unwrap();
+    let addr = receiver.local_addr().unwrap();
+    (receiver, addr)
+}
+
+fn send(to: SocketAddr, message: &[u8]) -> SocketAddr {
+    let socket = sender();
+    socket.send_to(message, to).unwrap();
+    socket.local_addr().unwrap()
+}
+
+fn v2_notification(tag: u8, req_id: i32, community: &[u8]) -> Vec<u8> {
+    community_message(1, community, pdu(tag, req_id, 0, 0, &[
+        varbind(SYS_UP_TIME, tlv(0x43, &[0x30, 0x39])),
+        varbind(SNMP_TRAP_OID, oid(LINK_DOWN)),
+        varbind(IF_INDEX_2, int(2)),
+    ]))
+}
+
+#[test]
+fn receives_v1_trap() {
+    let (mut receiver, addr) = receiver();
+    let trap = tlv(0xa4, &[
+        oid(ENTERPRISE),
+        tlv(0x40, &[192, 0, 2, 7]),
+        int(6),
+        int(17),
+        tlv(0x43, &[0x01, 0x00]),
+        seq(&[varbind(IF_INDEX_2, int(2))]),
+    ].concat());
+    let source = send(addr, &community_message(0, b"public", trap));
+
+    let notification = receiver.recv().unwrap();
+    assert_eq!(notification.source, source);
+    assert_eq!(notification.version, VERSION_1);
+    assert_eq!(notification.community, b"public");
+    assert_eq!(notification.kind, NotificationKind::TrapV1);
+    assert_eq!(notification.uptime, 256);
+    assert_eq!(notification.trap_oid, Oid::from(&[1, 3, 6, 1, 4, 1, 8072, 0, 17][..]));
+    assert_eq!(notification.trap_v1, Some(OwnedTrapV1 {
+        enterprise: Oid::from(ENTERPRISE),
+        agent_addr: [192, 0, 2, 7],
+        generic_trap: 6,
+        specific_trap: 17,
+        timestamp: 256,
+    }));
+    assert_eq!(notification.varbinds, vec![(Oid::from(IF_INDEX_2), OwnedValue::Integer(2))]);
+}
+
+#[test]
+fn receives_v2_trap_and_inform() {
+    let (mut receiver, addr) = receiver();
+    let source = send(addr, &v2_notification(0xa7, 41, b"public"));
+    send(addr, &v2_notification(0xa6, 42, b"public"));
+
+    let mut notifications = receiver.iter();
+    let trap = notifications.next().unwrap().unwrap();
+    assert_eq!(trap.source, source);
+    assert_eq!(trap.version, VERSION_2);
+    assert_eq!(trap.kind, NotificationKind::Trap);
+    assert_eq!(trap.req_id, 41);
+    assert_eq!(trap.uptime, 12345);
+    assert_eq!(trap.trap_oid, Oid::from(LINK_DOWN));
+    assert_eq!(trap.trap_v1, None);
+    assert_eq!(trap.varbinds, vec![(Oid::from(IF_INDEX_2), OwnedValue::Integer(2))]);
+
+    let inform = notifications.next().unwrap().unwrap();
+    assert_eq!(inform.kind, NotificationKind::Inform);
+    assert_eq!(inform.req_id, 42);
+    assert_eq!(inform.trap_oid, trap.trap_oid);
+}
+
+#[test]
+fn filters_communities_and_drops_malformed() {
+    let (mut receiver, addr) = receiver();
+    receiver.set_communities(&[b"traps"]);
+    send(addr, &v2_notification(0xa7, 1, b"public"));
+    send(addr, b"\x30\x03\x02\x01");
+    // A response is not a notification.
+    send(addr, &community_message(1, b"traps", pdu(0xa2, 2, 0, 0, &[])));
+    // Nor is a trap without sysUpTime.0 and snmpTrapOID.0.
+    send(addr, &community_message(1, b"traps", pdu(0xa7, 3, 0, 0, &[varbind(IF_INDEX_2, octets(b"x"))])));
+    send(addr, &v2_notification(0xa7, 4, b"traps"));
+
+    // Ends the run once everything has been read.
+    receiver.set_read_timeout(Some(Duration::from_millis(500))).unwrap();
+    let mut received = Vec::new();
+    let result = receiver.run(|notification: &Notification| {
+        received.push(notification.req_id);
+        true
+    });
+    assert!(result.is_err());
+    assert_eq!(received, vec![4]);
+}
+
+fn sender() -> UdpSocket {
+    let socket = UdpSocket::bind("127.0.0.1:0").unwrap();
+    socket.set_read_timeout(Some(Duration::from_millis(500))).unwrap();
+    socket
+}
+
+fn recv_ack(socket: &UdpSocket) -> Option<Vec<u8>> {
+    let mut buf = [0; 4096];
+    let len = socket.recv(&mut buf).ok()?;
+    Some(buf[..len].to_vec())
+}
+
+#[test]
+fn acknowledges_informs_on_receipt() {
+    let (mut receiver, addr) = receiver();
+    let sender = sender();
+    let inform = v2_notification(0xa6, 42, b"public");
+    sender.send_to(&inform, addr).unwrap();
+    sender.send_to(&v2_notification(0xa7, 43, b"public"), addr).unwrap();
+
+    assert_eq!(receiver.recv().unwrap().kind, NotificationKind::Inform);
+    let ack = recv_ack(&sender).unwrap();
+    let (version, community, pdu_bytes) = read_community_message(&ack);
+    assert_eq!((version, community), (VERSION_2, &b"public"[..]));
+    assert_eq!(read_pdu_header(pdu_bytes), (0xa2, 42));
+    // The Response differs from the InformRequest only in its PDU tag.
+    let mut expected = inform.clone();
+    let tag = expected.len() - pdu_bytes.len();
+    expected[tag] = 0xa2;
+    assert_eq!(ack, expected);
+
+    // Traps are not acknowledged.
+    assert_eq!(receiver.recv().unwrap().kind, NotificationKind::Trap);
+    assert_eq!(recv_ack(&sender), None);
+}
+
+#[test]
+fn acknowledges_informs_after_handler() {
+    let (mut receiver, addr) = receiver();
+    receiver.set_acknowledge(Acknowledge::AfterHandler);
+    let sender = sender();
+    sender.send_to(&v2_notification(0xa6, 1, b"public"), addr).unwrap();
+    sender.send_to(&v2_notification(0xa6, 2, b"public"), addr).unwrap();
+
+    receiver.set_read_timeout(Some(Duration::from_millis(500))).unwrap();
+    let result = receiver.run(|notification| notification.req_id == 2);
+    assert!(result.is_err());
+    let ack = recv_ack(&sender).unwrap();
+    assert_eq!(read_pdu_header(read_community_message(&ack).2), (0xa2, 2));
+    assert_eq!(recv_ack(&sender), None);
+
+    // recv leaves acknowledging to the caller.
+    sender.send_to(&v2_notification(0xa6, 3, b"public"), addr).unwrap();
+    let notification = receiver.recv().unwrap();
+    assert_eq!(recv_ack(&sender), None);
+    receiver.acknowledge(&notification).unwrap();
+    assert_eq!(read_pdu_header(read_community_message(&recv_ack(&sender).unwrap()).2), (0xa2, 3));
+}
+
+#[test]
+fn undeliverable_ack_keeps_notification() {
+    let (mut receiver, addr) = receiver();
+    {
+        let gone = sender();
+        gone.send_to(&v2_notification(0xa6, 7, b"public"), addr).unwrap();
+    }
+    let sender = sender();
+    sender.send_to(&v2_notification(0xa6, 8, b"public"), addr).unwrap();
+
+    let received: Vec<_> = receiver.iter().take(2).map(|notification| notification.unwrap().req_id).collect();
+    assert_eq!(received, vec![7, 8]);
+    assert_eq!(read_pdu_header(read_community_message(&recv_ack(&sender).unwrap()).2), (0xa2, 8));
+}
+
+#[test]
+fn sends_traps() {
+    let (mut receiver, addr) = receiver();
+    let mut sess = SyncSession::new_v1(addr, b"public", None, 0).unwrap();
+    sess.send_trap_v1(ENTERPRISE, [192, 0, 2, 7], 6, 17, 256, &[(IF_INDEX_2, Value::Integer(2))]).unwrap();
+    assert_eq!(sess.send_trap_v2c(0, LINK_DOWN, &[]).unwrap_err(), SnmpError::UnsupportedVersion);
+    assert_eq!(sess.send_inform(0, LINK_DOWN, &[]).unwrap_err(), SnmpError::UnsupportedVersion);
+
+    let trap = receiver.recv().unwrap();
+    assert_eq!(trap.kind, NotificationKind::TrapV1);
+    assert_eq!(trap.trap_v1.unwrap().specific_trap, 17);
+    assert_eq!(trap.varbinds, vec![(Oid::from(IF_INDEX_2), OwnedValue::Integer(2))]);
+
+    let mut sess = SyncSession::new(addr, b"public", None, 7).unwrap();
+    sess.send_trap_v2c(12345, LINK_DOWN, &[(IF_INDEX_2, Value::Integer(2))]).unwrap();
+    assert_eq!(sess.send_trap_v1(ENTERPRISE, [0; 4], 0, 0, 0, &[]).unwrap_err(), SnmpError::UnsupportedVersion);
+
+    let trap = receiver.recv().unwrap();
+    assert_eq!(trap.kind, NotificationKind::Trap);
+    assert_eq!(trap.req_id, 7);
+    assert_eq!(trap.uptime, 12345);
+    assert_eq!(trap.trap_oid, Oid::from(LINK_DOWN));
+    assert_eq!(trap.varbinds, vec![(Oid::from(IF_INDEX_2), OwnedValue::Integer(2))]);
+}
+
+#[test]
+fn sends_informs_until_acknowledged() {
+    let (mut receiver, addr) = receiver();
+    receiver.set_acknowledge(Acknowledge::AfterHandler);
+    receiver.set_read_timeout(Some(Duration::from_secs(1))).unwrap();
+    let handler = thread::spawn(move || {
+        let mut attempts = 0;
+        let _ = receiver.run(|notification| {
+            attempts += 1;
+            // Only the retransmission is acknowledged.
+            notification.req_id == 5 && attempts > 1
+        });
+        attempts
+    });
+
+    let mut sess = SyncSession::new(addr, b"public", None, 5).unwrap();
+    sess.set_retry_policy(RetryPolicy::new(Duration::from_millis(200)).with_retries(3));
+    let resp = sess.send_inform(12345, LINK_DOWN, &[(IF_INDEX_2, Value::Integer(2))]).unwrap();
+    assert_eq!(resp.req_id, 5);
+    let names: Vec<String> = resp.varbinds.clone().map(|(name, _)| name.to_string()).collect();
+    assert_eq!(names, ["1.3.6.1.2.1.1.3.0", "1.3.6.1.6.3.1.1.4.1.0", "1.3.6.1.2.1.2.2.1.1.2"]);
+    assert_eq!(handler.join().unwrap(), 2);
+}
+
+fn v1_trap(enterprise: &[u32], generic_trap: i64, specific_trap: i64) -> OwnedTrapV1 {
+    OwnedTrapV1 {
+        enterprise: Oid::from(enterprise),
+        agent_addr: [192, 0, 2, 7],
+        generic_trap,
+        specific_trap,
+        timestamp: 256,
+    }
+}
+
+#[test]
+fn translates_v1_traps_to_v2() {
+    let varbinds = vec![(Oid::from(IF_INDEX_2), OwnedValue::Integer(2))];
+    let link_down = v1_trap(ENTERPRISE, 2, 0);
+    assert_eq!(trap::v1_trap_oid(&link_down), Some(Oid::from(LINK_DOWN)));
+    assert_eq!(trap::v1_to_v2(&link_down, &varbinds).unwrap(), vec![
+        (Oid::from(SYS_UP_TIME), OwnedValue::Timeticks(256)),
+        (Oid::from(SNMP_TRAP_OID), OwnedValue::ObjectIdentifier(Oid::from(LINK_DOWN))),
+        (Oid::from(IF_INDEX_2), OwnedValue::Integer(2)),
+        (Oid::from(trap::SNMP_TRAP_ADDRESS), OwnedValue::IpAddress([192, 0, 2, 7])),
+        (Oid::from(trap::SNMP_TRAP_ENTERPRISE), OwnedValue::ObjectIdentifier(Oid::from(ENTERPRISE))),
+    ]);
+
+    let specific = v1_trap(ENTERPRISE, 6, 17);
+    assert_eq!(trap::v1_trap_oid(&specific).unwrap().to_string(), "1.3.6.1.4.1.8072.0.17");
+    assert_eq!(trap::v1_trap_oid(&v1_trap(ENTERPRISE, 7, 0)), None);
+    assert_eq!(trap::v1_trap_oid(&v1_trap(ENTERPRISE, 6, -1)), None);
+}
+
+#[test]
+fn translates_v2_traps_to_v1() {
+    let header = |trap_oid: &[u32]| vec![
+        (Oid::from(SYS_UP_TIME), OwnedValue::Timeticks(256)),
+        (Oid::from(SNMP_TRAP_OID), OwnedValue::ObjectIdentifier(Oid::from(trap_oid))),
+    ];
+
+    // Generic traps without snmpTrapEnterprise.0 are attributed to snmpTraps.
+    let mut varbinds = header(LINK_DOWN);
+    varbinds.push((Oid::from(IF_INDEX_2), OwnedValue::Integer(2)));
+    varbinds.push((Oid::from(IF_INDEX_2), OwnedValue::Counter64(1)));
+    let (trap, v1) = trap::v2_to_v1(&varbinds).unwrap();
+    assert_eq!(trap, OwnedTrapV1 {
+        enterprise: Oid::from(trap::SNMP_TRAPS),
+        agent_addr: [0; 4],
+        generic_trap: 2,
+        specific_trap: 0,
+        timestamp: 256,
+    });
+    assert_eq!(v1, vec![(Oid::from(IF_INDEX_2), OwnedValue::Integer(2))]);
+
+    // Without the zero the enterprise is the notification's parent.
+    let (trap, v1) = trap::v2_to_v1(&header(&[1, 3, 6, 1, 4, 1, 8072, 4, 2])).unwrap();
+    assert_eq!((trap.enterprise.to_string(), trap.generic_trap, trap.specific_trap),
+               ("1.3.6.1.4.1.8072.4".to_string(), 6, 2));
+    assert!(v1.is_empty());
+
+    assert_eq!(trap::v2_to_v1(&varbinds[1..]), None);
+
+    // v1 -> v2 -> v1 is lossless.
+    let varbinds = vec![(Oid::from(IF_INDEX_2), OwnedValue::Integer(2))];
+    for original in &[v1_trap(ENTERPRISE, 3, 0), v1_trap(ENTERPRISE, 6, 17)] {
+        let v2 = trap::v1_to_v2(original, &varbinds).unwrap();
+        assert_eq!(trap::v2_to_v1(&v2).unwrap(), (original.clone(), varbinds.clone()));
+    }
+}
+
+#[test]
+fn translates_decoded_pdus() {
+    let trap = tlv(0xa4, &[
+        oid(ENTERPRISE),
+        tlv(0x40, &[192, 0, 2, 7]),
+        int(6),
+        int(17),
+        tlv(0x43, &[0x01, 0x00]),
+        seq(&[varbind(IF_INDEX_2, int(2))]),
+    ].concat());
+    let message = community_message(0, b"public", trap);
+    let v1 = SnmpPdu::from_bytes(&message).unwrap().into_owned().unwrap();
+    assert_eq!(v1.to_trap_v1(), None);
+
+    let v2 = v1.to_trap_v2().unwrap();
+    assert_eq!(v2.version(), VERSION_2);
+    assert_eq!(v2.message_type, SnmpMessageType::Trap);
+    assert_eq!(v2.community, b"public");
+    assert_eq!(v2.varbinds[1].1, OwnedValue::ObjectIdentifier(Oid::from(&[1, 3, 6, 1, 4, 1, 8072, 0, 17][..])));
+    assert_eq!(v2.to_trap_v2(), None);
+
+    assert_eq!(v2.to_trap_v1().unwrap(), v1);
+}
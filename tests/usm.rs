@@ -0,0 +1,354 @@
+extern crate snmplib;
+
+mod common;
+
+use std::net::SocketAddr;
+use std::time::{Duration, Instant};
+
+use snmplib::{SnmpError, SyncSession};
+
+type SnmpResult<T> = Result<T, SnmpError>;
+use snmplib::v3::{self, AuthProtocol, PrivProtocol, Security};
+
+use common::*;
+
+const SYS_DESCR: &[u32] = &[1, 3, 6, 1, 2, 1, 1, 1, 0];
+
+// RFC 3414 A.3
+const ENGINE_ID: &[u8] = &[0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 2];
+
+fn find(haystack: &[u8], needle: &[u8]) -> usize {
+    haystack.windows(needle.len()).position(|w| w == needle).unwrap()
+}
+
+/// Builds an SNMPv3 message from `engine` = (id, boots, time), signed when
+/// `auth` is given and encrypted when `privacy` is given.
+fn v3_message(auth: Option<(AuthProtocol, &[u8])>, privacy: Option<(PrivProtocol, &[u8])>,
+              engine: (&[u8], u32, u32), user: &[u8], msg_id: i64, pdu: Vec<u8>) -> Vec<u8> {
+    let (engine_id, boots, time) = engine;
+    let placeholder = vec![0u8; auth.map_or(0, |(protocol, _)| protocol.mac_len())];
+    let scoped = seq(&[octets(engine_id), octets(b""), pdu]);
+    let mut flags = if auth.is_some() { v3::FLAG_AUTH } else { 0 };
+    let (data, salt) = match privacy {
+        Some((priv_protocol, priv_key)) => {
+            let salt = [0, 0, 0, 1, 0xca, 0xfe, 0xba, 0xbe];
+            let ciphertext = priv_protocol.encrypt(priv_key, boots, time, &salt, &scoped);
+            flags |= v3::FLAG_PRIV;
+            (octets(&ciphertext), salt.to_vec())
+        }
+        None => (scoped, Vec::new()),
+    };
+    let params = seq(&[octets(engine_id), int(boots.into()), int(time.into()), octets(user),
+                       octets(&placeholder), octets(&salt)]);
+    let mut msg = seq(&[
+        int(3),
+        seq(&[int(msg_id), int(4096), octets(&[flags]), int(3)]),
+        octets(&params),
+        data,
+    ]);
+    if let Some((protocol, key)) = auth {
+        let pos = find(&msg, &[octets(user), octets(&placeholder)].concat()) + octets(user).len() + 2;
+        let mac = protocol.mac(key, &msg);
+        msg[pos..pos + mac.len()].copy_from_slice(&mac);
+    }
+    msg
+}
+
+/// Builds an authenticated Response signed with `key`, encrypted when
+/// `privacy` is given.
+fn signed_response(protocol: AuthProtocol, key: &[u8], privacy: Option<(PrivProtocol, &[u8])>,
+                   user: &[u8], msg_id: i64, req_id: i32, varbinds: &[Vec<u8>]) -> Vec<u8> {
+    v3_message(Some((protocol, key)), privacy, (ENGINE_ID, 1, 100), user, msg_id,
+               pdu(0xa2, req_id, 0, 0, varbinds))
+}
+
+/// Request-id of the PDU inside a plaintext ScopedPDU.
+fn scoped_req_id(scoped_pdu: &[u8]) -> i32 {
+    let (_, scoped, _) = read_tlv(scoped_pdu);
+    let (_, _context_engine_id, rest) = read_tlv(scoped);
+    let (_, _context_name, pdu) = read_tlv(rest);
+    read_pdu_header(pdu).1
+}
+
+/// Stand-in authoritative agent answering every authenticated get with
+/// sysDescr.0 = "stand-in". Requests failing authentication are dropped.
+fn usm_agent(protocol: AuthProtocol, password: &'static [u8]) -> SocketAddr {
+    let key = protocol.localize_key(&protocol.password_to_key(password), ENGINE_ID);
+    spawn_agent(move |req| {
+        let msg = v3::Message::from_bytes(req).unwrap();
+        msg.verify(protocol, &key).ok()?;
+        assert_eq!(msg.engine_id, ENGINE_ID);
+        assert!(msg.is_reportable());
+        assert!(!msg.is_encrypted());
+        let req_id = scoped_req_id(msg.scoped_pdu);
+        Some(signed_response(protocol, &key, None, msg.username, msg.msg_id.into(), req_id,
+                             &[varbind(SYS_DESCR, octets(b"stand-in"))]))
+    })
+}
+
+/// Like `usm_agent`, but requires and answers with encrypted ScopedPDUs.
+fn usm_priv_agent(protocol: AuthProtocol, priv_protocol: PrivProtocol, password: &'static [u8]) -> SocketAddr {
+    let key = protocol.localize_key(&protocol.password_to_key(password), ENGINE_ID);
+    let priv_key = priv_protocol.localize_key(protocol, password, ENGINE_ID);
+    spawn_agent(move |req| {
+        let msg = v3::Message::from_bytes(req).unwrap();
+        msg.verify(protocol, &key).ok()?;
+        assert!(msg.is_encrypted());
+        assert_eq!(msg.priv_params.len(), 8);
+        let plaintext = msg.decrypt(priv_protocol, &priv_key).unwrap();
+        let req_id = scoped_req_id(&plaintext);
+        Some(signed_response(protocol, &key, Some((priv_protocol, &priv_key)), msg.username,
+                             msg.msg_id.into(), req_id, &[varbind(SYS_DESCR, octets(b"encrypted stand-in"))]))
+    })
+}
+
+fn session(addr: SocketAddr, security: Security) -> SyncSession {
+    SyncSession::new_v3(addr, security, Some(Duration::from_secs(1)), 1000).unwrap()
+}
+
+#[test]
+fn md5_key_localization() {
+    let ku = AuthProtocol::Md5.password_to_key(b"maplesyrup");
+    assert_eq!(ku, [0x9f, 0xaf, 0x32, 0x83, 0x88, 0x4e, 0x92, 0x83,
+                    0x4e, 0xbc, 0x98, 0x47, 0xd8, 0xed, 0xd9, 0x63]);
+    let kul = AuthProtocol::Md5.localize_key(&ku, ENGINE_ID);
+    assert_eq!(kul, [0x52, 0x6f, 0x5e, 0xed, 0x9f, 0xcc, 0xe2, 0x6f,
+                     0x89, 0x64, 0xc2, 0x93, 0x07, 0x87, 0xd8, 0x2b]);
+}
+
+#[test]
+fn sha1_key_localization() {
+    let ku = AuthProtocol::Sha1.password_to_key(b"maplesyrup");
+    assert_eq!(ku, [0x9f, 0xb5, 0xcc, 0x03, 0x81, 0x49, 0x7b, 0x37, 0x93, 0x52,
+                    0x89, 0x39, 0xff, 0x78, 0x8d, 0x5d, 0x79, 0x14, 0x52, 0x11]);
+    let kul = AuthProtocol::Sha1.localize_key(&ku, ENGINE_ID);
+    assert_eq!(kul, [0x66, 0x95, 0xfe, 0xbc, 0x92, 0x88, 0xe3, 0x62, 0x82, 0x23,
+                     0x5f, 0xc7, 0x15, 0x1f, 0x12, 0x84, 0x97, 0xb3, 0x8f, 0x3f]);
+}
+
+#[test]
+fn mac_lengths() {
+    use AuthProtocol::*;
+    let lens: Vec<usize> = [Md5, Sha1, Sha224, Sha256, Sha384, Sha512].iter()
+        .map(|p| p.mac(b"key", b"msg").len())
+        .collect();
+    assert_eq!(lens, [12, 12, 16, 24, 32, 48]);
+}
+
+#[test]
+fn authenticated_get() {
+    use AuthProtocol::*;
+    for &protocol in &[Md5, Sha1, Sha224, Sha256, Sha384, Sha512] {
+        let addr = usm_agent(protocol, b"maplesyrup");
+        let security = Security::new(b"operator")
+            .with_auth(protocol, b"maplesyrup")
+            .with_engine(ENGINE_ID, 1, 100);
+        let mut sess = session(addr, security);
+        let resp = sess.get(SYS_DESCR).unwrap();
+        let (name, value) = resp.varbinds.clone().next().unwrap();
+        assert_eq!(name, SYS_DESCR);
+        assert_eq!(format!("{:?}", value), "OCTET STRING: stand-in");
+    }
+}
+
+#[test]
+fn wrong_password_is_not_answered() {
+    let addr = usm_agent(AuthProtocol::Sha1, b"maplesyrup");
+    let security = Security::new(b"operator")
+        .with_auth(AuthProtocol::Sha1, b"pancakes")
+        .with_engine(ENGINE_ID, 1, 100);
+    let mut sess = session(addr, security);
+    assert_eq!(sess.get(SYS_DESCR).unwrap_err(), SnmpError::Timeout);
+}
+
+#[test]
+fn forged_response_is_rejected() {
+    let protocol = AuthProtocol::Md5;
+    let agent_key = protocol.localize_key(&protocol.password_to_key(b"other password"), ENGINE_ID);
+    let addr = spawn_agent(move |req| {
+        let msg = v3::Message::from_bytes(req).unwrap();
+        let req_id = scoped_req_id(msg.scoped_pdu);
+        Some(signed_response(protocol, &agent_key, None, msg.username, msg.msg_id.into(), req_id, &[]))
+    });
+    let security = Security::new(b"operator")
+        .with_auth(protocol, b"maplesyrup")
+        .with_engine(ENGINE_ID, 1, 100);
+    let mut sess = session(addr, security);
+    assert_eq!(sess.get(SYS_DESCR).unwrap_err(), SnmpError::AuthFailure);
+}
+
+#[test]
+fn privacy_round_trip() {
+    use PrivProtocol::*;
+    let plaintext = b"0123456789abcdefghijklmnopqrstuvwxyz";
+    let salt = [1, 2, 3, 4, 5, 6, 7, 8];
+    for &protocol in &[Des, Aes128, Aes192, Aes256, Aes192Reeder, Aes256Reeder] {
+        let key = protocol.localize_key(AuthProtocol::Sha1, b"maplesyrup", ENGINE_ID);
+        assert_eq!(key.len(), protocol.key_len());
+        let ciphertext = protocol.encrypt(&key, 7, 1234, &salt, plaintext);
+        assert_ne!(&ciphertext[..plaintext.len()], &plaintext[..]);
+        let decrypted = protocol.decrypt(&key, 7, 1234, &salt, &ciphertext).unwrap();
+        assert_eq!(&decrypted[..plaintext.len()], &plaintext[..]);
+    }
+}
+
+#[test]
+fn des_pads_to_block_size() {
+    let key = PrivProtocol::Des.localize_key(AuthProtocol::Md5, b"maplesyrup", ENGINE_ID);
+    let ciphertext = PrivProtocol::Des.encrypt(&key, 0, 0, &[0; 8], b"odd length");
+    assert_eq!(ciphertext.len(), 16);
+    assert_eq!(PrivProtocol::Des.decrypt(&key, 0, 0, &[0; 8], &ciphertext[..15]),
+               Err(SnmpError::DecryptionError));
+}
+
+#[test]
+fn aes_key_extension() {
+    // A SHA-1 localized key (20 octets) is too short for AES-192/256.
+    let kul = AuthProtocol::Sha1.localize_key(&AuthProtocol::Sha1.password_to_key(b"maplesyrup"), ENGINE_ID);
+    let blumenthal = PrivProtocol::Aes256.localize_key(AuthProtocol::Sha1, b"maplesyrup", ENGINE_ID);
+    let reeder = PrivProtocol::Aes256Reeder.localize_key(AuthProtocol::Sha1, b"maplesyrup", ENGINE_ID);
+    assert_eq!(&blumenthal[..20], &kul[..]);
+    assert_eq!(&reeder[..20], &kul[..]);
+    assert_ne!(blumenthal[20..], reeder[20..]);
+    let aes192 = PrivProtocol::Aes192.localize_key(AuthProtocol::Sha1, b"maplesyrup", ENGINE_ID);
+    assert_eq!(&aes192[..], &blumenthal[..24]);
+}
+
+#[test]
+fn encrypted_get() {
+    use PrivProtocol::*;
+    for &(auth, privacy) in &[(AuthProtocol::Md5, Des),
+                              (AuthProtocol::Sha1, Aes128),
+                              (AuthProtocol::Sha1, Aes192),
+                              (AuthProtocol::Sha1, Aes256Reeder),
+                              (AuthProtocol::Sha256, Aes256)] {
+        let addr = usm_priv_agent(auth, privacy, b"maplesyrup");
+        let security = Security::new(b"operator")
+            .with_auth(auth, b"maplesyrup")
+            .with_priv(privacy, b"maplesyrup")
+            .with_engine(ENGINE_ID, 1, 100);
+        let mut sess = session(addr, security);
+        let resp = sess.get(SYS_DESCR).unwrap();
+        let (_, value) = resp.varbinds.clone().next().unwrap();
+        assert_eq!(format!("{:?}", value), "OCTET STRING: encrypted stand-in");
+    }
+}
+
+#[test]
+fn encrypted_gets_across_engine_time_ticks() {
+    // The agent decrypts with the msgAuthoritativeEngineTime in each
+    // header, so the IV must come from that same reading of the clock.
+    let addr = usm_priv_agent(AuthProtocol::Sha1, PrivProtocol::Aes128, b"maplesyrup");
+    let security = Security::new(b"operator")
+        .with_auth(AuthProtocol::Sha1, b"maplesyrup")
+        .with_priv(PrivProtocol::Aes128, b"maplesyrup")
+        .with_engine(ENGINE_ID, 1, 100);
+    let mut sess = session(addr, security);
+    let start = Instant::now();
+    while start.elapsed() < Duration::from_millis(1200) {
+        sess.get(SYS_DESCR).unwrap();
+    }
+}
+
+#[test]
+fn unencrypted_response_to_encrypted_request_is_rejected() {
+    let (auth, privacy) = (AuthProtocol::Sha1, PrivProtocol::Aes128);
+    let key = auth.localize_key(&auth.password_to_key(b"maplesyrup"), ENGINE_ID);
+    let priv_key = privacy.localize_key(auth, b"maplesyrup", ENGINE_ID);
+    // Answers authPriv requests with authNoPriv responses.
+    let addr = spawn_agent(move |req| {
+        let msg = v3::Message::from_bytes(req).unwrap();
+        let plaintext = msg.decrypt(privacy, &priv_key).unwrap();
+        let req_id = scoped_req_id(&plaintext);
+        Some(signed_response(auth, &key, None, msg.username, msg.msg_id.into(), req_id,
+                             &[varbind(SYS_DESCR, octets(b"in the clear"))]))
+    });
+    let security = Security::new(b"operator")
+        .with_auth(auth, b"maplesyrup")
+        .with_priv(privacy, b"maplesyrup")
+        .with_engine(ENGINE_ID, 1, 100);
+    let mut sess = session(addr, security);
+    assert_eq!(sess.get(SYS_DESCR).unwrap_err(), SnmpError::UnsupportedSecurityLevel);
+}
+
+/// Stand-in agent with engine state that answers discovery probes and
+/// emits USM reports the way an authoritative engine would.
+fn discovering_agent(engine_id: &'static [u8], boots: u32, time: u32) -> SocketAddr {
+    let protocol = AuthProtocol::Sha1;
+    let key = protocol.localize_key(&protocol.password_to_key(b"maplesyrup"), engine_id);
+    spawn_agent(move |req| {
+        let msg = v3::Message::from_bytes(req).unwrap();
+        let engine = (engine_id, boots, time);
+        let req_id = if msg.is_encrypted() { 0 } else { scoped_req_id(msg.scoped_pdu) };
+        let report = |name: &[u32]| pdu(0xa8, req_id, 0, 0, &[varbind(name, tlv(0x41, &[1]))]);
+        let msg_id = msg.msg_id.into();
+        if msg.engine_id != engine_id {
+            return Some(v3_message(None, None, engine, msg.username, msg_id,
+                                   report(v3::USM_STATS_UNKNOWN_ENGINE_IDS)));
+        }
+        if msg.username != b"operator" {
+            return Some(v3_message(None, None, engine, msg.username, msg_id,
+                                   report(v3::USM_STATS_UNKNOWN_USER_NAMES)));
+        }
+        msg.verify(protocol, &key).ok()?;
+        if msg.engine_boots != boots || msg.engine_time + 150 < time {
+            return Some(v3_message(Some((protocol, &key)), None, engine, msg.username, msg_id,
+                                   report(v3::USM_STATS_NOT_IN_TIME_WINDOWS)));
+        }
+        Some(v3_message(Some((protocol, &key)), None, engine, msg.username, msg_id,
+                        pdu(0xa2, req_id, 0, 0, &[varbind(SYS_DESCR, octets(b"discovered"))])))
+    })
+}
+
+const REMOTE_ENGINE_ID: &[u8] = &[0x80, 0x00, 0x1f, 0x88, 0x80, 0x12, 0x34, 0x56, 0x78];
+
+fn sysdescr(sess: &mut SyncSession) -> SnmpResult<String> {
+    let resp = sess.get(SYS_DESCR)?;
+    let (_, value) = resp.varbinds.clone().next().unwrap();
+    Ok(format!("{:?}", value))
+}
+
+#[test]
+fn engine_discovery() {
+    let addr = discovering_agent(REMOTE_ENGINE_ID, 5, 5000);
+    let security = Security::new(b"operator").with_auth(AuthProtocol::Sha1, b"maplesyrup");
+    let mut sess = session(addr, security);
+    assert!(sess.engine().is_none());
+    assert_eq!(sysdescr(&mut sess).unwrap(), "OCTET STRING: discovered");
+    let engine = sess.engine().unwrap();
+    assert_eq!(engine.id, REMOTE_ENGINE_ID);
+    assert_eq!(engine.boots, 5);
+    assert!(engine.current_time() >= 5000);
+    // The cached engine is reused.
+    assert_eq!(sysdescr(&mut sess).unwrap(), "OCTET STRING: discovered");
+}
+
+#[test]
+fn resync_on_not_in_time_window() {
+    let addr = discovering_agent(REMOTE_ENGINE_ID, 7, 90000);
+    let security = Security::new(b"operator")
+        .with_auth(AuthProtocol::Sha1, b"maplesyrup")
+        .with_engine(REMOTE_ENGINE_ID, 6, 10);
+    let mut sess = session(addr, security);
+    assert_eq!(sysdescr(&mut sess).unwrap(), "OCTET STRING: discovered");
+    assert_eq!(sess.engine().unwrap().boots, 7);
+}
+
+#[test]
+fn rediscover_on_unknown_engine_id() {
+    let addr = discovering_agent(REMOTE_ENGINE_ID, 1, 100);
+    let security = Security::new(b"operator")
+        .with_auth(AuthProtocol::Sha1, b"maplesyrup")
+        .with_engine(ENGINE_ID, 1, 100);
+    let mut sess = session(addr, security);
+    assert_eq!(sysdescr(&mut sess).unwrap(), "OCTET STRING: discovered");
+    assert_eq!(sess.engine().unwrap().id, REMOTE_ENGINE_ID);
+}
+
+#[test]
+fn unknown_user_report() {
+    let addr = discovering_agent(REMOTE_ENGINE_ID, 1, 100);
+    let security = Security::new(b"intruder").with_auth(AuthProtocol::Sha1, b"maplesyrup");
+    let mut sess = session(addr, security);
+    assert_eq!(sysdescr(&mut sess).unwrap_err(), SnmpError::UnknownUserName);
+}
@@ -0,0 +1,103 @@
+extern crate snmplib;
+
+mod common;
+
+use std::net::{SocketAddr, UdpSocket};
+use std::time::Duration;
+
+use snmplib::{PduBuilder, SnmpError, SnmpMessageType, SnmpPdu, SyncSession, Value};
+
+use common::*;
+
+const SYS_DESCR: &[u32] = &[1, 3, 6, 1, 2, 1, 1, 1, 0];
+const SYS_FOO: &[u32] = &[1, 3, 6, 1, 2, 1, 1, 99, 0];
+
+/// Stand-in SNMPv1 agent that only knows sysDescr.0, whose value echoes
+/// the version of the request.
+fn v1_agent() -> SocketAddr {
+    spawn_agent(|req| {
+        let (version, community, pdu_bytes) = read_community_message(req);
+        assert_eq!(community, b"public");
+        let (tag, req_id) = read_pdu_header(pdu_bytes);
+        assert_eq!(tag, 0xa0);
+        let (_, body, _) = read_tlv(pdu_bytes);
+        let (_, rest) = read_int(body);
+        let (_, rest) = read_int(rest);
+        let (_, rest) = read_int(rest);
+        let (_, varbinds, _) = read_tlv(rest);
+        let (_, first, _) = read_tlv(varbinds);
+        let resp = if first.starts_with(&oid(SYS_DESCR)) {
+            let descr = format!("request version {}", version);
+            pdu(0xa2, req_id, 0, 0, &[varbind(SYS_DESCR, octets(descr.as_bytes()))])
+        } else {
+            // noSuchName on the first variable binding
+            pdu(0xa2, req_id, 2, 1, &[varbind(SYS_FOO, null())])
+        };
+        Some(community_message(0, community, resp))
+    })
+}
+
+fn session(addr: SocketAddr) -> SyncSession {
+    SyncSession::new_v1(addr, b"public", Some(Duration::from_secs(1)), 7).unwrap()
+}
+
+#[test]
+fn v1_get() {
+    let mut sess = session(v1_agent());
+    let resp = sess.get(SYS_DESCR).unwrap();
+    assert_eq!(resp.version(), 0);
+    assert_eq!(resp.error_status, 0);
+    let (name, value) = resp.varbinds.clone().next().unwrap();
+    assert_eq!(name, SYS_DESCR);
+    assert_eq!(format!("{:?}", value), "OCTET STRING: request version 0");
+}
+
+#[test]
+fn v1_no_such_name() {
+    let mut sess = session(v1_agent());
+    let resp = sess.get(SYS_FOO).unwrap();
+    assert_eq!(resp.error_status, 2);
+    assert_eq!(resp.error_index, 1);
+}
+
+#[test]
+fn v1_has_no_getbulk() {
+    let mut sess = session(v1_agent());
+    assert_eq!(sess.getbulk(&[SYS_DESCR], 0, 10).unwrap_err(), SnmpError::UnsupportedVersion);
+}
+
+#[test]
+fn v2c_session_rejects_v1_response() {
+    let addr = v1_agent();
+    let mut sess = SyncSession::new(addr, b"public", Some(Duration::from_secs(1)), 7).unwrap();
+    assert_eq!(sess.get(SYS_DESCR).unwrap_err(), SnmpError::UnsupportedVersion);
+}
+
+#[test]
+fn v1_trap_round_trip() {
+    let receiver = UdpSocket::bind("127.0.0.1:0").unwrap();
+    receiver.set_read_timeout(Some(Duration::from_secs(2))).unwrap();
+    let enterprise: &[u32] = &[1, 3, 6, 1, 4, 1, 8072, 4];
+    let if_index: &[u32] = &[1, 3, 6, 1, 2, 1, 2, 2, 1, 1, 2];
+    let mut sess = session(receiver.local_addr().unwrap());
+    sess.send_trap_v1(enterprise, [192, 0, 2, 1], 6, 17, 4200,
+                      &[(if_index, Value::Integer(2)), (SYS_DESCR, Value::OctetString(b"router"))]).unwrap();
+
+    let mut buf = [0; 4096];
+    let len = receiver.recv(&mut buf).unwrap();
+    let msg = SnmpPdu::from_bytes(&buf[..len]).unwrap();
+    assert_eq!((msg.version(), msg.community), (0, &b"public"[..]));
+    assert_eq!(msg.message_type, SnmpMessageType::TrapV1);
+    let trap = msg.trap_v1.as_ref().unwrap();
+    assert_eq!(trap.enterprise, enterprise);
+    assert_eq!(trap.agent_addr, [192, 0, 2, 1]);
+    assert_eq!((trap.generic_trap, trap.specific_trap, trap.timestamp), (6, 17, 4200));
+    let varbinds: Vec<_> = msg.varbinds.clone().map(|(name, value)| (name.to_string(), format!("{:?}", value))).collect();
+    assert_eq!(varbinds, [
+        ("1.3.6.1.2.1.2.2.1.1.2".to_string(), "INTEGER: 2".to_string()),
+        ("1.3.6.1.2.1.1.1.0".to_string(), "OCTET STRING: router".to_string()),
+    ]);
+
+    // Re-encoding the decoded PDU gives back the same message.
+    assert_eq!(PduBuilder::from_pdu(&msg).encode_message(0, b"public"), &buf[..len]);
+}
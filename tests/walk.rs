@@ -0,0 +1,105 @@
+extern crate snmplib;
+
+mod common;
+
+use std::net::SocketAddr;
+use std::time::Duration;
+
+use snmplib::{Oid, OwnedValue, SnmpError, SyncSession};
+
+use common::*;
+
+const SYSTEM: &[u32] = &[1, 3, 6, 1, 2, 1, 1];
+
+fn objects() -> Vec<(Vec<u32>, Vec<u8>)> {
+    vec![
+        (vec![1, 3, 6, 1, 2, 1, 1, 1, 0], octets(b"descr")),
+        (vec![1, 3, 6, 1, 2, 1, 1, 3, 0], tlv(0x43, &[0x01, 0x00])),
+        (vec![1, 3, 6, 1, 2, 1, 1, 4, 0], octets(b"contact")),
+        (vec![1, 3, 6, 1, 2, 1, 1, 5, 0], octets(b"name")),
+        (vec![1, 3, 6, 1, 2, 1, 1, 6, 0], octets(b"location")),
+        (vec![1, 3, 6, 1, 2, 1, 2, 1, 0], int(2)),
+    ]
+}
+
+fn session(addr: SocketAddr) -> SyncSession {
+    SyncSession::new(addr, b"public", Some(Duration::from_secs(2)), 0).unwrap()
+}
+
+fn names(items: Vec<(Oid, OwnedValue)>) -> Vec<u32> {
+    items.into_iter().map(|(name, _)| name[7]).collect()
+}
+
+#[test]
+fn walk_stops_at_subtree_end() {
+    let mut sess = session(mib_agent(1, b"public", objects()));
+    let items: Vec<_> = sess.walk(SYSTEM).collect::<Result<_, _>>().unwrap();
+    assert_eq!(names(items.clone()), [1, 3, 4, 5, 6]);
+    assert_eq!(items[1].1, OwnedValue::Timeticks(256));
+    assert_eq!(format!("{:?}", items[0].1), "OCTET STRING: descr");
+}
+
+#[test]
+fn walk_stops_at_end_of_mib_view() {
+    let mut sess = session(mib_agent(1, b"public", objects()));
+    let items: Vec<_> = sess.walk(&[1, 3, 6, 1, 2, 1, 2]).collect::<Result<_, _>>().unwrap();
+    assert_eq!(items, [("1.3.6.1.2.1.2.1.0".parse().unwrap(), OwnedValue::Integer(2))]);
+}
+
+#[test]
+fn bulkwalk_spans_several_requests() {
+    let mut sess = session(mib_agent(1, b"public", objects()));
+    let items: Vec<_> = sess.bulkwalk(SYSTEM, 2).collect::<Result<_, _>>().unwrap();
+    assert_eq!(names(items), [1, 3, 4, 5, 6]);
+    let items: Vec<_> = sess.bulkwalk(&[1, 3, 6, 1, 2, 1, 2], 10).collect::<Result<_, _>>().unwrap();
+    assert_eq!(items.len(), 1);
+}
+
+#[test]
+fn v1_walk_ends_on_no_such_name() {
+    let objects = objects();
+    let addr = spawn_agent(move |req| {
+        let (_, community, pdu_bytes) = read_community_message(req);
+        let (_, req_id, _, _, names) = read_request_pdu(pdu_bytes);
+        let resp = match objects.iter().find(|(n, _)| n > &names[0].0) {
+            Some((n, v)) => pdu(0xa2, req_id, 0, 0, &[varbind(n, v.clone())]),
+            None => pdu(0xa2, req_id, 2, 1, &[varbind(&names[0].0, null())]),
+        };
+        Some(community_message(0, community, resp))
+    });
+    let mut sess = SyncSession::new_v1(addr, b"public", Some(Duration::from_secs(2)), 0).unwrap();
+    let items: Vec<_> = sess.walk(&[1, 3, 6, 1, 2, 1]).collect::<Result<_, _>>().unwrap();
+    assert_eq!(items.len(), 6);
+    assert_eq!(sess.bulkwalk(SYSTEM, 5).next(), Some(Err(SnmpError::UnsupportedVersion)));
+}
+
+#[test]
+fn agent_loop_is_detected() {
+    // Always answers with the same object.
+    let addr = spawn_agent(|req| {
+        let (_, community, pdu_bytes) = read_community_message(req);
+        let (_, req_id) = read_pdu_header(pdu_bytes);
+        let resp = pdu(0xa2, req_id, 0, 0, &[varbind(&[1, 3, 6, 1, 2, 1, 1, 1, 0], int(1))]);
+        Some(community_message(1, community, resp))
+    });
+    let mut sess = session(addr);
+    let items: Vec<_> = sess.walk(SYSTEM).collect();
+    assert_eq!(items.len(), 2);
+    assert!(items[0].is_ok());
+    assert_eq!(items[1], Err(SnmpError::NonIncreasingOid));
+    let items: Vec<_> = sess.bulkwalk(SYSTEM, 3).collect();
+    assert_eq!(items.len(), 2);
+    assert_eq!(items[1], Err(SnmpError::NonIncreasingOid));
+}
+
+#[test]
+fn error_status_is_surfaced() {
+    let addr = spawn_agent(|req| {
+        let (_, community, pdu_bytes) = read_community_message(req);
+        let (_, req_id) = read_pdu_header(pdu_bytes);
+        Some(community_message(1, community, pdu(0xa2, req_id, 5, 1, &[varbind(SYSTEM, null())])))
+    });
+    let mut sess = session(addr);
+    let items: Vec<_> = sess.walk(SYSTEM).collect();
+    assert_eq!(items, [Err(SnmpError::ErrorStatus { status: 5, index: 1 })]);
+}